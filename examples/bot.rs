@@ -0,0 +1,36 @@
+// Headless bot benchmark: flies a few runs with the built-in autopilot as fast as possible
+//
+//     cargo run --release --example bot
+
+use std::time::Instant;
+
+use floopybirb::agent::{Agent, Env, HeuristicPilot};
+
+const EPISODES: u64 = 5;
+// Give up on a run after this many ticks (10 minutes of game time)
+const MAX_TICKS: u32 = 60 * 60 * 10;
+
+fn main() {
+    let mut env = Env::new();
+    let mut pilot = HeuristicPilot;
+    let started = Instant::now();
+    let mut total_ticks = 0u64;
+
+    for episode in 0..EPISODES {
+        let mut obs = env.reset(Some(episode));
+        while obs.alive && obs.tick < MAX_TICKS {
+            obs = env.step(pilot.act(&obs)).observation;
+        }
+        total_ticks += obs.tick as u64;
+        println!(
+            "seed {episode}: score {} after {} ticks",
+            obs.score, obs.tick
+        );
+    }
+
+    let secs = started.elapsed().as_secs_f64();
+    println!(
+        "{total_ticks} ticks in {secs:.2}s ({:.0}x realtime)",
+        total_ticks as f64 / 60.0 / secs
+    );
+}
@@ -0,0 +1,149 @@
+// In-game achievements, unlocked from the score, session stats and streaks
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::streak::StreakMilestone;
+use crate::{GameState, RunRanking, Score, Stats};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Achievement {
+    FirstPipe,
+    TenPipes,
+    TwentyFivePipes,
+    FiftyPipes,
+    HundredPipes,
+    FrequentFlyer,
+    HotStreak,
+    Untouchable,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 8] = [
+        Achievement::FirstPipe,
+        Achievement::TenPipes,
+        Achievement::TwentyFivePipes,
+        Achievement::FiftyPipes,
+        Achievement::HundredPipes,
+        Achievement::FrequentFlyer,
+        Achievement::HotStreak,
+        Achievement::Untouchable,
+    ];
+
+    // Stable identifier, shared with platform integrations (e.g. Steam API names)
+    #[cfg_attr(not(feature = "steamworks"), allow(dead_code))]
+    pub fn id(self) -> &'static str {
+        match self {
+            Achievement::FirstPipe => "FIRST_PIPE",
+            Achievement::TenPipes => "SCORE_10",
+            Achievement::TwentyFivePipes => "SCORE_25",
+            Achievement::FiftyPipes => "SCORE_50",
+            Achievement::HundredPipes => "SCORE_100",
+            Achievement::FrequentFlyer => "RUNS_50",
+            Achievement::HotStreak => "STREAK_25",
+            Achievement::Untouchable => "STREAK_50",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::FirstPipe => "First Pipe",
+            Achievement::TenPipes => "Getting the Hang of It",
+            Achievement::TwentyFivePipes => "Birb Brain",
+            Achievement::FiftyPipes => "Pipe Dream",
+            Achievement::HundredPipes => "Centurion",
+            Achievement::FrequentFlyer => "Frequent Flyer",
+            Achievement::HotStreak => "Hot Streak",
+            Achievement::Untouchable => "Untouchable",
+        }
+    }
+
+    fn is_earned(self, score: u32, stats: &Stats) -> bool {
+        match self {
+            Achievement::FirstPipe => score >= 1,
+            Achievement::TenPipes => score >= 10,
+            Achievement::TwentyFivePipes => score >= 25,
+            Achievement::FiftyPipes => score >= 50,
+            Achievement::HundredPipes => score >= 100,
+            Achievement::FrequentFlyer => stats.runs_played >= 50,
+            // See check_streak_achievements
+            Achievement::HotStreak | Achievement::Untouchable => false,
+        }
+    }
+
+    // Earned when the streak reaches this many pipes
+    fn streak(self) -> Option<u32> {
+        match self {
+            Achievement::HotStreak => Some(25),
+            Achievement::Untouchable => Some(50),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct UnlockedAchievements(pub HashSet<Achievement>);
+
+#[derive(Event)]
+pub struct AchievementUnlocked(pub Achievement);
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UnlockedAchievements>()
+            .add_event::<AchievementUnlocked>()
+            .add_systems(
+                Update,
+                (
+                    check_achievements.run_if(in_state(GameState::Playing)),
+                    check_streak_achievements,
+                    announce_achievements,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn check_achievements(
+    ranking: RunRanking,
+    score: Res<Score>,
+    stats: Res<Stats>,
+    mut unlocked: ResMut<UnlockedAchievements>,
+    mut events: EventWriter<AchievementUnlocked>,
+) {
+    if !ranking.ranked() || (!score.is_changed() && !stats.is_changed()) {
+        return;
+    }
+    for achievement in Achievement::ALL {
+        if !unlocked.0.contains(&achievement) && achievement.is_earned(score.0, &stats) {
+            unlocked.0.insert(achievement);
+            events.send(AchievementUnlocked(achievement));
+        }
+    }
+}
+
+fn check_streak_achievements(
+    ranking: RunRanking,
+    mut milestones: EventReader<StreakMilestone>,
+    mut unlocked: ResMut<UnlockedAchievements>,
+    mut events: EventWriter<AchievementUnlocked>,
+) {
+    for milestone in milestones.read() {
+        if !ranking.ranked() {
+            continue;
+        }
+        for achievement in Achievement::ALL {
+            if achievement.streak() == Some(milestone.streak) && unlocked.0.insert(achievement) {
+                events.send(AchievementUnlocked(achievement));
+            }
+        }
+    }
+}
+
+fn announce_achievements(mut events: EventReader<AchievementUnlocked>) {
+    for AchievementUnlocked(achievement) in events.read() {
+        info!("Achievement unlocked: {}", achievement.name());
+    }
+}
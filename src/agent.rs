@@ -0,0 +1,329 @@
+// Programmatic control for scripted bots and reinforcement learning experiments.
+//
+// Two ways in:
+// - implement `Agent` and add `AgentPlugin` to let it fly the bird in the normal game,
+//   in place of the built-in autopilot
+// - use `Env` to step a headless simulation one fixed tick at a time, as fast as
+//   the CPU allows
+//
+// Either way the agent sees an `Observation` every fixed tick and answers with an
+// `Action`, which goes through the same buffered flap input as the keyboard.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bevy::audio::AudioPlugin;
+use bevy::gilrs::GilrsPlugin;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::render::settings::{RenderCreation, WgpuSettings};
+use bevy::render::RenderPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+
+use crate::autopilot::Autopilot;
+use crate::{
+    Bird, FixedTransform, FlapInput, GamePlugin, GameState, LeadBird, NextRunSeed, Pipe,
+    RunModifiers, RunTick, Score, BIRD_SIZE, FIXED_HZ, WINDOW_H,
+};
+
+// The next gap the bird has to fly through
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gap {
+    // Horizontal distance from the bird's center to the pipes' center (px)
+    pub dx: f32,
+    pub center_y: f32,
+    pub height: f32,
+}
+
+// What an agent sees each fixed tick. Positions are in world pixels with the
+// origin at the screen center and y pointing up; a reverse run is mirrored, so
+// the course always runs to the right.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Observation {
+    // Fixed ticks since the run started
+    pub tick: u32,
+    pub bird_y: f32,
+    // px / s, positive is up
+    pub bird_vy: f32,
+    pub next_gap: Option<Gap>,
+    pub score: u32,
+    pub alive: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Idle,
+    Flap,
+}
+
+pub trait Agent: Send + Sync + 'static {
+    fn act(&mut self, observation: &Observation) -> Action;
+}
+
+// The built-in autopilot: flap whenever we're below the middle of the next gap
+// and not already rising
+pub struct HeuristicPilot;
+
+impl Agent for HeuristicPilot {
+    fn act(&mut self, observation: &Observation) -> Action {
+        let target = observation
+            .next_gap
+            .map_or(0.0, |gap| gap.center_y - gap.height * 0.25);
+        if observation.bird_y < target && observation.bird_vy < 0.0 {
+            Action::Flap
+        } else {
+            Action::Idle
+        }
+    }
+}
+
+// --------------------------------------------
+// Observations
+// --------------------------------------------
+
+fn next_gap(bird_x: f32, pipes: &[(Vec3, Vec2, bool)]) -> Option<Gap> {
+    // Nearest column whose right edge is still ahead of the bird's tail
+    let bird_left = bird_x - BIRD_SIZE.x * 0.5;
+    let column_x = pipes
+        .iter()
+        .filter(|(pos, size, _)| pos.x + size.x * 0.5 >= bird_left)
+        .map(|(pos, _, _)| pos.x)
+        .min_by(f32::total_cmp)?;
+
+    let mut gap_top = None;
+    let mut gap_bottom = None;
+    for (pos, size, is_top) in pipes.iter().filter(|(pos, ..)| pos.x == column_x) {
+        if *is_top {
+            gap_top = Some(pos.y - size.y * 0.5);
+        } else {
+            gap_bottom = Some(pos.y + size.y * 0.5);
+        }
+    }
+    // A pipe blasted by a laser leaves the gap open to the edge of the screen
+    let (top, bottom) = (
+        gap_top.unwrap_or(WINDOW_H * 0.5),
+        gap_bottom.unwrap_or(-WINDOW_H * 0.5),
+    );
+    Some(Gap {
+        dx: column_x - bird_x,
+        center_y: (top + bottom) * 0.5,
+        height: top - bottom,
+    })
+}
+
+pub(crate) fn observe(
+    tick: u32,
+    score: u32,
+    alive: bool,
+    bird: Option<(Vec3, f32)>,
+    pipes: &[(Vec3, Vec2, bool)],
+    // Modifiers::facing of the run
+    facing: f32,
+) -> Observation {
+    let (bird_pos, bird_vy) = bird.unwrap_or_default();
+    // Mirror a reverse run, so agents only ever see the one direction
+    let mirror = Vec3::new(facing, 1.0, 1.0);
+    let pipes: Vec<_> = pipes
+        .iter()
+        .map(|&(pos, size, is_top)| (pos * mirror, size, is_top))
+        .collect();
+    Observation {
+        tick,
+        bird_y: bird_pos.y,
+        bird_vy,
+        next_gap: next_gap(bird_pos.x * facing, &pipes),
+        score,
+        alive,
+    }
+}
+
+// Collect pipe positions and sizes from the simulated (not interpolated) transforms
+pub(crate) fn pipe_shapes<'a>(
+    pipes: impl Iterator<Item = (&'a FixedTransform, &'a Pipe)>,
+) -> Vec<(Vec3, Vec2, bool)> {
+    pipes
+        .map(|(tf, pipe)| (tf.current.translation, pipe.size, pipe.is_top))
+        .collect()
+}
+
+// --------------------------------------------
+// In-game agents
+// --------------------------------------------
+
+// Flies the bird in a normal game with the given agent, which also plays the
+// menu demo. B hands control back to the player.
+pub struct AgentPlugin {
+    agent: Mutex<Option<Box<dyn Agent>>>,
+}
+
+impl AgentPlugin {
+    pub fn new(agent: impl Agent) -> Self {
+        Self {
+            agent: Mutex::new(Some(Box::new(agent))),
+        }
+    }
+}
+
+impl Plugin for AgentPlugin {
+    fn build(&self, app: &mut App) {
+        let agent = self
+            .agent
+            .lock()
+            .unwrap()
+            .take()
+            .expect("AgentPlugin can only be added once");
+        app.insert_resource(Autopilot::new(agent, true));
+    }
+}
+
+// Agent driven from elsewhere (another thread, a Python bridge...) in lockstep:
+// each tick it sends an observation and waits for the matching action. If the
+// other side hangs up the bird just stops flapping.
+pub struct ChannelAgent {
+    observations: Sender<Observation>,
+    actions: Mutex<Receiver<Action>>,
+}
+
+impl ChannelAgent {
+    // Returns the agent plus the other ends of its channels
+    pub fn new() -> (Self, Receiver<Observation>, Sender<Action>) {
+        let (obs_tx, obs_rx) = mpsc::channel();
+        let (action_tx, action_rx) = mpsc::channel();
+        let agent = Self {
+            observations: obs_tx,
+            actions: Mutex::new(action_rx),
+        };
+        (agent, obs_rx, action_tx)
+    }
+}
+
+impl Agent for ChannelAgent {
+    fn act(&mut self, observation: &Observation) -> Action {
+        if self.observations.send(*observation).is_err() {
+            return Action::Idle;
+        }
+        let actions = self.actions.get_mut().unwrap();
+        actions.recv().unwrap_or(Action::Idle)
+    }
+}
+
+// --------------------------------------------
+// Headless environment
+// --------------------------------------------
+
+pub struct Step {
+    pub observation: Observation,
+    // +1 per pipe passed this step, -1 for crashing
+    pub reward: f32,
+    pub done: bool,
+}
+
+// Headless, deterministic game: no window, audio or rendering, and every `step`
+// advances exactly one fixed tick regardless of wall-clock time
+pub struct Env {
+    app: App,
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Env {
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins(
+            DefaultPlugins
+                .build()
+                .disable::<WinitPlugin>()
+                .disable::<AudioPlugin>()
+                .disable::<GilrsPlugin>()
+                // Leave logging to the host program
+                .disable::<LogPlugin>()
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                })
+                .set(RenderPlugin {
+                    render_creation: RenderCreation::Automatic(WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }),
+                    ..default()
+                }),
+        )
+        .add_plugins(GamePlugin)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / FIXED_HZ,
+        )));
+        app.finish();
+        app.cleanup();
+        // Run startup systems
+        app.update();
+        Self { app }
+    }
+
+    // Start a new run (on the given course, or a random one) and return its first
+    // observation
+    pub fn reset(&mut self, seed: Option<u64>) -> Observation {
+        // OnEnter(Playing) only runs on a real transition, so leave the run first
+        if *self.app.world().resource::<State<GameState>>() == GameState::Playing {
+            self.set_state(GameState::Menu);
+        }
+        self.app.world_mut().resource_mut::<NextRunSeed>().0 = seed;
+        self.set_state(GameState::Playing);
+        self.observation()
+    }
+
+    // Apply an action and advance one fixed tick. Call `reset` once `done`.
+    pub fn step(&mut self, action: Action) -> Step {
+        let score_before = self.app.world().resource::<Score>().0;
+        if action == Action::Flap {
+            self.app.world_mut().resource_mut::<FlapInput>().requested = true;
+        }
+        self.app.update();
+
+        let observation = self.observation();
+        let passed = observation.score.saturating_sub(score_before) as f32;
+        let crashed = if observation.alive { 0.0 } else { 1.0 };
+        Step {
+            observation,
+            reward: passed - crashed,
+            done: !observation.alive,
+        }
+    }
+
+    pub fn observation(&mut self) -> Observation {
+        let world = self.app.world_mut();
+        let tick = world.resource::<RunTick>().0;
+        let score = world.resource::<Score>().0;
+        // A crash is only visible as a pending state change until the next update
+        let alive = *world.resource::<State<GameState>>() == GameState::Playing
+            && !matches!(
+                world.resource::<NextState<GameState>>(),
+                NextState::Pending(GameState::GameOver)
+            );
+        let bird = world
+            .query_filtered::<(&FixedTransform, &Bird), LeadBird>()
+            .get_single(world)
+            .ok()
+            .map(|(tf, bird)| (tf.current.translation, bird.vy));
+        let mut pipes_q = world.query::<(&FixedTransform, &Pipe)>();
+        let pipes = pipe_shapes(pipes_q.iter(world));
+        let facing = world.resource::<RunModifiers>().0.facing();
+        observe(tick, score, alive, bird, &pipes, facing)
+    }
+
+    fn set_state(&mut self, state: GameState) {
+        self.app
+            .world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(state);
+        self.app.update();
+    }
+}
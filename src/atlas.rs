@@ -0,0 +1,146 @@
+// The sprite sheet: every textured game sprite is cut from one image, described
+// by a layout file next to it, so they all share one texture bind and a skin or
+// theme swaps them all by replacing two files.
+//
+//   textures/atlas.txt   `key = value` lines:
+//                          size = sheet width, height in pixels
+//                          <region> = x, y, width, height
+//
+// Animations are the regions sharing a name prefix, in file order: the bird
+// flaps through bird_1, bird_2, bird_3. New sprites (pipe caps, digits, medals,
+// icons) get a region in the same sheet.
+//
+// The sheet is still called bird.png, its first tenant, so existing skin mods
+// keep working. A mod that rearranges the sheet ships its own atlas.txt.
+
+use bevy::prelude::*;
+
+use crate::mods::Mods;
+
+const SHEET_PATH: &str = "textures/bird.png";
+const LAYOUT_PATH: &str = "textures/atlas.txt";
+
+#[derive(Resource)]
+pub struct SpriteAtlas {
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    // Region names, by index into the layout
+    names: Vec<String>,
+}
+
+impl SpriteAtlas {
+    // Load the sheet and layout, from the first enabled mod that has them
+    pub fn load(
+        mods: &Mods,
+        asset_server: &AssetServer,
+        layouts: &mut Assets<TextureAtlasLayout>,
+    ) -> Self {
+        let (layout, names) = read_layout(mods);
+        Self {
+            texture: asset_server.load(mods.resolve(SHEET_PATH)),
+            layout: layouts.add(layout),
+            names,
+        }
+    }
+
+    // Re-read the layout after the sheet or its layout file changed under a
+    // running game (see hot_reload.rs), keeping the same layout handle so every
+    // sprite cut from it follows. If the sheet is no longer the size the layout
+    // says, the regions are scaled to fit: a sheet redrawn at twice the size
+    // still cuts the same way.
+    pub fn reload_layout(
+        &mut self,
+        mods: &Mods,
+        sheet_size: UVec2,
+        layouts: &mut Assets<TextureAtlasLayout>,
+    ) {
+        let (mut layout, names) = read_layout(mods);
+        if layout.size != sheet_size && layout.size.cmpgt(UVec2::ZERO).all() {
+            warn!(
+                "{LAYOUT_PATH} is for a {}x{} sheet but it's {}x{} now; scaling the regions",
+                layout.size.x, layout.size.y, sheet_size.x, sheet_size.y
+            );
+            let scale = sheet_size.as_vec2() / layout.size.as_vec2();
+            for rect in &mut layout.textures {
+                *rect = URect::from_corners(
+                    (rect.min.as_vec2() * scale).round().as_uvec2(),
+                    (rect.max.as_vec2() * scale).round().as_uvec2(),
+                );
+            }
+            layout.size = sheet_size;
+        }
+        match layouts.get_mut(&self.layout) {
+            Some(current) => *current = layout,
+            None => self.layout = layouts.add(layout),
+        }
+        self.names = names;
+    }
+
+    // Indices of the regions named `<prefix>_...`, in file order
+    pub fn frames(&self, prefix: &str) -> Vec<usize> {
+        self.names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| {
+                name.strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('_'))
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+// Where the layout comes from: the first enabled mod that has one, or the game
+pub fn layout_file(mods: &Mods) -> std::path::PathBuf {
+    mods.resolve_file(LAYOUT_PATH)
+}
+
+fn read_layout(mods: &Mods) -> (TextureAtlasLayout, Vec<String>) {
+    let path = layout_file(mods);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => parse_layout(&text),
+        Err(err) => {
+            error!("Cannot read sprite layout {}: {err}", path.display());
+            (TextureAtlasLayout::new_empty(UVec2::ONE), Vec::new())
+        }
+    }
+}
+
+fn parse_layout(text: &str) -> (TextureAtlasLayout, Vec<String>) {
+    let mut size = UVec2::ONE;
+    let mut regions = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = line.split_once('=').and_then(|(key, value)| {
+            let numbers: Vec<u32> = value
+                .split(',')
+                .map(|v| v.trim().parse().ok())
+                .collect::<Option<_>>()?;
+            Some((key.trim(), numbers))
+        });
+        match parsed {
+            Some(("size", numbers)) if numbers.len() == 2 => {
+                size = UVec2::new(numbers[0], numbers[1]);
+            }
+            Some((name, numbers)) if numbers.len() == 4 => {
+                let min = UVec2::new(numbers[0], numbers[1]);
+                let rect = URect::from_corners(min, min + UVec2::new(numbers[2], numbers[3]));
+                regions.push((name.to_string(), rect));
+            }
+            _ => warn!("{LAYOUT_PATH}: ignoring line \"{line}\""),
+        }
+    }
+
+    let mut layout = TextureAtlasLayout::new_empty(size);
+    let names = regions
+        .into_iter()
+        .map(|(name, rect)| {
+            layout.add_texture(rect);
+            name
+        })
+        .collect();
+    (layout, names)
+}
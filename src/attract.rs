@@ -0,0 +1,143 @@
+// Attract mode: once the main menu has sat untouched for IDLE_SECS, the best
+// saved replay (or the bundled demo.fbr, before any are saved) plays behind the
+// title. Any key, click or button, or the replay coming to an end, goes back
+// to the normal menu.
+
+use bevy::prelude::*;
+
+use crate::branding::Branding;
+use crate::mods::Mods;
+use crate::replay::{best_replay, start_playback};
+use crate::toast::ShowToast;
+use crate::{GameState, WINDOW_W};
+
+const IDLE_SECS: f32 = 20.0;
+
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuIdle>()
+            .add_systems(OnEnter(GameState::Menu), reset_idle)
+            .add_systems(Update, start_attract.run_if(in_state(GameState::Menu)))
+            .add_systems(OnEnter(GameState::Replay), show_attract_title)
+            .add_systems(Update, end_attract.run_if(resource_exists::<Attract>))
+            .add_systems(OnExit(GameState::Replay), stop_attract);
+    }
+}
+
+// Seconds the menu has gone without input
+#[derive(Resource, Default)]
+struct MenuIdle(f32);
+
+// Present while the replay being watched is the attract sequence
+#[derive(Resource)]
+struct Attract;
+
+#[derive(Component)]
+struct AttractUI;
+
+fn any_input(
+    keys: &ButtonInput<KeyCode>,
+    mouse: &ButtonInput<MouseButton>,
+    pads: &ButtonInput<GamepadButton>,
+) -> bool {
+    keys.get_just_pressed().next().is_some()
+        || mouse.get_just_pressed().next().is_some()
+        || pads.get_just_pressed().next().is_some()
+}
+
+fn reset_idle(mut idle: ResMut<MenuIdle>) {
+    idle.0 = 0.0;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_attract(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    pads: Res<ButtonInput<GamepadButton>>,
+    mods: Res<Mods>,
+    mut idle: ResMut<MenuIdle>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if any_input(&keys, &mouse, &pads) {
+        idle.0 = 0.0;
+        return;
+    }
+    idle.0 += time.delta_seconds();
+    // Something else is already leaving the menu
+    if idle.0 < IDLE_SECS || matches!(*next_state, NextState::Pending(_)) {
+        return;
+    }
+    // With nothing to show, the bot's demo carries on for another while
+    idle.0 = 0.0;
+    if let Some(replay) = best_replay(&mods) {
+        commands.insert_resource(Attract);
+        start_playback(replay, &mut commands, &mut next_state, &mut toasts);
+    }
+}
+
+// The title over the replay, in place of the menu
+fn show_attract_title(
+    mut commands: Commands,
+    attract: Option<Res<Attract>>,
+    branding: Res<Branding>,
+) {
+    if attract.is_none() {
+        return;
+    }
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                format!("{}\n", branding.logo),
+                TextStyle {
+                    font_size: 56.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+            TextSection::new(
+                "Press any key",
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(140.0),
+            left: Val::Px(WINDOW_W * 0.5 - 160.0),
+            ..default()
+        }),
+        AttractUI,
+    ));
+}
+
+// Straight back to the menu, skipping the game over screen when the replay ends
+fn end_attract(
+    state: Res<State<GameState>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    pads: Res<ButtonInput<GamepadButton>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if *state.get() != GameState::Replay {
+        return;
+    }
+    let ended = matches!(*next_state, NextState::Pending(GameState::GameOver));
+    if ended || any_input(&keys, &mouse, &pads) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn stop_attract(mut commands: Commands, ui: Query<Entity, With<AttractUI>>) {
+    commands.remove_resource::<Attract>();
+    for e in &ui {
+        commands.entity(e).despawn_recursive();
+    }
+}
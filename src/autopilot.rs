@@ -0,0 +1,164 @@
+// Autopilot: an agent flies the bird as a live demo behind the menu, and can take
+// over during a run with B ("watch the bot").
+//
+// The built-in pilot is `agent::HeuristicPilot`; `agent::AgentPlugin` swaps in
+// any other `Agent`. Runs the autopilot touched don't count towards the best
+// score, stats or achievements.
+
+use bevy::prelude::*;
+
+use crate::agent::{observe, pipe_shapes, Action, Agent, HeuristicPilot};
+use crate::toast::ShowToast;
+use crate::{
+    advance_run_tick, handle_flap_input, Bird, FixedTransform, FlapInput, GameState, LeadBird,
+    Pipe, RunModifiers, RunTick, Score,
+};
+
+pub struct AutopilotPlugin;
+
+impl Plugin for AutopilotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autopilot>()
+            .add_systems(OnEnter(GameState::Playing), start_run)
+            .add_systems(
+                FixedUpdate,
+                drive_autopilot
+                    .after(advance_run_tick)
+                    .before(handle_flap_input)
+                    .run_if(autopilot_flying),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_autopilot.run_if(in_state(GameState::Playing)),
+                    update_autopilot_banner,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct Autopilot {
+    agent: Box<dyn Agent>,
+    // Flying the bird during runs, not just the menu demo
+    engaged: bool,
+    used_this_run: bool,
+}
+
+impl Autopilot {
+    pub fn new(agent: Box<dyn Agent>, engaged: bool) -> Self {
+        Self {
+            agent,
+            engaged,
+            used_this_run: engaged,
+        }
+    }
+
+    // The autopilot flew at least part of the current run
+    pub fn assisted(&self) -> bool {
+        self.used_this_run
+    }
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self::new(Box::new(HeuristicPilot), false)
+    }
+}
+
+#[derive(Component)]
+struct AutopilotBanner;
+
+fn autopilot_flying(state: Res<State<GameState>>, autopilot: Res<Autopilot>) -> bool {
+    match state.get() {
+        GameState::Menu => true,
+        GameState::Playing => autopilot.engaged,
+        GameState::Replay
+        | GameState::GameOver
+        | GameState::Mods
+        | GameState::Settings
+        | GameState::ChallengeEntry
+        | GameState::Lobby
+        | GameState::Tournament
+        | GameState::HotSeat
+        | GameState::Presets
+        | GameState::Shop
+        | GameState::Story => false,
+    }
+}
+
+fn start_run(mut autopilot: ResMut<Autopilot>) {
+    autopilot.used_this_run = autopilot.engaged;
+}
+
+fn drive_autopilot(
+    mut autopilot: ResMut<Autopilot>,
+    mut flap_input: ResMut<FlapInput>,
+    tick: Res<RunTick>,
+    score: Res<Score>,
+    bird_q: Query<(&FixedTransform, &Bird), LeadBird>,
+    pipes_q: Query<(&FixedTransform, &Pipe)>,
+    run_modifiers: Res<RunModifiers>,
+) {
+    let bird = bird_q
+        .get_single()
+        .ok()
+        .map(|(tf, bird)| (tf.current.translation, bird.vy));
+    let pipes = pipe_shapes(pipes_q.iter());
+    let facing = run_modifiers.0.facing();
+    let observation = observe(tick.0, score.0, true, bird, &pipes, facing);
+    if autopilot.agent.act(&observation) == Action::Flap {
+        flap_input.requested = true;
+    }
+}
+
+fn toggle_autopilot(
+    input: Res<ButtonInput<KeyCode>>,
+    mut autopilot: ResMut<Autopilot>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !input.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+    autopilot.engaged = !autopilot.engaged;
+    if autopilot.engaged {
+        autopilot.used_this_run = true;
+        toasts.send(ShowToast("Autopilot on - this run won't count".to_string()));
+    } else {
+        toasts.send(ShowToast("Autopilot off".to_string()));
+    }
+}
+
+fn update_autopilot_banner(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    autopilot: Res<Autopilot>,
+    banners: Query<Entity, With<AutopilotBanner>>,
+) {
+    let show = autopilot.engaged && *state.get() == GameState::Playing;
+    match (show, banners.get_single()) {
+        (true, Err(_)) => {
+            commands.spawn((
+                TextBundle::from_section(
+                    "AUTOPILOT",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::srgb(0.3, 0.6, 1.0),
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    // Below the replay banner
+                    top: Val::Px(36.0),
+                    left: Val::Px(10.0),
+                    ..default()
+                }),
+                AutopilotBanner,
+            ));
+        }
+        (false, Ok(e)) => commands.entity(e).despawn_recursive(),
+        _ => {}
+    }
+}
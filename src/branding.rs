@@ -0,0 +1,152 @@
+// Branding: the window title, window icon and title-screen logo, read from a
+// config file so forks and mod packs can rebrand the game without touching code.
+// A mod ships its own branding.txt to replace the game's, like any other asset.
+//
+//   branding.txt   `key = value` lines, all optional:
+//                    title = window title
+//                    icon = image under assets/ for the window icon
+//                    logo = title-screen text
+//                    logo_image = image under assets/ drawn instead of the text
+
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy::winit::WinitWindows;
+use winit::window::Icon;
+
+use crate::mods::Mods;
+
+const BRANDING_PATH: &str = "branding.txt";
+const DEFAULT_NAME: &str = "Floopy Birb";
+
+pub struct BrandingPlugin;
+
+impl Plugin for BrandingPlugin {
+    fn build(&self, app: &mut App) {
+        // Mods are found when ModsPlugin is built, before this
+        let branding = Branding::load(app.world().resource::<Mods>());
+        app.insert_resource(branding)
+            .add_systems(Startup, (set_window_title, load_window_icon))
+            .add_systems(
+                Update,
+                set_window_icon.run_if(resource_exists::<PendingIcon>),
+            );
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Branding {
+    pub title: String,
+    // Asset paths
+    pub icon: Option<String>,
+    pub logo: String,
+    pub logo_image: Option<String>,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            title: DEFAULT_NAME.to_string(),
+            icon: None,
+            logo: DEFAULT_NAME.to_string(),
+            logo_image: None,
+        }
+    }
+}
+
+impl Branding {
+    fn load(mods: &Mods) -> Self {
+        let path = mods.resolve_file(BRANDING_PATH);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text),
+            Err(err) => {
+                warn!("Cannot read branding {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut branding = Self::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warn!("{BRANDING_PATH}: ignoring line \"{line}\"");
+                continue;
+            };
+            let value = value.trim().to_string();
+            match key.trim() {
+                "title" => branding.title = value,
+                "icon" => branding.icon = Some(value),
+                "logo" => branding.logo = value,
+                "logo_image" => branding.logo_image = Some(value),
+                other => warn!("{BRANDING_PATH}: unknown key \"{other}\""),
+            }
+        }
+        branding
+    }
+}
+
+// The icon image, until it has loaded and been handed to the window
+#[derive(Resource)]
+struct PendingIcon(Handle<Image>);
+
+fn set_window_title(branding: Res<Branding>, mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+    for mut window in &mut windows {
+        window.title.clone_from(&branding.title);
+    }
+}
+
+fn load_window_icon(
+    mut commands: Commands,
+    branding: Res<Branding>,
+    mods: Res<Mods>,
+    asset_server: Res<AssetServer>,
+) {
+    if let Some(icon) = &branding.icon {
+        commands.insert_resource(PendingIcon(asset_server.load(mods.resolve(icon))));
+    }
+}
+
+// Bevy has no window icon setting of its own, so this goes to winit directly
+fn set_window_icon(
+    mut commands: Commands,
+    pending: Res<PendingIcon>,
+    asset_server: Res<AssetServer>,
+    images: Res<Assets<Image>>,
+    winit_windows: NonSend<WinitWindows>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+) {
+    if matches!(asset_server.load_state(&pending.0), LoadState::Failed(_)) {
+        error!("Cannot load the window icon");
+        commands.remove_resource::<PendingIcon>();
+        return;
+    }
+    let Some(image) = images.get(&pending.0) else {
+        return;
+    };
+    // The window shows up a frame or two after startup
+    let Some(window) = windows
+        .get_single()
+        .ok()
+        .and_then(|e| winit_windows.get_window(e))
+    else {
+        return;
+    };
+    commands.remove_resource::<PendingIcon>();
+    let rgba = match image.clone().try_into_dynamic() {
+        Ok(dynamic) => dynamic.into_rgba8(),
+        Err(err) => {
+            error!("Cannot use the window icon: {err}");
+            return;
+        }
+    };
+    let (width, height) = rgba.dimensions();
+    match Icon::from_rgba(rgba.into_raw(), width, height) {
+        Ok(icon) => window.set_window_icon(Some(icon)),
+        Err(err) => error!("Cannot use the window icon: {err}"),
+    }
+}
@@ -0,0 +1,163 @@
+// Game-state broadcaster for stream overlays (enabled with the `broadcast` cargo feature)
+//
+// Serves WebSocket clients on localhost and pushes a JSON snapshot to all of
+// them whenever something changes (up to SNAPSHOTS_PER_SEC while flying):
+//
+//   {"state":"playing","score":3,"best":12,"tick":420,
+//    "bird":{"x":-150.0,"y":31.5,"vy":-120.3},"events":["flap","pipe"]}
+//
+// `events` lists what happened since the previous snapshot: "flap", "pipe",
+// "crash" and "achievement:<id>".
+
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use tungstenite::{Message, WebSocket};
+
+use crate::achievements::AchievementUnlocked;
+use crate::{
+    BestScore, Bird, Crashed, FixedTransform, Flapped, GameState, LeadBird, PipePassed, RunTick,
+    Score,
+};
+
+const BROADCAST_ADDR: &str = "127.0.0.1:7878";
+const SNAPSHOTS_PER_SEC: f32 = 30.0;
+// Drop clients that stop reading rather than stall every other overlay
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+// And clients that connect but never finish the handshake, which would keep
+// anyone else from connecting
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct BroadcastPlugin;
+
+impl Plugin for BroadcastPlugin {
+    fn build(&self, app: &mut App) {
+        let listener = match TcpListener::bind(BROADCAST_ADDR) {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!("Cannot start state broadcast on {BROADCAST_ADDR}: {err}");
+                return;
+            }
+        };
+        info!("Broadcasting game state on ws://{BROADCAST_ADDR}");
+
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::channel();
+        let accepting = clients.clone();
+        std::thread::spawn(move || accept_clients(listener, accepting));
+        std::thread::spawn(move || send_snapshots(rx, clients));
+
+        app.insert_resource(Broadcaster {
+            tx,
+            timer: Timer::from_seconds(1.0 / SNAPSHOTS_PER_SEC, TimerMode::Repeating),
+            last_sent: None,
+            pending_events: Vec::new(),
+        })
+        .add_systems(Update, (collect_events, broadcast_snapshot).chain());
+    }
+}
+
+type Clients = Arc<Mutex<Vec<WebSocket<TcpStream>>>>;
+
+#[derive(Resource)]
+struct Broadcaster {
+    tx: Sender<String>,
+    timer: Timer,
+    // Last snapshot without its events, to skip sending duplicates
+    last_sent: Option<String>,
+    pending_events: Vec<String>,
+}
+
+// --------------------------------------------
+// Server threads
+// --------------------------------------------
+
+fn accept_clients(listener: TcpListener, clients: Clients) {
+    for stream in listener.incoming().flatten() {
+        let _ = stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT));
+        let _ = stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT));
+        match tungstenite::accept(stream) {
+            Ok(socket) => clients.lock().unwrap().push(socket),
+            Err(err) => warn!("Broadcast client handshake failed: {err}"),
+        }
+    }
+}
+
+fn send_snapshots(rx: Receiver<String>, clients: Clients) {
+    for json in rx {
+        let mut clients = clients.lock().unwrap();
+        clients.retain_mut(|socket| socket.send(Message::text(json.clone())).is_ok());
+    }
+}
+
+// --------------------------------------------
+// Snapshots
+// --------------------------------------------
+
+fn collect_events(
+    mut broadcaster: ResMut<Broadcaster>,
+    mut flapped: EventReader<Flapped>,
+    mut passed: EventReader<PipePassed>,
+    mut crashed: EventReader<Crashed>,
+    mut achievements: EventReader<AchievementUnlocked>,
+) {
+    let events = flapped
+        .read()
+        .map(|_| "flap".to_string())
+        .chain(passed.read().map(|_| "pipe".to_string()))
+        .chain(crashed.read().map(|_| "crash".to_string()))
+        .chain(
+            achievements
+                .read()
+                .map(|AchievementUnlocked(a)| format!("achievement:{}", a.id())),
+        );
+    let events: Vec<String> = events.collect();
+    if !events.is_empty() {
+        broadcaster.pending_events.extend(events);
+    }
+}
+
+fn broadcast_snapshot(
+    time: Res<Time>,
+    mut broadcaster: ResMut<Broadcaster>,
+    state: Res<State<GameState>>,
+    score: Res<Score>,
+    best: Res<BestScore>,
+    tick: Res<RunTick>,
+    bird_q: Query<(&FixedTransform, &Bird), LeadBird>,
+) {
+    if !broadcaster.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let bird = match bird_q.get_single() {
+        Ok((tf, bird)) => format!(
+            "{{\"x\":{:.1},\"y\":{:.1},\"vy\":{:.1}}}",
+            tf.current.translation.x, tf.current.translation.y, bird.vy
+        ),
+        Err(_) => "null".to_string(),
+    };
+    let snapshot = format!(
+        "\"state\":\"{}\",\"score\":{},\"best\":{},\"tick\":{},\"bird\":{bird}",
+        format!("{:?}", state.get()).to_lowercase(),
+        score.0,
+        best.0,
+        tick.0,
+    );
+    if broadcaster.pending_events.is_empty() && broadcaster.last_sent.as_ref() == Some(&snapshot) {
+        return;
+    }
+
+    let events: Vec<String> = broadcaster
+        .pending_events
+        .drain(..)
+        .map(|e| format!("\"{e}\""))
+        .collect();
+    let json = format!("{{{snapshot},\"events\":[{}]}}", events.join(","));
+    // Only fails if the sender thread died, and then there's nobody to tell
+    let _ = broadcaster.tx.send(json);
+    broadcaster.last_sent = Some(snapshot);
+}
@@ -0,0 +1,158 @@
+// Fail-clip capture (enabled with the `capture` cargo feature)
+//
+// Keeps the last few seconds of downscaled frames in memory and writes them out
+// as a looping GIF when the bird crashes or F9 is pressed.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
+use bevy::window::PrimaryWindow;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+
+use crate::screenshot::{captures_dir, screenshot_hotkey};
+use crate::toast::ShowToast;
+use crate::Crashed;
+
+const CAPTURE_FPS: u32 = 15;
+const CAPTURE_SECS: u32 = 10;
+const MAX_FRAMES: usize = (CAPTURE_FPS * CAPTURE_SECS) as usize;
+// Frames are stored at 1/CAPTURE_DOWNSCALE of the window size
+const CAPTURE_DOWNSCALE: u32 = 4;
+// 1 = best quality, 30 = fastest; clips are small so lean towards speed
+const GIF_ENCODE_SPEED: i32 = 20;
+
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CaptureTimer(Timer::from_seconds(
+            1.0 / CAPTURE_FPS as f32,
+            TimerMode::Repeating,
+        )))
+        .init_resource::<FrameBuffer>()
+        .add_systems(
+            Update,
+            (
+                // F12 gets the frame's capture when it wants it
+                record_frames.after(screenshot_hotkey),
+                save_clip,
+                finish_clips,
+            ),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct CaptureTimer(Timer);
+
+// Shared with the screenshot callbacks, which run on a task pool thread
+#[derive(Resource, Default, Clone)]
+struct FrameBuffer(Arc<Mutex<VecDeque<RgbaImage>>>);
+
+// A clip being encoded in the background
+#[derive(Component)]
+struct EncodeClip(Task<Result<PathBuf, String>>);
+
+fn record_frames(
+    time: Res<Time>,
+    mut timer: ResMut<CaptureTimer>,
+    window_q: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    frames: Res<FrameBuffer>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Ok(window) = window_q.get_single() else {
+        return;
+    };
+
+    let frames = frames.clone();
+    // Fails only if F12 already claimed this frame, which skips one frame
+    let _ = screenshots.take_screenshot(window, move |img| {
+        let Ok(img) = img.try_into_dynamic() else {
+            return;
+        };
+        let small = image::imageops::thumbnail(
+            &img.to_rgba8(),
+            img.width() / CAPTURE_DOWNSCALE,
+            img.height() / CAPTURE_DOWNSCALE,
+        );
+        let mut buffer = frames.0.lock().unwrap();
+        buffer.push_back(small);
+        if buffer.len() > MAX_FRAMES {
+            buffer.pop_front();
+        }
+    });
+}
+
+fn save_clip(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    mut crashed: EventReader<Crashed>,
+    frames: Res<FrameBuffer>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let died = crashed.read().count() > 0;
+    if !died && !input.just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    let clip: Vec<RgbaImage> = frames.0.lock().unwrap().iter().cloned().collect();
+    if clip.is_empty() {
+        return;
+    }
+
+    // Milliseconds, like screenshots, so a death right after F9 keeps both clips
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S-%3f");
+    let path = captures_dir().join(format!("floopybirb_{timestamp}.gif"));
+    toasts.send(ShowToast("Saving clip...".to_string()));
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        encode_gif(&path, clip).map_err(|err| format!("{}: {err}", path.display()))?;
+        Ok(path)
+    });
+    commands.spawn(EncodeClip(task));
+}
+
+fn encode_gif(path: &Path, clip: Vec<RgbaImage>) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut encoder =
+        GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), GIF_ENCODE_SPEED);
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = Delay::from_numer_denom_ms(1000, CAPTURE_FPS);
+    encoder.encode_frames(
+        clip.into_iter()
+            .map(|frame| Frame::from_parts(frame, 0, 0, delay)),
+    )?;
+    Ok(())
+}
+
+fn finish_clips(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut EncodeClip)>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for (e, mut task) in &mut tasks {
+        let Some(result) = block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+        match result {
+            Ok(path) => toasts.send(ShowToast(format!("Saved {}", path.display()))),
+            Err(err) => {
+                error!("Cannot save clip: {err}");
+                toasts.send(ShowToast("Saving clip failed".to_string()))
+            }
+        };
+        commands.entity(e).despawn();
+    }
+}
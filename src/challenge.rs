@@ -0,0 +1,532 @@
+// Challenge codes: ten characters that pin down a course and its rules, so
+// players can challenge each other to the exact same run. The game over screen
+// shows the code of the run just played; C on the main menu lets you type one
+// in. Every run while a challenge is active (retries included) flies its
+// course, until Escape on the game over screen goes back to the menu. The best
+// score for each code is kept on disk.
+//
+// A code packs 50 bits into Crockford base32, shown as XXXXX-XXXXX:
+//   bits 0-31   course seed
+//   bits 32-33  hitbox preset (the game's difficulty setting), index into Hitbox::ALL
+//   bits 34-45  modifiers (see modifiers.rs)
+//   bits 46-49  check value, so most typos are caught instead of starting a
+//               different course
+// Lowercase letters are fine when typing, I and L read as 1 and O as 0.
+//
+// There's also a weekly challenge, played by pressing Enter on the code screen
+// without typing anything: a course seeded from the ISO week and a modifier set
+// from WEEKLY_ROTATION. It's just a code everyone gets, so it keeps its best the
+// same way.
+//
+// Tab on the code screen lists the challenge presets instead (see presets.rs).
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use chrono::{DateTime, Datelike, Days, Utc};
+
+use crate::modifiers::{Modifier, Modifiers, Rules};
+use crate::photo::PhotoMode;
+use crate::presets::ActivePreset;
+use crate::replay::ReplayPlayback;
+use crate::settings::{Hitbox, Settings};
+use crate::toast::ShowToast;
+use crate::{
+    update_best_score, GameOverUI, GameState, RunModifiers, RunRanking, RunRules, RunSeed, Score,
+    UnrecordedCourse, WINDOW_W,
+};
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const CODE_LEN: usize = 10;
+
+// Modifier sets the weekly challenge cycles through, one per ISO week
+const WEEKLY_ROTATION: [&[Modifier]; 5] = [
+    &[Modifier::Night, Modifier::Wind, Modifier::SmallGap],
+    &[Modifier::FastPipes],
+    &[Modifier::Wind],
+    &[Modifier::Night, Modifier::SmallGap],
+    &[Modifier::Night, Modifier::FastPipes, Modifier::Wind],
+];
+
+pub struct ChallengePlugin;
+
+impl Plugin for ChallengePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ChallengeBests::load())
+            .init_resource::<CodeEntry>()
+            .add_systems(Update, open_code_entry.run_if(in_state(GameState::Menu)))
+            .add_systems(OnEnter(GameState::ChallengeEntry), show_code_entry)
+            .add_systems(
+                Update,
+                (
+                    code_entry_input,
+                    update_code_entry.run_if(in_state(GameState::ChallengeEntry)),
+                )
+                    .chain(),
+            )
+            .add_systems(OnExit(GameState::ChallengeEntry), despawn_code_entry)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                show_challenge_result.after(update_best_score),
+            )
+            .add_systems(
+                Update,
+                leave_challenge.run_if(
+                    in_state(GameState::GameOver).and_then(not(resource_exists::<PhotoMode>)),
+                ),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChallengeCode {
+    pub seed: u32,
+    pub hitbox: Hitbox,
+    pub modifiers: Modifiers,
+}
+
+impl ChallengeCode {
+    fn payload(self) -> u64 {
+        let hitbox = Hitbox::ALL
+            .iter()
+            .position(|&h| h == self.hitbox)
+            .unwrap_or(0);
+        self.seed as u64 | (hitbox as u64) << 32 | (self.modifiers.bits() as u64) << 34
+    }
+
+    fn encode(self) -> String {
+        let payload = self.payload();
+        let value = payload | check_value(payload) << 46;
+        let mut code = String::with_capacity(CODE_LEN + 1);
+        for i in (0..CODE_LEN).rev() {
+            code.push(ALPHABET[(value >> (i * 5)) as usize & 31] as char);
+            if i == CODE_LEN / 2 {
+                code.push('-');
+            }
+        }
+        code
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut value = 0u64;
+        let mut len = 0;
+        for c in text.chars().filter(|&c| c != '-' && !c.is_whitespace()) {
+            value = value << 5 | digit(c)? as u64;
+            len += 1;
+        }
+        if len != CODE_LEN {
+            return None;
+        }
+        let payload = value & ((1 << 46) - 1);
+        if value >> 46 != check_value(payload) {
+            return None;
+        }
+        Some(Self {
+            seed: payload as u32,
+            hitbox: *Hitbox::ALL.get((payload >> 32) as usize & 3)?,
+            modifiers: Modifiers::from_bits((payload >> 34) as u16),
+        })
+    }
+}
+
+impl std::fmt::Display for ChallengeCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.encode())
+    }
+}
+
+fn check_value(payload: u64) -> u64 {
+    payload.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 60
+}
+
+fn digit(c: char) -> Option<u8> {
+    let c = match c.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        c => c,
+    };
+    ALPHABET
+        .iter()
+        .position(|&a| a as char == c)
+        .map(|i| i as u8)
+}
+
+// The challenge being played, if any. Set from the code entry screen, read by
+// start_game.
+#[derive(Resource, Default)]
+pub struct ActiveChallenge(pub Option<ChallengeCode>);
+
+// The code for the run that just ended, or just started. Codes have no room
+// for a challenge preset's rules, so those runs have none.
+pub fn run_code(
+    challenge: &ActiveChallenge,
+    seed: &RunSeed,
+    settings: &Settings,
+    modifiers: &RunModifiers,
+    rules: &RunRules,
+    course: &UnrecordedCourse,
+) -> Option<ChallengeCode> {
+    // A code plays back on its own course, which this run wasn't
+    if course.reason().is_some() {
+        return None;
+    }
+    challenge.0.or_else(|| {
+        if rules.0 != Rules::default() {
+            return None;
+        }
+        Some(ChallengeCode {
+            // Runs from before codes (old replays) can have longer seeds
+            seed: u32::try_from(seed.0).ok()?,
+            hitbox: settings.hitbox,
+            modifiers: modifiers.0,
+        })
+    })
+}
+
+// --------------------------------------------
+// Weekly challenge
+// --------------------------------------------
+
+// The same for everyone: weeks start on Monday, midnight UTC
+fn weekly_challenge(now: DateTime<Utc>) -> ChallengeCode {
+    let week = now.iso_week();
+    // 202642 for week 42 of 2026, hashed so neighbouring weeks look unrelated
+    let number = week.year() as u64 * 100 + week.week() as u64;
+    let modifiers = WEEKLY_ROTATION[week.week() as usize % WEEKLY_ROTATION.len()]
+        .iter()
+        .fold(Modifiers::NONE, |set, &m| set.with(m));
+    ChallengeCode {
+        seed: (number.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32) as u32,
+        hitbox: Hitbox::default(),
+        modifiers,
+    }
+}
+
+// "3d 4h" or "5h 12m" until the next weekly challenge
+fn time_to_next_week(now: DateTime<Utc>) -> String {
+    let days = 7 - now.weekday().num_days_from_monday() as u64;
+    let next = (now.date_naive() + Days::new(days))
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    let left = next - now;
+    if left.num_days() > 0 {
+        format!("{}d {}h", left.num_days(), left.num_hours() % 24)
+    } else {
+        format!("{}h {}m", left.num_hours(), left.num_minutes() % 60)
+    }
+}
+
+// --------------------------------------------
+// Best scores per code
+// --------------------------------------------
+
+#[derive(Resource, Default)]
+struct ChallengeBests(HashMap<String, u32>);
+
+impl ChallengeBests {
+    fn load() -> Self {
+        let text = std::fs::read_to_string(bests_path()).unwrap_or_default();
+        let bests = text
+            .lines()
+            .filter_map(|line| {
+                let (code, best) = line.split_once('=')?;
+                Some((code.trim().to_string(), best.trim().parse().ok()?))
+            })
+            .collect();
+        Self(bests)
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let path = bests_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut codes: Vec<_> = self.0.iter().collect();
+        codes.sort();
+        let text: String = codes
+            .into_iter()
+            .map(|(code, best)| format!("{code} = {best}\n"))
+            .collect();
+        std::fs::write(path, text)
+    }
+}
+
+fn bests_path() -> PathBuf {
+    crate::data_file("challenges.txt")
+}
+
+// --------------------------------------------
+// Code entry screen
+// --------------------------------------------
+
+// What has been typed so far, without the dash
+#[derive(Resource, Default)]
+struct CodeEntry(String);
+
+#[derive(Component)]
+struct CodeEntryUI;
+
+#[derive(Component)]
+struct CodeEntryText;
+
+fn open_code_entry(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if input.just_pressed(KeyCode::KeyC) {
+        next_state.set(GameState::ChallengeEntry);
+    }
+}
+
+fn show_code_entry(mut commands: Commands, mut entry: ResMut<CodeEntry>) {
+    entry.0.clear();
+    commands.spawn((
+        TextBundle::from_section(
+            "Challenge",
+            TextStyle {
+                font_size: 56.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(WINDOW_W * 0.5 - 130.0),
+            ..default()
+        }),
+        CodeEntryUI,
+    ));
+    // Filled in by update_code_entry
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(120.0),
+            left: Val::Px(WINDOW_W * 0.5 - 200.0),
+            ..default()
+        }),
+        CodeEntryUI,
+        CodeEntryText,
+    ));
+}
+
+fn despawn_code_entry(mut commands: Commands, q: Query<Entity, With<CodeEntryUI>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// Runs in every state so it has always read past the keys pressed before the
+// screen opened, C included
+fn code_entry_input(
+    state: Res<State<GameState>>,
+    mut keys: EventReader<KeyboardInput>,
+    mut entry: ResMut<CodeEntry>,
+    mut challenge: ResMut<ActiveChallenge>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if *state.get() != GameState::ChallengeEntry {
+        keys.clear();
+        return;
+    }
+    for key in keys.read() {
+        if !key.state.is_pressed() {
+            continue;
+        }
+        match &key.logical_key {
+            Key::Escape => next_state.set(GameState::Menu),
+            Key::Tab => next_state.set(GameState::Presets),
+            Key::Backspace => {
+                entry.0.pop();
+            }
+            Key::Enter if entry.0.is_empty() => {
+                challenge.0 = Some(weekly_challenge(Utc::now()));
+                next_state.set(GameState::Playing);
+            }
+            Key::Enter => match ChallengeCode::parse(&entry.0) {
+                Some(code) => {
+                    challenge.0 = Some(code);
+                    next_state.set(GameState::Playing);
+                }
+                None => {
+                    toasts.send(ShowToast("That's not a valid challenge code".to_string()));
+                }
+            },
+            Key::Character(text) => {
+                for c in text.chars() {
+                    if entry.0.len() < CODE_LEN && digit(c).is_some() {
+                        entry.0.push(c.to_ascii_uppercase());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Rebuilt every frame for the countdown, but only written when it reads differently
+fn update_code_entry(
+    entry: Res<CodeEntry>,
+    bests: Res<ChallengeBests>,
+    mut q: Query<&mut Text, With<CodeEntryText>>,
+) {
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    // Typed characters, then blanks, with the dash in the middle
+    let mut shown = String::new();
+    for i in 0..CODE_LEN {
+        if i == CODE_LEN / 2 {
+            shown.push('-');
+        }
+        shown.push(entry.0.chars().nth(i).unwrap_or('_'));
+    }
+    let now = Utc::now();
+    let weekly = weekly_challenge(now);
+    let best = bests.0.get(&weekly.to_string()).copied().unwrap_or(0);
+    let value = format!(
+        "Type a challenge code:\n\n{shown}\n\nEnter to play, Tab for presets, Esc to go back\n\n\
+         Weekly challenge: {}\nBest {best}, next one in {}\nEnter with no code to play it",
+        weekly.modifiers,
+        time_to_next_week(now)
+    );
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+// --------------------------------------------
+// Game over
+// --------------------------------------------
+
+#[allow(clippy::too_many_arguments)]
+fn show_challenge_result(
+    mut commands: Commands,
+    challenge: Res<ActiveChallenge>,
+    seed: Res<RunSeed>,
+    settings: Res<Settings>,
+    modifiers: Res<RunModifiers>,
+    rules: Res<RunRules>,
+    course: UnrecordedCourse,
+    score: Res<Score>,
+    ranking: RunRanking,
+    playback: Option<Res<ReplayPlayback>>,
+    preset: Res<ActivePreset>,
+    mut bests: ResMut<ChallengeBests>,
+) {
+    // Replays carry their own course, and presets show their own result
+    if playback.is_some() || preset.0.is_some() {
+        return;
+    }
+    let Some(code) = run_code(&challenge, &seed, &settings, &modifiers, &rules, &course) else {
+        return;
+    };
+    let message = if challenge.0.is_some() {
+        let best = bests.0.entry(code.to_string()).or_default();
+        if ranking.ranked() && score.0 > *best {
+            *best = score.0;
+        }
+        let best = *best;
+        if let Err(err) = bests.save() {
+            error!("Cannot save challenge bests: {err}");
+        }
+        let name = if code == weekly_challenge(Utc::now()) {
+            "Weekly challenge".to_string()
+        } else {
+            format!("Challenge {code}")
+        };
+        format!(
+            "{name} ({}) - best {best}\nEsc to leave the challenge",
+            code.modifiers
+        )
+    } else {
+        format!("Challenge code for this course: {code}")
+    };
+    commands.spawn((
+        TextBundle::from_section(
+            message,
+            TextStyle {
+                font_size: 22.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Below the practice result
+            top: Val::Px(400.0),
+            left: Val::Px(WINDOW_W * 0.5 - 220.0),
+            ..default()
+        }),
+        GameOverUI,
+    ));
+}
+
+fn leave_challenge(
+    input: Res<ButtonInput<KeyCode>>,
+    mut challenge: ResMut<ActiveChallenge>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if challenge.0.is_some() && input.just_pressed(KeyCode::Escape) {
+        challenge.0 = None;
+        next_state.set(GameState::Menu);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes() -> Vec<ChallengeCode> {
+        let mut codes = Vec::new();
+        for hitbox in Hitbox::ALL {
+            for (seed, bits) in [(0, 0), (42, 0b101), (u32::MAX, (1 << Modifiers::BITS) - 1)] {
+                codes.push(ChallengeCode {
+                    seed,
+                    hitbox,
+                    modifiers: Modifiers::from_bits(bits),
+                });
+            }
+        }
+        codes
+    }
+
+    #[test]
+    fn round_trip() {
+        for code in codes() {
+            let text = code.to_string();
+            assert_eq!(text.len(), CODE_LEN + 1);
+            assert_eq!(ChallengeCode::parse(&text), Some(code));
+            // As typed: lowercase, without the dash, spaced out
+            let typed = text.replace('-', " ").to_lowercase();
+            assert_eq!(ChallengeCode::parse(&typed), Some(code));
+        }
+    }
+
+    #[test]
+    fn look_alike_letters() {
+        let code = ChallengeCode {
+            seed: 0,
+            hitbox: Hitbox::ALL[0],
+            modifiers: Modifiers::NONE,
+        };
+        let typed = code.to_string().replace('0', "O").replace('1', "l");
+        assert_eq!(ChallengeCode::parse(&typed), Some(code));
+    }
+
+    #[test]
+    fn wrong_length() {
+        let text = codes()[1].to_string();
+        assert_eq!(ChallengeCode::parse(&text[..text.len() - 1]), None);
+        assert_eq!(ChallengeCode::parse(&format!("{text}0")), None);
+    }
+}
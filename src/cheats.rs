@@ -0,0 +1,269 @@
+// Cheat codes: typing a cheat's code (see Cheat::code) on the main menu turns
+// it on for the runs after it, and typing it again turns it off. Codes only use
+// letters the menu doesn't already listen for.
+//
+// A run played with cheats doesn't count towards the best score, achievements
+// or the challenge bests, and can't be saved as a replay. Codes found are
+// remembered in cheats.txt next to the settings; which cheats are on is not.
+
+use std::path::PathBuf;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::toast::ShowToast;
+use crate::wallet::Wallet;
+use crate::{start_game, Bird, FixedTransform, GameState, LeadBird, MenuUI, RestartDemo, RunRules};
+
+// Typed letters kept to match codes against
+const MAX_TYPED: usize = 16;
+const BIG_BIRD_SCALE: f32 = 1.6;
+// Fraction of the usual gravity on the moon
+const MOON_GRAVITY: f32 = 0.4;
+// The rainbow goes once round the hues in this many seconds
+const RAINBOW_PERIOD: f32 = 2.0;
+
+pub struct CheatsPlugin;
+
+impl Plugin for CheatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load())
+            .init_resource::<ActiveCheats>()
+            .init_resource::<RunCheats>()
+            .add_systems(
+                OnEnter(GameState::Menu),
+                (apply_cheats.after(start_game), show_cheats),
+            )
+            .add_systems(OnEnter(GameState::Playing), apply_cheats.after(start_game))
+            .add_systems(OnEnter(GameState::Replay), clear_cheats.after(start_game))
+            .add_systems(
+                Update,
+                apply_cheats
+                    .after(start_game)
+                    .run_if(on_event::<RestartDemo>()),
+            )
+            .add_systems(
+                Update,
+                ((cheat_input, update_cheats_text).chain(), paint_rainbow),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cheat {
+    BigBird,
+    Rainbow,
+    MoonGravity,
+}
+
+impl Cheat {
+    const ALL: [Cheat; 3] = [Cheat::BigBird, Cheat::Rainbow, Cheat::MoonGravity];
+
+    // What's typed on the menu, and how it's saved once found
+    fn code(self) -> &'static str {
+        match self {
+            Cheat::BigBird => "puffup",
+            Cheat::Rainbow => "iridia",
+            Cheat::MoonGravity => "upupup",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Cheat::BigBird => "Big bird",
+            Cheat::Rainbow => "Rainbow mode",
+            Cheat::MoonGravity => "Moon gravity",
+        }
+    }
+}
+
+// Codes typed in so far, ever
+#[derive(Resource, Default)]
+struct DiscoveredCheats(Vec<Cheat>);
+
+// Cheats turned on from the menu this session, for the runs to come
+#[derive(Resource, Default)]
+struct ActiveCheats(Vec<Cheat>);
+
+// Cheats the current run is played with
+#[derive(Resource, Default)]
+pub struct RunCheats(pub Vec<Cheat>);
+
+impl RunCheats {
+    pub fn any(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    fn has(&self, cheat: Cheat) -> bool {
+        self.0.contains(&cheat)
+    }
+}
+
+fn cheats_path() -> PathBuf {
+    crate::data_file("cheats.txt")
+}
+
+fn load() -> DiscoveredCheats {
+    let mut discovered = DiscoveredCheats::default();
+    let text = std::fs::read_to_string(cheats_path()).unwrap_or_default();
+    for line in text.lines() {
+        let Some(("discovered", value)) = line.split_once('=').map(|(k, v)| (k.trim(), v)) else {
+            continue;
+        };
+        for code in value.split(',').map(str::trim) {
+            if let Some(cheat) = Cheat::ALL.into_iter().find(|c| c.code() == code) {
+                discovered.0.push(cheat);
+            }
+        }
+    }
+    discovered
+}
+
+fn save(discovered: &DiscoveredCheats) {
+    let codes: Vec<_> = discovered.0.iter().map(|cheat| cheat.code()).collect();
+    let path = cheats_path();
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, format!("discovered = {}\n", codes.join(", "))));
+    if let Err(err) = result {
+        error!("Cannot save cheats: {err}");
+    }
+}
+
+// Runs in every state so it has always read past the keys pressed elsewhere
+fn cheat_input(
+    state: Res<State<GameState>>,
+    mut keys: EventReader<KeyboardInput>,
+    mut typed: Local<String>,
+    mut active: ResMut<ActiveCheats>,
+    mut discovered: ResMut<DiscoveredCheats>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if *state.get() != GameState::Menu {
+        keys.clear();
+        typed.clear();
+        return;
+    }
+    for key in keys.read() {
+        if !key.state.is_pressed() {
+            continue;
+        }
+        let Key::Character(text) = &key.logical_key else {
+            continue;
+        };
+        typed.extend(
+            text.chars()
+                .filter(char::is_ascii_alphabetic)
+                .map(|c| c.to_ascii_lowercase()),
+        );
+        let excess = typed.len().saturating_sub(MAX_TYPED);
+        typed.drain(..excess);
+
+        let Some(cheat) = Cheat::ALL.into_iter().find(|c| typed.ends_with(c.code())) else {
+            continue;
+        };
+        typed.clear();
+        if !discovered.0.contains(&cheat) {
+            discovered.0.push(cheat);
+            save(&discovered);
+        }
+        let message = if let Some(i) = active.0.iter().position(|&c| c == cheat) {
+            active.0.remove(i);
+            format!("Cheat off: {}", cheat.label())
+        } else {
+            active.0.push(cheat);
+            format!("Cheat on: {} (runs won't count)", cheat.label())
+        };
+        toasts.send(ShowToast(message));
+    }
+}
+
+// At the start of each run, the menu demo's too
+fn apply_cheats(
+    active: Res<ActiveCheats>,
+    mut run_cheats: ResMut<RunCheats>,
+    mut run_rules: ResMut<RunRules>,
+    mut birds: Query<(&mut Transform, &mut FixedTransform), LeadBird>,
+) {
+    run_cheats.0.clone_from(&active.0);
+    if run_cheats.has(Cheat::MoonGravity) {
+        run_rules.0.gravity *= MOON_GRAVITY;
+    }
+    if run_cheats.has(Cheat::BigBird) {
+        for (mut tf, mut fixed_tf) in &mut birds {
+            tf.scale *= BIG_BIRD_SCALE;
+            *fixed_tf = FixedTransform::new(*tf);
+        }
+    }
+}
+
+// Replays play back as they were recorded
+fn clear_cheats(mut run_cheats: ResMut<RunCheats>) {
+    run_cheats.0.clear();
+}
+
+// Every bird cycles through the hues; the skin comes back when the cheat's off
+fn paint_rainbow(
+    time: Res<Time>,
+    run_cheats: Res<RunCheats>,
+    mut birds: Query<&mut Sprite, With<Bird>>,
+    mut wallet: ResMut<Wallet>,
+    mut painted: Local<bool>,
+) {
+    if !run_cheats.has(Cheat::Rainbow) {
+        if *painted {
+            *painted = false;
+            // Has the skin put back on
+            wallet.set_changed();
+        }
+        return;
+    }
+    *painted = true;
+    let hue = (time.elapsed_seconds() / RAINBOW_PERIOD).fract() * 360.0;
+    for mut sprite in &mut birds {
+        sprite.color = Color::hsl(hue, 0.9, 0.65);
+    }
+}
+
+#[derive(Component)]
+struct CheatsText;
+
+fn cheats_text(active: &ActiveCheats) -> String {
+    if active.0.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<_> = active.0.iter().map(|cheat| cheat.label()).collect();
+    format!("Cheats on: {}", labels.join(", "))
+}
+
+fn show_cheats(mut commands: Commands, active: Res<ActiveCheats>) {
+    commands.spawn((
+        TextBundle::from_section(
+            cheats_text(&active),
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Where the run timer goes during a run
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        CheatsText,
+        MenuUI,
+    ));
+}
+
+fn update_cheats_text(active: Res<ActiveCheats>, mut q: Query<&mut Text, With<CheatsText>>) {
+    if !active.is_changed() {
+        return;
+    }
+    for mut text in &mut q {
+        text.sections[0].value = cheats_text(&active);
+    }
+}
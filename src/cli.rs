@@ -0,0 +1,67 @@
+// Command-line launch options
+//
+// Parsed before the app (and its logger) exists, so problems go to stderr.
+
+use std::path::PathBuf;
+
+#[derive(Default, Clone, Debug)]
+pub struct LaunchOptions {
+    // `--twitch <channel>`: let Twitch chat flap the bird
+    pub twitch_channel: Option<String>,
+    // `--replay <file>`: watch a saved replay straight away
+    pub replay_path: Option<PathBuf>,
+    // `--overlay`: transparent always-on-top window with no background
+    pub overlay: bool,
+    // `--race-host <port>` / `--race-join <address>`: a ghost race against
+    // another player
+    pub race: Option<RaceRole>,
+    // `--export-stats <file>`: write the run history to a CSV (or .json) file
+    // and quit
+    pub export_stats: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+pub enum RaceRole {
+    // Wait for a rival on this UDP port
+    Host(u16),
+    // Race whoever is hosting at this address (host:port)
+    Join(String),
+}
+
+impl LaunchOptions {
+    pub fn from_args() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--twitch" => match args.next() {
+                    Some(channel) => options.twitch_channel = Some(channel),
+                    None => eprintln!("--twitch needs a channel name"),
+                },
+                "--replay" => match args.next() {
+                    Some(path) => options.replay_path = Some(PathBuf::from(path)),
+                    None => eprintln!("--replay needs a file path"),
+                },
+                "--overlay" => options.overlay = true,
+                "--race-host" => match args.next().map(|port| port.parse()) {
+                    Some(Ok(port)) => options.race = Some(RaceRole::Host(port)),
+                    _ => eprintln!("--race-host needs a port number"),
+                },
+                "--race-join" => match args.next() {
+                    Some(address) => options.race = Some(RaceRole::Join(address)),
+                    None => eprintln!("--race-join needs an address, like 192.168.1.20:7879"),
+                },
+                "--export-stats" => match args.next() {
+                    Some(path) => options.export_stats = Some(PathBuf::from(path)),
+                    None => eprintln!("--export-stats needs a file path, like runs.csv"),
+                },
+                other => eprintln!("Ignoring unknown argument: {other}"),
+            }
+        }
+        options
+    }
+}
@@ -0,0 +1,346 @@
+// Developer console: the backquote key drops it down over the game, and lines
+// typed into it run commands from COMMANDS, e.g. `set gap 180`, `spawn pipe`,
+// `state gameover`, `seed 42` or `score 99`. `help` lists them all.
+//
+// While it's open it takes every key press, so typing doesn't flap or trigger
+// the menus, and opening it pauses a run. A run changed from the console
+// (rules, score, pipes) doesn't count towards the best score.
+
+use bevy::ecs::event::ManualEventReader;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::pause::PauseRequest;
+use crate::{start_game, GameState, NextRunSeed, PipeSpawnTimer, RunRules, Score};
+
+// Lines of past commands and their output kept on screen
+const LOG_LINES: usize = 8;
+const MAX_LINE_LEN: usize = 60;
+const CONSOLE_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .init_resource::<ConsoleUsed>()
+            .add_systems(Startup, spawn_console)
+            .add_systems(PreUpdate, console_input.after(InputSystem))
+            .add_systems(
+                OnEnter(GameState::Menu),
+                reset_console_used.after(start_game),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_console_used.after(start_game),
+            )
+            .add_systems(
+                OnEnter(GameState::Replay),
+                reset_console_used.after(start_game),
+            )
+            .add_systems(
+                Update,
+                (
+                    run_console_commands
+                        .run_if(|console: Res<Console>| !console.submitted.is_empty()),
+                    update_console,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Console {
+    open: bool,
+    // What's being typed
+    line: String,
+    // Lines entered since the commands last ran
+    submitted: Vec<String>,
+    log: Vec<String>,
+}
+
+// Whether the console has changed the current run
+#[derive(Resource, Default)]
+pub struct ConsoleUsed(pub bool);
+
+fn reset_console_used(mut used: ResMut<ConsoleUsed>) {
+    used.0 = false;
+}
+
+// --------------------------------------------
+// Commands
+// --------------------------------------------
+
+struct ConsoleCommand {
+    name: &'static str,
+    usage: &'static str,
+    // Whether it changes the run being flown, keeping it off the best score
+    alters_run: bool,
+    run: fn(&mut World, &[&str]) -> Result<String, String>,
+}
+
+const COMMANDS: &[ConsoleCommand] = &[
+    ConsoleCommand {
+        name: "help",
+        usage: "help",
+        alters_run: false,
+        run: help,
+    },
+    ConsoleCommand {
+        name: "set",
+        usage: "set gap|speed|gravity <value>  (this run only)",
+        alters_run: true,
+        run: set,
+    },
+    ConsoleCommand {
+        name: "spawn",
+        usage: "spawn pipe",
+        alters_run: true,
+        run: spawn,
+    },
+    ConsoleCommand {
+        name: "state",
+        usage: "state menu|playing|gameover",
+        alters_run: false,
+        run: state,
+    },
+    ConsoleCommand {
+        name: "seed",
+        usage: "seed <number>  (the next run's course)",
+        alters_run: false,
+        run: seed,
+    },
+    ConsoleCommand {
+        name: "score",
+        usage: "score <number>",
+        alters_run: true,
+        run: score,
+    },
+];
+
+fn usage(name: &str) -> String {
+    let usage = COMMANDS
+        .iter()
+        .find(|command| command.name == name)
+        .map_or("", |command| command.usage);
+    format!("Usage: {usage}")
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Not a number: {value}"))
+}
+
+fn help(_world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let usages: Vec<_> = COMMANDS.iter().map(|command| command.usage).collect();
+    Ok(usages.join("\n"))
+}
+
+fn set(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [name, value] = args else {
+        return Err(usage("set"));
+    };
+    let value: f32 = number(value)?;
+    let mut rules = world.resource_mut::<RunRules>();
+    match *name {
+        "gap" => rules.0.pipe_gap = value.max(0.0),
+        // Speed and gravity as strengths; the pipes always come towards the
+        // bird and gravity always pulls down the course
+        "speed" => rules.0.pipe_speed = -value.abs(),
+        "gravity" => rules.0.gravity = -value.abs(),
+        _ => return Err(usage("set")),
+    }
+    Ok(format!("{name} = {value}"))
+}
+
+// Brings the next pair of pipes forward to the next fixed step
+fn spawn(world: &mut World, args: &[&str]) -> Result<String, String> {
+    if args != ["pipe"] {
+        return Err(usage("spawn"));
+    }
+    let mut spawn_timer = world.resource_mut::<PipeSpawnTimer>();
+    let duration = spawn_timer.timer.duration();
+    spawn_timer.timer.set_elapsed(duration);
+    Ok("Pipes on the way".to_string())
+}
+
+fn state(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let state = match args {
+        ["menu"] => GameState::Menu,
+        ["playing"] => GameState::Playing,
+        ["gameover"] => GameState::GameOver,
+        _ => return Err(usage("state")),
+    };
+    world
+        .resource_mut::<NextState<GameState>>()
+        .set(state.clone());
+    Ok(format!("Going to {state:?}"))
+}
+
+fn seed(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [value] = args else {
+        return Err(usage("seed"));
+    };
+    let seed: u64 = number(value)?;
+    world.resource_mut::<NextRunSeed>().0 = Some(seed);
+    Ok(format!("The next run flies course {seed}"))
+}
+
+fn score(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [value] = args else {
+        return Err(usage("score"));
+    };
+    let value: u32 = number(value)?;
+    world.resource_mut::<Score>().0 = value;
+    Ok(format!("Score = {value}"))
+}
+
+fn run_console_commands(world: &mut World) {
+    let lines = std::mem::take(&mut world.resource_mut::<Console>().submitted);
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((name, args)) = words.split_first() else {
+            continue;
+        };
+        let result = match COMMANDS.iter().find(|command| command.name == *name) {
+            Some(command) => {
+                let result = (command.run)(world, args);
+                if command.alters_run && result.is_ok() {
+                    world.resource_mut::<ConsoleUsed>().0 = true;
+                }
+                result
+            }
+            None => Err(format!("Unknown command: {name} (try help)")),
+        };
+        let mut console = world.resource_mut::<Console>();
+        console.log.push(format!("> {line}"));
+        console
+            .log
+            .extend(result.unwrap_or_else(|err| err).lines().map(str::to_string));
+        let excess = console.log.len().saturating_sub(LOG_LINES);
+        console.log.drain(..excess);
+    }
+}
+
+// --------------------------------------------
+// Input and display
+// --------------------------------------------
+
+// Ahead of every other system, so that while the console is open (and on the
+// press that closes it) no one else sees the keys
+fn console_input(
+    mut events: ResMut<Events<KeyboardInput>>,
+    mut reader: Local<ManualEventReader<KeyboardInput>>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut console: ResMut<Console>,
+    state: Res<State<GameState>>,
+    mut pause: EventWriter<PauseRequest>,
+) {
+    let was_open = console.open;
+    for key in reader.read(&events) {
+        if !key.state.is_pressed() {
+            continue;
+        }
+        if key.key_code == KeyCode::Backquote {
+            console.open = !console.open;
+            if console.open && *state.get() == GameState::Playing {
+                pause.send(PauseRequest);
+            }
+            continue;
+        }
+        if !console.open {
+            continue;
+        }
+        match &key.logical_key {
+            Key::Escape => console.open = false,
+            Key::Backspace => {
+                console.line.pop();
+            }
+            Key::Enter => {
+                let line = std::mem::take(&mut console.line);
+                if !line.trim().is_empty() {
+                    console.submitted.push(line);
+                }
+            }
+            Key::Space if console.line.len() < MAX_LINE_LEN => console.line.push(' '),
+            Key::Character(text) => {
+                for c in text.chars() {
+                    if console.line.len() < MAX_LINE_LEN && c.is_ascii_graphic() {
+                        console.line.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if was_open || console.open {
+        events.clear();
+        keys.reset_all();
+    }
+}
+
+#[derive(Component)]
+struct ConsoleUI;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn spawn_console(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    display: Display::None,
+                    ..default()
+                },
+                background_color: CONSOLE_COLOR.into(),
+                // Over the HUD and every screen
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            ConsoleUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                ConsoleText,
+            ));
+        });
+}
+
+fn update_console(
+    console: Res<Console>,
+    mut panel: Query<&mut Style, With<ConsoleUI>>,
+    mut text: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut style in &mut panel {
+        style.display = if console.open {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    let mut value = console.log.join("\n");
+    if !value.is_empty() {
+        value.push('\n');
+    }
+    value.push_str(&format!("] {}_", console.line));
+    for mut text in &mut text {
+        text.sections[0].value = value.clone();
+    }
+}
@@ -0,0 +1,374 @@
+// Control presets, so the game is comfortable to play one-handed. Systems ask
+// `GameInput` whether the player flapped, retried or paused instead of reading
+// keys themselves, and the preset picked in the settings menu decides what that
+// means. A controller works alongside every preset: A (Cross) flaps, starts and
+// retries, Y (Triangle) retries the same course, X (Square) fires the laser,
+// B (Circle) rewinds after a crash, Start pauses.
+//
+// The flap, confirm (start, retry, move the story on) and pause buttons can be
+// changed per controller from the settings menu (see gamepad.rs). They're saved
+// in gamepads.txt next to the settings, one `controller name = flap, confirm,
+// pause` line per model: Bevy doesn't tell us a controller's product ID, and
+// its name tells models apart the same way.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use bevy::ecs::system::SystemParam;
+use bevy::input::gamepad::Gamepads;
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ControlPreset {
+    #[default]
+    Standard,
+    LeftHand,
+    RightHand,
+    MouseOnly,
+    SpaceOnly,
+}
+
+impl ControlPreset {
+    pub const ALL: [ControlPreset; 5] = [
+        ControlPreset::Standard,
+        ControlPreset::LeftHand,
+        ControlPreset::RightHand,
+        ControlPreset::MouseOnly,
+        ControlPreset::SpaceOnly,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ControlPreset::Standard => "standard",
+            ControlPreset::LeftHand => "left_hand",
+            ControlPreset::RightHand => "right_hand",
+            ControlPreset::MouseOnly => "mouse_only",
+            ControlPreset::SpaceOnly => "space_only",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ControlPreset::Standard => "Standard",
+            ControlPreset::LeftHand => "Left hand",
+            ControlPreset::RightHand => "Right hand",
+            ControlPreset::MouseOnly => "Mouse only",
+            ControlPreset::SpaceOnly => "Spacebar only",
+        }
+    }
+
+    fn flap_keys(self) -> &'static [KeyCode] {
+        match self {
+            ControlPreset::Standard | ControlPreset::SpaceOnly => &[KeyCode::Space],
+            ControlPreset::LeftHand => &[KeyCode::Space, KeyCode::KeyW],
+            ControlPreset::RightHand => &[KeyCode::ArrowUp, KeyCode::Enter, KeyCode::Numpad0],
+            ControlPreset::MouseOnly => &[],
+        }
+    }
+
+    // On top of the flap keys
+    fn retry_keys(self) -> &'static [KeyCode] {
+        match self {
+            ControlPreset::Standard => &[KeyCode::KeyR],
+            _ => &[],
+        }
+    }
+
+    fn pause_keys(self) -> &'static [KeyCode] {
+        match self {
+            ControlPreset::Standard | ControlPreset::RightHand => &[KeyCode::KeyP],
+            ControlPreset::LeftHand => &[KeyCode::KeyQ],
+            ControlPreset::MouseOnly | ControlPreset::SpaceOnly => &[],
+        }
+    }
+
+    fn flap_buttons(self) -> &'static [MouseButton] {
+        match self {
+            ControlPreset::MouseOnly => &[MouseButton::Left],
+            _ => &[],
+        }
+    }
+
+    fn pause_buttons(self) -> &'static [MouseButton] {
+        match self {
+            ControlPreset::MouseOnly => &[MouseButton::Right],
+            _ => &[],
+        }
+    }
+
+    // For on-screen instructions: "Press Space to start"
+    pub fn flap_hint(self) -> &'static str {
+        match self {
+            ControlPreset::Standard | ControlPreset::SpaceOnly => "Space",
+            ControlPreset::LeftHand => "Space or W",
+            ControlPreset::RightHand => "Up or Enter",
+            ControlPreset::MouseOnly => "Click",
+        }
+    }
+
+    pub fn retry_hint(self) -> &'static str {
+        match self {
+            ControlPreset::Standard => "Space or R",
+            _ => self.flap_hint(),
+        }
+    }
+
+    pub fn pause_hint(self) -> &'static str {
+        match self {
+            ControlPreset::Standard | ControlPreset::RightHand => "P or Start",
+            ControlPreset::LeftHand => "Q or Start",
+            ControlPreset::MouseOnly => "Right click or Start",
+            ControlPreset::SpaceOnly => "Start",
+        }
+    }
+}
+
+// --------------------------------------------
+// Controller buttons
+// --------------------------------------------
+
+// What a controller's buttons can be bound to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PadAction {
+    Flap,
+    Confirm,
+    Pause,
+}
+
+impl PadAction {
+    pub const ALL: [PadAction; 3] = [PadAction::Flap, PadAction::Confirm, PadAction::Pause];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PadAction::Flap => "flap",
+            PadAction::Confirm => "confirm (start and retry)",
+            PadAction::Pause => "pause",
+        }
+    }
+}
+
+// Buttons that can be bound, with their names in gamepads.txt and on screen
+const PAD_BUTTONS: [(GamepadButtonType, &str, &str); 17] = [
+    (GamepadButtonType::South, "south", "A / Cross"),
+    (GamepadButtonType::East, "east", "B / Circle"),
+    (GamepadButtonType::West, "west", "X / Square"),
+    (GamepadButtonType::North, "north", "Y / Triangle"),
+    (GamepadButtonType::LeftTrigger, "left_bumper", "LB / L1"),
+    (GamepadButtonType::RightTrigger, "right_bumper", "RB / R1"),
+    (GamepadButtonType::LeftTrigger2, "left_trigger", "LT / L2"),
+    (GamepadButtonType::RightTrigger2, "right_trigger", "RT / R2"),
+    (GamepadButtonType::Select, "select", "Select"),
+    (GamepadButtonType::Start, "start", "Start"),
+    (GamepadButtonType::Mode, "mode", "Home"),
+    (GamepadButtonType::LeftThumb, "left_stick", "L3"),
+    (GamepadButtonType::RightThumb, "right_stick", "R3"),
+    (GamepadButtonType::DPadUp, "dpad_up", "D-pad up"),
+    (GamepadButtonType::DPadDown, "dpad_down", "D-pad down"),
+    (GamepadButtonType::DPadLeft, "dpad_left", "D-pad left"),
+    (GamepadButtonType::DPadRight, "dpad_right", "D-pad right"),
+];
+
+fn pad_button(name: &str) -> Option<GamepadButtonType> {
+    PAD_BUTTONS
+        .iter()
+        .find(|(_, n, _)| *n == name)
+        .map(|(button, _, _)| *button)
+}
+
+fn pad_button_name(button: GamepadButtonType) -> Option<&'static str> {
+    PAD_BUTTONS
+        .iter()
+        .find(|(b, _, _)| *b == button)
+        .map(|(_, name, _)| *name)
+}
+
+// None for buttons that can't be bound
+pub fn pad_button_label(button: GamepadButtonType) -> Option<&'static str> {
+    PAD_BUTTONS
+        .iter()
+        .find(|(b, _, _)| *b == button)
+        .map(|(_, _, label)| *label)
+}
+
+// One controller's buttons for each PadAction
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PadBindings {
+    pub flap: GamepadButtonType,
+    pub confirm: GamepadButtonType,
+    pub pause: GamepadButtonType,
+}
+
+impl Default for PadBindings {
+    fn default() -> Self {
+        Self {
+            flap: GamepadButtonType::South,
+            confirm: GamepadButtonType::South,
+            pause: GamepadButtonType::Start,
+        }
+    }
+}
+
+impl PadBindings {
+    fn button(self, action: PadAction) -> GamepadButtonType {
+        match action {
+            PadAction::Flap => self.flap,
+            PadAction::Confirm => self.confirm,
+            PadAction::Pause => self.pause,
+        }
+    }
+
+    // "flap, confirm, pause" button names, as saved
+    fn to_text(self) -> String {
+        PadAction::ALL
+            .map(|action| pad_button_name(self.button(action)).unwrap_or("south"))
+            .join(", ")
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let buttons: Vec<_> = text
+            .split(',')
+            .map(|name| pad_button(name.trim()))
+            .collect();
+        let [flap, confirm, pause] = buttons[..] else {
+            return None;
+        };
+        Some(Self {
+            flap: flap?,
+            confirm: confirm?,
+            pause: pause?,
+        })
+    }
+}
+
+// Rebound controllers, by name; the rest use the defaults
+#[derive(Resource, Default)]
+pub struct GamepadBindings(HashMap<String, PadBindings>);
+
+impl GamepadBindings {
+    pub fn for_pad(&self, name: Option<&str>) -> PadBindings {
+        name.and_then(|name| self.0.get(name))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set(&mut self, name: &str, bindings: PadBindings) {
+        self.0.insert(name.to_string(), bindings);
+    }
+
+    pub fn load() -> Self {
+        let mut bindings = Self::default();
+        let text = std::fs::read_to_string(bindings_path()).unwrap_or_default();
+        for line in text.lines() {
+            // Names may have an = in them, button names don't
+            let Some((name, buttons)) = line.rsplit_once('=') else {
+                continue;
+            };
+            if let Some(pad) = PadBindings::parse(buttons) {
+                bindings.set(name.trim(), pad);
+            }
+        }
+        bindings
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = bindings_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut names: Vec<_> = self.0.keys().collect();
+        names.sort();
+        let text: String = names
+            .into_iter()
+            .map(|name| format!("{name} = {}\n", self.0[name].to_text()))
+            .collect();
+        std::fs::write(path, text)
+    }
+}
+
+fn bindings_path() -> PathBuf {
+    crate::data_file("gamepads.txt")
+}
+
+// --------------------------------------------
+// Input
+// --------------------------------------------
+
+#[derive(SystemParam)]
+pub struct GameInput<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    pads: Res<'w, ButtonInput<GamepadButton>>,
+    gamepads: Res<'w, Gamepads>,
+    bindings: Res<'w, GamepadBindings>,
+    settings: Res<'w, Settings>,
+}
+
+impl GameInput<'_> {
+    pub fn flap(&self) -> bool {
+        self.flap_key_pressed() || self.pad_action(PadAction::Flap)
+    }
+
+    // Start a run from the menu, or move the story on
+    pub fn confirm(&self) -> bool {
+        self.flap_key_pressed() || self.pad_action(PadAction::Confirm)
+    }
+
+    pub fn retry(&self) -> bool {
+        self.confirm()
+            || self
+                .keys
+                .any_just_pressed(self.settings.controls.retry_keys().iter().copied())
+    }
+
+    // Retry on the course just flown rather than a new one; T on every preset
+    pub fn retry_same_course(&self) -> bool {
+        self.keys.just_pressed(KeyCode::KeyT) || self.pad_pressed(GamepadButtonType::North)
+    }
+
+    // Fire a laser charge (see laser.rs); F on every preset
+    pub fn fire(&self) -> bool {
+        self.keys.just_pressed(KeyCode::KeyF) || self.pad_pressed(GamepadButtonType::West)
+    }
+
+    // Rewind after a crash (see rewind.rs); Backspace on every preset
+    pub fn rewind(&self) -> bool {
+        self.keys.just_pressed(KeyCode::Backspace) || self.pad_pressed(GamepadButtonType::East)
+    }
+
+    pub fn pause(&self) -> bool {
+        let preset = self.settings.controls;
+        self.keys
+            .any_just_pressed(preset.pause_keys().iter().copied())
+            || self
+                .mouse
+                .any_just_pressed(preset.pause_buttons().iter().copied())
+            || self.pad_action(PadAction::Pause)
+    }
+
+    // The preset's flap keys or mouse buttons
+    fn flap_key_pressed(&self) -> bool {
+        let preset = self.settings.controls;
+        self.keys
+            .any_just_pressed(preset.flap_keys().iter().copied())
+            || self
+                .mouse
+                .any_just_pressed(preset.flap_buttons().iter().copied())
+    }
+
+    // The button bound to `action` on whichever controller pressed it
+    fn pad_action(&self, action: PadAction) -> bool {
+        self.pads.get_just_pressed().any(|button| {
+            let bindings = self.bindings.for_pad(self.gamepads.name(button.gamepad));
+            button.button_type == bindings.button(action)
+        })
+    }
+
+    // On any controller
+    fn pad_pressed(&self, button_type: GamepadButtonType) -> bool {
+        self.pads
+            .get_just_pressed()
+            .any(|button| button.button_type == button_type)
+    }
+}
@@ -0,0 +1,149 @@
+// Decay mode (a mode in the settings menu, or a modifier in a challenge code):
+// the score drains away while the bird flies, and each pipe passed is worth a
+// few points, so staying alive means keeping ahead of the drain. The run is
+// over if the score runs out, as if the bird had crashed. The extra points
+// mean decay runs don't count towards the best score.
+//
+// The drain is counted in fixed steps, so replays run out at the same moment.
+
+use bevy::prelude::*;
+
+use crate::modifiers::Modifier;
+use crate::{
+    check_collisions_and_scoring, simulation_running, start_game, CrashCause, Crashed, GameState,
+    PipePassed, RunModifiers, Score, ScoreText, FIXED_HZ, WINDOW_W,
+};
+
+// What a decay run starts with: enough to last until the first pipes
+const START_SCORE: u32 = 15;
+// A point drains away every this many seconds
+const DRAIN_SECONDS: f32 = 0.6;
+// What a pipe is worth, against the point a pipe is normally worth
+const PIPE_POINTS: u32 = 3;
+// The score turns red at or under this
+const LOW_SCORE: u32 = 5;
+const DECAY_COLOR: Color = Color::srgb(1.0, 0.6, 0.2);
+const LOW_COLOR: Color = Color::srgb(1.0, 0.2, 0.2);
+
+pub struct DecayPlugin;
+
+impl Plugin for DecayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Decay>()
+            .add_systems(Startup, spawn_decay_text)
+            .add_systems(OnEnter(GameState::Menu), reset_decay.after(start_game))
+            .add_systems(OnEnter(GameState::Playing), reset_decay.after(start_game))
+            .add_systems(OnEnter(GameState::Replay), reset_decay.after(start_game))
+            .add_systems(
+                FixedUpdate,
+                drain_score
+                    .after(check_collisions_and_scoring)
+                    .run_if(simulation_running),
+            )
+            .add_systems(Update, update_decay_hud);
+    }
+}
+
+// Whether this run decays, and fixed steps since the last point drained
+#[derive(Resource, Default)]
+struct Decay {
+    active: bool,
+    ticks: u32,
+}
+
+#[derive(Component)]
+struct DecayText;
+
+fn spawn_decay_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: DECAY_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Under the streak pips, where growth mode puts its points
+            top: Val::Px(72.0),
+            left: Val::Px(WINDOW_W * 0.5 - 80.0),
+            ..default()
+        }),
+        DecayText,
+    ));
+}
+
+// The menu demo doesn't decay
+fn reset_decay(
+    state: Res<State<GameState>>,
+    run_modifiers: Res<RunModifiers>,
+    mut score: ResMut<Score>,
+    mut decay: ResMut<Decay>,
+) {
+    let active = run_modifiers.0.has(Modifier::Decay) && *state.get() != GameState::Menu;
+    *decay = Decay { active, ticks: 0 };
+    if active {
+        score.0 = START_SCORE;
+    }
+}
+
+fn drain_score(
+    mut decay: ResMut<Decay>,
+    mut passed: EventReader<PipePassed>,
+    mut score: ResMut<Score>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut crashed: EventWriter<Crashed>,
+) {
+    let pipes = passed.read().count() as u32;
+    // Already crashed this step
+    if !decay.active || matches!(*next_state, NextState::Pending(_)) {
+        return;
+    }
+    score.0 += pipes * (PIPE_POINTS - 1);
+    decay.ticks += 1;
+    if decay.ticks < (DRAIN_SECONDS * FIXED_HZ as f32) as u32 {
+        return;
+    }
+    decay.ticks = 0;
+    score.0 = score.0.saturating_sub(1);
+    if score.0 == 0 {
+        decay.active = false;
+        next_state.set(GameState::GameOver);
+        crashed.send(Crashed(CrashCause::Decay));
+    }
+}
+
+// The score in orange while it drains, red when it's nearly gone
+fn update_decay_hud(
+    decay: Res<Decay>,
+    score: Res<Score>,
+    mut score_q: Query<&mut Text, (With<ScoreText>, Without<DecayText>)>,
+    mut decay_q: Query<&mut Text, With<DecayText>>,
+) {
+    if !decay.is_changed() && !score.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = decay_q.get_single_mut() {
+        let value = if decay.active {
+            format!("{PIPE_POINTS} points a pipe, draining")
+        } else {
+            String::new()
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+    let Ok(mut text) = score_q.get_single_mut() else {
+        return;
+    };
+    let color = match (decay.active, score.0) {
+        (false, _) => Color::WHITE,
+        (true, score) if score <= LOW_SCORE => LOW_COLOR,
+        (true, _) => DECAY_COLOR,
+    };
+    if text.sections[0].style.color != color {
+        text.sections[0].style.color = color;
+    }
+}
@@ -0,0 +1,129 @@
+// Discord Rich Presence (enabled with the `discord` cargo feature)
+//
+// Talks to the local Discord client over its IPC socket, as the Discord app whose
+// ID is in FLOOPYBIRB_DISCORD_APP_ID when the game runs or, failing that, when
+// it's built (see the README). Without an ID, or if Discord isn't running, we
+// log once and carry on without presence.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
+
+use crate::{BestScore, GameState, Score};
+
+const APP_ID_VAR: &str = "FLOOPYBIRB_DISCORD_APP_ID";
+// Only push score updates every few points; Discord rate-limits activity updates
+const SCORE_MILESTONE: u32 = 5;
+
+pub struct DiscordPresencePlugin;
+
+impl Plugin for DiscordPresencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, connect_discord).add_systems(
+            Update,
+            update_presence.run_if(resource_exists::<DiscordPresence>),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct DiscordPresence {
+    client: DiscordIpcClient,
+    // What we last sent, so we only talk to Discord when something changes
+    last_state: Option<GameState>,
+    last_milestone: u32,
+    run_started_ms: i64,
+}
+
+impl Drop for DiscordPresence {
+    fn drop(&mut self) {
+        let _ = self.client.clear_activity();
+        let _ = self.client.close();
+    }
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+fn app_id() -> Option<String> {
+    std::env::var(APP_ID_VAR)
+        .ok()
+        .or_else(|| option_env!("FLOOPYBIRB_DISCORD_APP_ID").map(str::to_string))
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+fn connect_discord(mut commands: Commands) {
+    let Some(app_id) = app_id() else {
+        info!("{APP_ID_VAR} not set, rich presence disabled");
+        return;
+    };
+    let mut client = DiscordIpcClient::new(&app_id);
+    match client.connect() {
+        Ok(()) => commands.insert_resource(DiscordPresence {
+            client,
+            last_state: None,
+            last_milestone: 0,
+            run_started_ms: unix_millis(),
+        }),
+        Err(err) => info!("Discord not available, rich presence disabled: {err}"),
+    }
+}
+
+fn update_presence(
+    mut commands: Commands,
+    mut presence: ResMut<DiscordPresence>,
+    state: Res<State<GameState>>,
+    score: Res<Score>,
+    best: Res<BestScore>,
+) {
+    let state = state.get();
+    let milestone = score.0 / SCORE_MILESTONE;
+    let state_changed = presence.last_state.as_ref() != Some(state);
+    if !state_changed && milestone == presence.last_milestone {
+        return;
+    }
+
+    if state_changed && *state == GameState::Playing {
+        presence.run_started_ms = unix_millis();
+    }
+
+    let details = match state {
+        GameState::Menu
+        | GameState::Mods
+        | GameState::Settings
+        | GameState::ChallengeEntry
+        | GameState::Lobby
+        | GameState::Tournament
+        | GameState::HotSeat
+        | GameState::Presets
+        | GameState::Shop => "In menu".to_string(),
+        GameState::Playing => format!("Flying — score {}", score.0),
+        GameState::Replay => "Watching a replay".to_string(),
+        GameState::Story => "Reading the story".to_string(),
+        GameState::GameOver => format!("Crashed — score {}", score.0),
+    };
+    let best_text = format!("Best: {}", best.0);
+
+    let mut activity = activity::Activity::new().details(&details);
+    if best.0 > 0 {
+        activity = activity.state(&best_text);
+    }
+    if *state == GameState::Playing {
+        activity = activity.timestamps(activity::Timestamps::new().start(presence.run_started_ms));
+    }
+
+    if let Err(err) = presence.client.set_activity(activity) {
+        warn!("Lost connection to Discord, rich presence disabled: {err}");
+        commands.remove_resource::<DiscordPresence>();
+        return;
+    }
+
+    presence.last_state = Some(state.clone());
+    presence.last_milestone = milestone;
+}
@@ -0,0 +1,108 @@
+// Distance scoring (Scoring in the settings menu, picked for each mode): the
+// score on screen is how far the bird flew, in meters, rather than the pipes it
+// passed. Pipes are still counted underneath, for achievements, streaks and the
+// best score, and both go into the stats and the run history.
+//
+// Distance is the course's scroll speed times fixed steps survived, so it's the
+// same on replay and a faster course covers more ground.
+
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::{
+    simulation_running, start_game, Course, GameOverUI, GameState, RestartDemo, RunModifiers,
+    RunRanking, WINDOW_W,
+};
+
+// About a bird's length
+const PIXELS_PER_METER: f32 = 34.0;
+
+pub struct DistancePlugin;
+
+impl Plugin for DistancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Distance>()
+            .init_resource::<BestDistance>()
+            .add_systems(OnEnter(GameState::Menu), reset_distance.after(start_game))
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_distance.after(start_game),
+            )
+            .add_systems(OnEnter(GameState::Replay), reset_distance.after(start_game))
+            .add_systems(
+                Update,
+                reset_distance
+                    .after(start_game)
+                    .run_if(on_event::<RestartDemo>()),
+            )
+            .add_systems(FixedUpdate, advance_distance.run_if(simulation_running))
+            .add_systems(OnEnter(GameState::GameOver), show_distance_result);
+    }
+}
+
+// How far the bird has flown this run, and whether that's its score
+#[derive(Resource, Default)]
+pub struct Distance {
+    pub meters: f32,
+    pub scored: bool,
+}
+
+// Longest ranked run this session, among those scored by distance
+#[derive(Resource, Default)]
+struct BestDistance(f32);
+
+fn reset_distance(
+    settings: Res<Settings>,
+    run_modifiers: Res<RunModifiers>,
+    mut distance: ResMut<Distance>,
+) {
+    *distance = Distance {
+        meters: 0.0,
+        scored: settings.scores_distance(run_modifiers.0.mode()),
+    };
+}
+
+fn advance_distance(
+    state: Res<State<GameState>>,
+    time: Res<Time<Fixed>>,
+    course: Course,
+    mut distance: ResMut<Distance>,
+) {
+    // The menu demo doesn't go anywhere
+    if *state.get() == GameState::Menu {
+        return;
+    }
+    distance.meters += course.pipe_speed().abs() * time.delta_seconds() / PIXELS_PER_METER;
+}
+
+fn show_distance_result(
+    mut commands: Commands,
+    distance: Res<Distance>,
+    ranking: RunRanking,
+    mut best: ResMut<BestDistance>,
+) {
+    if !distance.scored {
+        return;
+    }
+    if ranking.ranked() {
+        best.0 = best.0.max(distance.meters);
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            format!("Best distance {:.0} m", best.0),
+            TextStyle {
+                font_size: 22.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Above the game over text
+            top: Val::Px(120.0),
+            left: Val::Px(WINDOW_W * 0.5 - 220.0),
+            ..default()
+        }),
+        GameOverUI,
+    ));
+}
@@ -0,0 +1,98 @@
+// Flock mode (a mode in the settings menu, or a modifier in a challenge code):
+// three to five birds fly on the one flap button, stacked a little apart. A bird
+// that crashes is gone, the run goes on while any are left, and every pipe is
+// worth a point per bird still flying, so flock runs don't count towards the
+// best score.
+//
+// The lead bird is the game's usual Bird, which the camera, autopilot and HUD
+// follow; the others are Flockmates, spawned fresh for each run. When the lead
+// bird crashes a flockmate takes its place (see check_collisions_and_scoring).
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::atlas::SpriteAtlas;
+use crate::modifiers::Modifier;
+use crate::{
+    start_game, Bird, FixedTransform, GameState, RestartDemo, RunModifiers, RunSeed, BIRD_START_X,
+    BIRD_START_Y,
+};
+
+// Mixed into the run seed for the flock size, so it doesn't move the pipes
+const FLOCK_STREAM: u64 = 0xf10c_f10c_f10c_f10c;
+// Birds in a flock, counting the lead bird
+const MIN_FLOCK: usize = 3;
+const MAX_FLOCK: usize = 5;
+// Vertical distance between neighbouring birds at the start
+const FLOCK_SPACING: f32 = 36.0;
+
+pub struct FlockPlugin;
+
+impl Plugin for FlockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Menu), spawn_flock.after(start_game))
+            .add_systems(OnEnter(GameState::Playing), spawn_flock.after(start_game))
+            .add_systems(OnEnter(GameState::Replay), spawn_flock.after(start_game))
+            .add_systems(
+                Update,
+                spawn_flock
+                    .after(start_game)
+                    .run_if(on_event::<RestartDemo>()),
+            );
+    }
+}
+
+// Every bird but the lead one
+#[derive(Component)]
+pub struct Flockmate;
+
+fn spawn_flock(
+    mut commands: Commands,
+    atlas: Option<Res<SpriteAtlas>>,
+    run_seed: Res<RunSeed>,
+    run_modifiers: Res<RunModifiers>,
+    mates: Query<Entity, With<Flockmate>>,
+) {
+    for e in &mates {
+        commands.entity(e).despawn_recursive();
+    }
+    // The first menu is entered before the sprite sheet loads
+    let Some(atlas) = atlas.filter(|_| run_modifiers.0.has(Modifier::Flock)) else {
+        return;
+    };
+
+    let size = StdRng::seed_from_u64(run_seed.0 ^ FLOCK_STREAM).gen_range(MIN_FLOCK..=MAX_FLOCK);
+    let frames = atlas.frames("bird");
+    let facing = run_modifiers.0.facing();
+    for i in 1..size {
+        // Alternately above and below the lead bird, further out each time
+        let side = if i % 2 == 1 { 1.0 } else { -1.0 };
+        let offset = side * i.div_ceil(2) as f32 * FLOCK_SPACING;
+        // Just behind the lead bird
+        let transform = Transform::from_xyz(BIRD_START_X * facing, BIRD_START_Y + offset, 0.9);
+        commands.spawn((
+            SpriteBundle {
+                texture: atlas.texture.clone(),
+                transform,
+                sprite: Sprite {
+                    flip_x: facing < 0.0,
+                    ..default()
+                },
+                ..default()
+            },
+            TextureAtlas {
+                layout: atlas.layout.clone(),
+                index: frames.get(1).copied().unwrap_or_default(),
+            },
+            Bird {
+                vy: 0.0,
+                vx: 0.0,
+                anim_timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+                frames: frames.clone(),
+            },
+            FixedTransform::new(transform),
+            Flockmate,
+        ));
+    }
+}
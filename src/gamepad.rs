@@ -0,0 +1,211 @@
+// Controller connect/disconnect toasts, auto-pause when the controller being
+// played with drops out mid-run, and rebinding a controller's buttons from the
+// settings menu: Enter on "Controller buttons", then press the new flap,
+// confirm and pause buttons in turn on the controller to change (see
+// controls.rs for how they're kept).
+
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent, Gamepads};
+use bevy::prelude::*;
+
+use crate::controls::{pad_button_label, GamepadBindings, PadAction, PadBindings};
+use crate::pause::PauseRequest;
+use crate::toast::ShowToast;
+use crate::{GameState, WINDOW_W};
+
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveGamepad>()
+            .insert_resource(GamepadBindings::load())
+            .add_event::<RebindGamepad>()
+            .add_systems(Update, (track_active_gamepad, handle_connections).chain())
+            .add_systems(
+                Update,
+                (start_rebinding, rebind_input, update_rebind_prompt)
+                    .chain()
+                    .run_if(in_state(GameState::Settings)),
+            )
+            .add_systems(OnExit(GameState::Settings), stop_rebinding);
+    }
+}
+
+// The controller that last pressed a button
+#[derive(Resource, Default)]
+struct ActiveGamepad(Option<Gamepad>);
+
+fn track_active_gamepad(pads: Res<ButtonInput<GamepadButton>>, mut active: ResMut<ActiveGamepad>) {
+    if let Some(button) = pads.get_just_pressed().next() {
+        if active.0 != Some(button.gamepad) {
+            active.0 = Some(button.gamepad);
+        }
+    }
+}
+
+fn handle_connections(
+    mut events: EventReader<GamepadConnectionEvent>,
+    mut active: ResMut<ActiveGamepad>,
+    state: Res<State<GameState>>,
+    mut pause: EventWriter<PauseRequest>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for event in events.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                toasts.send(ShowToast(format!("Controller connected: {}", info.name)));
+            }
+            GamepadConnection::Disconnected => {
+                if active.0 != Some(event.gamepad) {
+                    toasts.send(ShowToast("Controller disconnected".to_string()));
+                    continue;
+                }
+                active.0 = None;
+                if *state.get() == GameState::Playing {
+                    pause.send(PauseRequest);
+                    toasts.send(ShowToast("Controller disconnected - paused".to_string()));
+                } else {
+                    toasts.send(ShowToast("Controller disconnected".to_string()));
+                }
+            }
+        }
+    }
+}
+
+// --------------------------------------------
+// Rebinding
+// --------------------------------------------
+
+// Sent by the settings menu to start listening for a controller's new buttons
+#[derive(Event)]
+pub struct RebindGamepad;
+
+// Present while listening
+#[derive(Resource, Default)]
+struct Rebinding {
+    // The controller being rebound, and its name, once it's pressed a button
+    pad: Option<(Gamepad, String)>,
+    // New buttons so far, in PadAction::ALL order
+    buttons: Vec<GamepadButtonType>,
+}
+
+#[derive(Component)]
+struct RebindPrompt;
+
+fn start_rebinding(
+    mut commands: Commands,
+    mut events: EventReader<RebindGamepad>,
+    prompts: Query<Entity, With<RebindPrompt>>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+    // Enter again starts over
+    for e in &prompts {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.insert_resource(Rebinding::default());
+    // Filled in by update_rebind_prompt
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 22.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(WINDOW_W * 0.5 - 220.0),
+            ..default()
+        }),
+        RebindPrompt,
+    ));
+}
+
+fn rebind_input(
+    mut commands: Commands,
+    pads: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    rebinding: Option<ResMut<Rebinding>>,
+    mut bindings: ResMut<GamepadBindings>,
+    prompts: Query<Entity, With<RebindPrompt>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let Some(mut rebinding) = rebinding else {
+        return;
+    };
+    for button in pads.get_just_pressed() {
+        // Whichever controller answers first is the one rebound
+        match &rebinding.pad {
+            Some((pad, _)) if *pad != button.gamepad => continue,
+            Some(_) => {}
+            None => {
+                let name = gamepads.name(button.gamepad).unwrap_or("Controller");
+                rebinding.pad = Some((button.gamepad, name.to_string()));
+            }
+        }
+        if pad_button_label(button.button_type).is_none() {
+            toasts.send(ShowToast(
+                "That button can't be used - try another".to_string(),
+            ));
+            continue;
+        }
+        rebinding.buttons.push(button.button_type);
+        let [flap, confirm, pause] = rebinding.buttons[..] else {
+            continue;
+        };
+
+        let name = rebinding.pad.as_ref().map_or("", |(_, name)| name.as_str());
+        bindings.set(
+            name,
+            PadBindings {
+                flap,
+                confirm,
+                pause,
+            },
+        );
+        if let Err(err) = bindings.save() {
+            error!("Cannot save controller buttons: {err}");
+            toasts.send(ShowToast("Couldn't save controller buttons".to_string()));
+        } else {
+            toasts.send(ShowToast(format!("Controller buttons saved for {name}")));
+        }
+        commands.remove_resource::<Rebinding>();
+        for e in &prompts {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+}
+
+fn update_rebind_prompt(
+    rebinding: Option<Res<Rebinding>>,
+    mut q: Query<&mut Text, With<RebindPrompt>>,
+) {
+    let Some(rebinding) = rebinding else {
+        return;
+    };
+    if !rebinding.is_changed() {
+        return;
+    }
+    let Some(action) = PadAction::ALL.get(rebinding.buttons.len()) else {
+        return;
+    };
+    let mut prompt = format!("Press the controller button to {}", action.label());
+    if let Some(last) = rebinding.buttons.last().copied().and_then(pad_button_label) {
+        prompt = format!("{last} set. {prompt}");
+    }
+    for mut text in &mut q {
+        text.sections[0].value.clone_from(&prompt);
+    }
+}
+
+// Leaving the settings part way through keeps the old buttons
+fn stop_rebinding(mut commands: Commands, prompts: Query<Entity, With<RebindPrompt>>) {
+    commands.remove_resource::<Rebinding>();
+    for e in &prompts {
+        commands.entity(e).despawn_recursive();
+    }
+}
@@ -0,0 +1,262 @@
+// Growth mode (a mode in the settings menu, or a modifier in a challenge code):
+// coins float in many of the gaps. Each one the bird eats makes it a little
+// bigger, hitbox and all, and adds a point to every pipe it passes from then
+// on; the next run starts small again. The bonus points mean growth runs don't
+// count towards the best score, but the coins still go into the wallet.
+//
+// Coins are placed from their own stream of the run seed, so replays see the
+// same ones.
+
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::modifiers::Modifier;
+use crate::{
+    check_collisions_and_scoring, new_gap_center, simulation_running, start_game, sweep_pipes,
+    Bird, Course, FixedTransform, GameState, LeadBird, NewPipes, PipePassed, RestartDemo, Rewound,
+    RunModifiers, RunSeed, Score, BIRD_SIZE, PIPE_DESPAWN_X, WINDOW_W,
+};
+
+// Mixed into the run seed for placing coins, so they don't move the pipes
+const COIN_STREAM: u64 = 0xc011_c011_c011_c011;
+// Chance that a new pair of pipes has a coin in its gap
+const COIN_CHANCE: f64 = 0.5;
+pub const COIN_RADIUS: f32 = 9.0;
+const COIN_COLOR: Color = Color::srgb(1.0, 0.8, 0.1);
+// Each coin adds this much to the bird's size, up to MAX_COINS of them
+const GROWTH_PER_COIN: f32 = 0.1;
+const MAX_COINS: u32 = 8;
+
+pub struct GrowthPlugin;
+
+impl Plugin for GrowthPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Growth>()
+            .init_resource::<CoinAssets>()
+            .add_event::<CoinEaten>()
+            .add_systems(Startup, spawn_growth_text)
+            .add_systems(OnEnter(GameState::Menu), reset_growth.after(start_game))
+            .add_systems(OnEnter(GameState::Playing), reset_growth.after(start_game))
+            .add_systems(OnEnter(GameState::Replay), reset_growth.after(start_game))
+            .add_systems(Update, clear_coins.run_if(on_event::<Rewound>()))
+            .add_systems(
+                Update,
+                reset_growth
+                    .after(start_game)
+                    .run_if(on_event::<RestartDemo>()),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    (move_coins, spawn_coins, eat_coins)
+                        .chain()
+                        .after(sweep_pipes)
+                        .before(check_collisions_and_scoring),
+                    add_bonus_points.after(check_collisions_and_scoring),
+                )
+                    .run_if(simulation_running),
+            )
+            .add_systems(Update, update_growth_text);
+    }
+}
+
+// Coins eaten this run, and the stream coins are placed from
+#[derive(Resource)]
+struct Growth {
+    coins: u32,
+    rng: StdRng,
+}
+
+impl Default for Growth {
+    fn default() -> Self {
+        Self {
+            coins: 0,
+            rng: StdRng::seed_from_u64(COIN_STREAM),
+        }
+    }
+}
+
+impl Growth {
+    fn scale(&self) -> f32 {
+        1.0 + self.coins as f32 * GROWTH_PER_COIN
+    }
+}
+
+// The bird ate a coin, for the wallet
+#[derive(Event)]
+pub struct CoinEaten;
+
+// One mesh and material for every coin, like PipeAssets
+#[derive(Resource)]
+struct CoinAssets {
+    circle: Mesh2dHandle,
+    material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for CoinAssets {
+    fn from_world(world: &mut World) -> Self {
+        let circle = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Circle::new(COIN_RADIUS));
+        Self {
+            circle: Mesh2dHandle(circle),
+            material: world
+                .resource_mut::<Assets<ColorMaterial>>()
+                .add(COIN_COLOR),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Coin;
+
+#[derive(Component)]
+struct GrowthText;
+
+fn spawn_growth_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: COIN_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Under the streak pips
+            top: Val::Px(72.0),
+            left: Val::Px(WINDOW_W * 0.5 - 60.0),
+            ..default()
+        }),
+        GrowthText,
+    ));
+}
+
+fn reset_growth(
+    mut commands: Commands,
+    run_seed: Res<RunSeed>,
+    mut growth: ResMut<Growth>,
+    coins: Query<Entity, With<Coin>>,
+) {
+    for e in &coins {
+        commands.entity(e).despawn_recursive();
+    }
+    *growth = Growth {
+        coins: 0,
+        rng: StdRng::seed_from_u64(run_seed.0 ^ COIN_STREAM),
+    };
+}
+
+// A rewind doesn't bring coins back (see rewind.rs); those eaten stay eaten
+fn clear_coins(mut commands: Commands, coins: Query<Entity, With<Coin>>) {
+    for e in &coins {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// Coins scroll with the pipes
+fn move_coins(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    course: Course,
+    mut coins: Query<(Entity, &mut Transform), With<Coin>>,
+) {
+    let facing = course.facing();
+    let step = course.pipe_speed() * time.delta_seconds() * facing;
+    for (e, mut tf) in &mut coins {
+        tf.translation.x += step;
+        if tf.translation.x * facing < PIPE_DESPAWN_X {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+fn spawn_coins(
+    mut commands: Commands,
+    run_modifiers: Res<RunModifiers>,
+    assets: Res<CoinAssets>,
+    mut growth: ResMut<Growth>,
+    new_pipes: NewPipes,
+) {
+    if !run_modifiers.0.has(Modifier::Growth) {
+        return;
+    }
+    let Some(center) = new_gap_center(&new_pipes) else {
+        return;
+    };
+    if !growth.rng.gen_bool(COIN_CHANCE) {
+        return;
+    }
+    let transform = Transform::from_translation(center.extend(0.5));
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: assets.circle.clone(),
+            material: assets.material.clone(),
+            transform,
+            ..default()
+        },
+        FixedTransform::new(transform),
+        Coin,
+    ));
+}
+
+// Coins, never the bird whose transform is being grown
+type EdibleCoin = (With<Coin>, Without<Bird>);
+
+// Eating a coin grows the lead bird straight away, so this tick's collisions
+// already use the bigger hitbox
+fn eat_coins(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    mut growth: ResMut<Growth>,
+    mut bird_q: Query<&mut Transform, LeadBird>,
+    coins: Query<(Entity, &Transform), EdibleCoin>,
+    mut eaten: EventWriter<CoinEaten>,
+) {
+    let Ok(mut bird_tf) = bird_q.get_single_mut() else {
+        return;
+    };
+    let reach = BIRD_SIZE * 0.5 * bird_tf.scale.truncate() + Vec2::splat(COIN_RADIUS);
+    for (e, tf) in &coins {
+        let distance = (bird_tf.translation - tf.translation).truncate().abs();
+        if distance.x > reach.x || distance.y > reach.y {
+            continue;
+        }
+        commands.entity(e).despawn_recursive();
+        growth.coins = (growth.coins + 1).min(MAX_COINS);
+        bird_tf.scale = Vec3::splat(growth.scale());
+        // The menu demo doesn't count
+        if *state.get() != GameState::Menu {
+            eaten.send(CoinEaten);
+        }
+    }
+}
+
+// A point per coin eaten on top of each pipe's own
+fn add_bonus_points(
+    growth: Res<Growth>,
+    mut passed: EventReader<PipePassed>,
+    mut score: ResMut<Score>,
+) {
+    let pipes = passed.read().count() as u32;
+    if pipes > 0 && growth.coins > 0 {
+        score.0 += pipes * growth.coins;
+    }
+}
+
+fn update_growth_text(growth: Res<Growth>, mut q: Query<&mut Text, With<GrowthText>>) {
+    if !growth.is_changed() {
+        return;
+    }
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = match growth.coins {
+        0 => String::new(),
+        coins => format!("{} points a pipe", coins + 1),
+    };
+}
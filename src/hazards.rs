@@ -0,0 +1,314 @@
+// Hazards: now and then a small enemy bird flies across the course against the
+// pipes, at its own height and speed, timed to cross the bird's path between
+// two pairs of pipes. A blinking arrow at the edge of the screen warns where it
+// will come in. Touching one ends the run like a pipe does (a streak's shield
+// covers them too); pipes don't bother them.
+//
+// They're placed from their own stream of the run seed, so replays see the
+// same ones. Challenge presets can turn them off.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::atlas::SpriteAtlas;
+use crate::settings::AccessibilitySettings;
+use crate::{
+    check_collisions_and_scoring, new_gap_center, simulation_running, start_game, sweep_pipes,
+    Bird, Course, FixedTransform, GameState, NewPipes, RestartDemo, Rewound, RunModifiers,
+    RunRules, RunSeed, BIRD_SIZE, BIRD_START_X, PIPE_DESPAWN_X, PIPE_SPAWN_INTERVAL, PIPE_SPAWN_X,
+    WINDOW_H, WINDOW_W,
+};
+
+// Mixed into the run seed for sending hazards, so they don't move the pipes
+const HAZARD_STREAM: u64 = 0xbadb_1d5b_adb1_d5ba;
+// Chance that a new pair of pipes has a hazard following it, once the run is
+// CALM_PIPES pairs in
+const HAZARD_CHANCE: f64 = 0.2;
+const CALM_PIPES: u32 = 4;
+// Speed along the course, as a multiple of the pipes'
+const HAZARD_SPEEDS: std::ops::RangeInclusive<f32> = 1.6..=2.6;
+// Kept this far from the floor and the ceiling
+const HAZARD_MARGIN: f32 = 40.0;
+// Drawn smaller than the player's bird
+const HAZARD_SCALE: f32 = 0.75;
+pub const HAZARD_SIZE: Vec2 = Vec2::new(BIRD_SIZE.x * HAZARD_SCALE, BIRD_SIZE.y * HAZARD_SCALE);
+const HAZARD_TINT: Color = Color::srgb(1.0, 0.35, 0.3);
+// The warning shows for this long before a hazard comes into view
+const WARNING_SECS: f32 = 1.0;
+const WARNING_SIZE: f32 = 14.0;
+const WARNING_COLOR: Color = Color::srgb(1.0, 0.25, 0.2);
+const WARNING_BLINK_HZ: f32 = 6.0;
+
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HazardStream>()
+            .init_resource::<WarningAssets>()
+            .add_systems(OnEnter(GameState::Menu), reset_hazards.after(start_game))
+            .add_systems(OnEnter(GameState::Playing), reset_hazards.after(start_game))
+            .add_systems(OnEnter(GameState::Replay), reset_hazards.after(start_game))
+            .add_systems(Update, clear_hazards.run_if(on_event::<Rewound>()))
+            .add_systems(
+                Update,
+                reset_hazards
+                    .after(start_game)
+                    .run_if(on_event::<RestartDemo>()),
+            )
+            .add_systems(
+                FixedUpdate,
+                (move_hazards, spawn_hazards, place_warnings)
+                    .chain()
+                    .after(sweep_pipes)
+                    .before(check_collisions_and_scoring)
+                    .run_if(simulation_running),
+            )
+            .add_systems(Update, (animate_hazards, blink_warnings));
+    }
+}
+
+// Whether this run has hazards, pipes spawned so far, and the stream hazards
+// are sent from
+#[derive(Resource)]
+struct HazardStream {
+    enabled: bool,
+    pipes: u32,
+    rng: StdRng,
+}
+
+impl Default for HazardStream {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pipes: 0,
+            rng: StdRng::seed_from_u64(HAZARD_STREAM),
+        }
+    }
+}
+
+// One arrow mesh and material for every warning; blinking fades the material,
+// so they all blink together
+#[derive(Resource)]
+struct WarningAssets {
+    arrow: Mesh2dHandle,
+    material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for WarningAssets {
+    fn from_world(world: &mut World) -> Self {
+        // Pointing along -x, the way hazards fly on a normal course
+        let arrow = world.resource_mut::<Assets<Mesh>>().add(Triangle2d::new(
+            Vec2::new(-WARNING_SIZE * 0.5, 0.0),
+            Vec2::new(WARNING_SIZE * 0.5, WARNING_SIZE * 0.6),
+            Vec2::new(WARNING_SIZE * 0.5, -WARNING_SIZE * 0.6),
+        ));
+        Self {
+            arrow: Mesh2dHandle(arrow),
+            material: world
+                .resource_mut::<Assets<ColorMaterial>>()
+                .add(WARNING_COLOR),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Hazard {
+    // Along the course, in px / s
+    speed: f32,
+    frames: Vec<usize>,
+    anim_timer: Timer,
+}
+
+// The warning for a hazard still off screen
+#[derive(Component)]
+struct HazardWarning(Entity);
+
+// Hazards in flight, for the collision check
+#[derive(SystemParam)]
+pub struct Hazards<'w, 's> {
+    hazards: Query<'w, 's, &'static Transform, (With<Hazard>, Without<Bird>)>,
+}
+
+impl Hazards<'_, '_> {
+    // Whether a bird at `pos` touches a hazard
+    pub fn hit(&self, pos: Vec2, hitbox_half: Vec2) -> bool {
+        self.hazards.iter().any(|tf| {
+            let distance = (pos - tf.translation.truncate()).abs();
+            distance.x <= hitbox_half.x + HAZARD_SIZE.x * 0.5
+                && distance.y <= hitbox_half.y + HAZARD_SIZE.y * 0.5
+        })
+    }
+}
+
+type HazardObject = Or<(With<Hazard>, With<HazardWarning>)>;
+
+fn reset_hazards(
+    mut commands: Commands,
+    run_seed: Res<RunSeed>,
+    run_rules: Res<RunRules>,
+    mut stream: ResMut<HazardStream>,
+    objects: Query<Entity, HazardObject>,
+) {
+    for e in &objects {
+        commands.entity(e).despawn_recursive();
+    }
+    *stream = HazardStream {
+        enabled: run_rules.0.hazards,
+        pipes: 0,
+        rng: StdRng::seed_from_u64(run_seed.0 ^ HAZARD_STREAM),
+    };
+}
+
+// A rewind doesn't bring hazards back, so it clears the ones in flight (see
+// rewind.rs); the stream carries on from where it was
+fn clear_hazards(mut commands: Commands, objects: Query<Entity, HazardObject>) {
+    for e in &objects {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// Hazards fly the same way as the pipes, only faster
+fn move_hazards(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    run_modifiers: Res<RunModifiers>,
+    mut hazards: Query<(Entity, &mut Transform, &Hazard)>,
+) {
+    let facing = run_modifiers.0.facing();
+    for (e, mut tf, hazard) in &mut hazards {
+        tf.translation.x -= hazard.speed * time.delta_seconds() * facing;
+        if tf.translation.x * facing < PIPE_DESPAWN_X {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+// A pair of pipes spawned this tick may send a hazard after it. It starts far
+// enough back to reach the bird halfway between that pair and the next, and
+// with its warning already up.
+fn spawn_hazards(
+    mut commands: Commands,
+    course: Course,
+    atlas: Res<SpriteAtlas>,
+    assets: Res<WarningAssets>,
+    mut stream: ResMut<HazardStream>,
+    new_pipes: NewPipes,
+) {
+    if !stream.enabled || new_gap_center(&new_pipes).is_none() {
+        return;
+    }
+    stream.pipes += 1;
+    if stream.pipes <= CALM_PIPES || !stream.rng.gen_bool(HAZARD_CHANCE) {
+        return;
+    }
+    let half_h = WINDOW_H * 0.5 - HAZARD_MARGIN - HAZARD_SIZE.y * 0.5;
+    let y = stream.rng.gen_range(-half_h..=half_h);
+    let pipe_speed = course.pipe_speed().abs();
+    let speed = pipe_speed * stream.rng.gen_range(HAZARD_SPEEDS);
+
+    let facing = course.facing();
+    let meets_bird = (PIPE_SPAWN_X - BIRD_START_X) / pipe_speed + PIPE_SPAWN_INTERVAL * 0.5;
+    let x = (BIRD_START_X + speed * meets_bird) * facing;
+    let frames = atlas.frames("bird");
+    let transform =
+        Transform::from_xyz(x, y, 0.9).with_scale(Vec3::new(HAZARD_SCALE, HAZARD_SCALE, 1.0));
+    let hazard = commands
+        .spawn((
+            SpriteBundle {
+                texture: atlas.texture.clone(),
+                sprite: Sprite {
+                    color: HAZARD_TINT,
+                    // Facing the way it flies
+                    flip_x: facing > 0.0,
+                    ..default()
+                },
+                transform,
+                ..default()
+            },
+            TextureAtlas {
+                layout: atlas.layout.clone(),
+                index: frames.first().copied().unwrap_or_default(),
+            },
+            Hazard {
+                speed,
+                frames,
+                anim_timer: Timer::from_seconds(0.08, TimerMode::Repeating),
+            },
+            FixedTransform::new(transform),
+        ))
+        .id();
+
+    let warning_x = (WINDOW_W * 0.5 - WARNING_SIZE) * facing;
+    let warning_transform =
+        Transform::from_xyz(warning_x, y, 0.95).with_scale(Vec3::new(facing, 1.0, 1.0));
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: assets.arrow.clone(),
+            material: assets.material.clone(),
+            transform: warning_transform,
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        FixedTransform::new(warning_transform),
+        HazardWarning(hazard),
+    ));
+}
+
+// Each warning shows for the last WARNING_SECS before its hazard comes into
+// view, and goes once it has
+fn place_warnings(
+    mut commands: Commands,
+    run_modifiers: Res<RunModifiers>,
+    hazards: Query<(&Transform, &Hazard)>,
+    mut warnings: Query<(Entity, &HazardWarning, &mut Visibility)>,
+) {
+    let facing = run_modifiers.0.facing();
+    let edge = WINDOW_W * 0.5 + HAZARD_SIZE.x * 0.5;
+    for (e, warning, mut visibility) in &mut warnings {
+        let Ok((tf, hazard)) = hazards.get(warning.0) else {
+            commands.entity(e).despawn_recursive();
+            continue;
+        };
+        let ahead = tf.translation.x * facing - edge;
+        if ahead <= 0.0 {
+            commands.entity(e).despawn_recursive();
+        } else if ahead <= hazard.speed * WARNING_SECS && *visibility == Visibility::Hidden {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+fn animate_hazards(time: Res<Time>, mut q: Query<(&mut Hazard, &mut TextureAtlas)>) {
+    for (mut hazard, mut atlas) in &mut q {
+        if hazard.anim_timer.tick(time.delta()).just_finished() && !hazard.frames.is_empty() {
+            let next = hazard
+                .frames
+                .iter()
+                .position(|&i| i == atlas.index)
+                .map_or(0, |pos| (pos + 1) % hazard.frames.len());
+            atlas.index = hazard.frames[next];
+        }
+    }
+}
+
+// Steady instead of blinking with reduced motion
+fn blink_warnings(
+    time: Res<Time>,
+    accessibility: Res<AccessibilitySettings>,
+    assets: Res<WarningAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let lit = accessibility.reduced_motion
+        || ((time.elapsed_seconds() * WARNING_BLINK_HZ * 2.0) as u32).is_multiple_of(2);
+    let alpha = if lit { 1.0 } else { 0.3 };
+    // Only touch the asset when it changes, so it isn't re-uploaded every frame
+    let current = materials.get(&assets.material).map(|m| m.color.alpha());
+    if current.is_some_and(|current| current != alpha) {
+        if let Some(material) = materials.get_mut(&assets.material) {
+            material.color.set_alpha(alpha);
+        }
+    }
+}
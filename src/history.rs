@@ -0,0 +1,511 @@
+// Run history: every run the player flies, saved one per line in history.txt
+// next to the settings, so it can be exported for spreadsheets and scripts.
+// Export from the settings menu (Export run history) or with
+// `--export-stats <file>`, as CSV, or JSON for a file ending in .json.
+//
+// history.txt is tab-separated: date, score, duration in seconds, flaps, seed,
+// mode, distance in meters, what the bird crashed into and the speedrun splits
+// in seconds, comma-separated (the last three missing from older lines, the
+// cause empty for a run that ended without a crash, the splits for a run that
+// wasn't a speedrun). Lines that don't parse (from a newer version) are
+// skipped.
+//
+// The game over screen lists the last RECENT_RUNS runs from it, newest first.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::distance::Distance;
+use crate::presets::ActivePreset;
+use crate::speedrun::Speedrun;
+use crate::timer::{pace, run_seconds};
+use crate::toast::ShowToast;
+use crate::{
+    CrashCause, Crashed, Flapped, GameOverUI, GameState, RunModifiers, RunRanking, RunSeed,
+    RunTick, Score,
+};
+
+// Runs listed on the game over screen
+const RECENT_RUNS: usize = 10;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunFlaps>()
+            .insert_resource(RecentRuns(load_recent()))
+            .add_event::<ExportStats>()
+            .add_systems(OnEnter(GameState::Playing), reset_run_flaps)
+            .add_systems(
+                Update,
+                (
+                    count_run_flaps.run_if(in_state(GameState::Playing)),
+                    export_stats.run_if(on_event::<ExportStats>()),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (record_run, show_recent_runs).chain(),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Csv, ExportFormat::Json];
+
+    // For the settings file, and the exported file's extension
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+        }
+    }
+
+    fn from_path(path: &Path) -> Self {
+        if path.extension().is_some_and(|ext| ext == "json") {
+            ExportFormat::Json
+        } else {
+            ExportFormat::Csv
+        }
+    }
+}
+
+// Write the history out in this format; sent from the settings menu
+#[derive(Event)]
+pub struct ExportStats(pub ExportFormat);
+
+#[derive(Clone, Debug)]
+struct RunRecord {
+    // Local time the run ended, "2026-10-15 18:04:31"
+    date: String,
+    score: u32,
+    duration: f32,
+    flaps: u32,
+    seed: u64,
+    // "Normal", the modifiers ("Night, Wind") or the preset ("Preset: Rush Hour")
+    mode: String,
+    distance: f32,
+    // None if the run ended some other way (quit, a finished story level or
+    // speedrun)
+    cause: Option<CrashCause>,
+    // Seconds into the run of each speedrun split (see speedrun.rs)
+    splits: Vec<f32>,
+}
+
+impl RunRecord {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{:.2}\t{}\t{}\t{}\t{:.1}\t{}\t{}",
+            self.date,
+            self.score,
+            self.duration,
+            self.flaps,
+            self.seed,
+            self.mode,
+            self.distance,
+            self.cause_name(),
+            self.splits_text()
+        )
+    }
+
+    // "12.40,25.10", as saved
+    fn splits_text(&self) -> String {
+        let splits: Vec<String> = self.splits.iter().map(|s| format!("{s:.2}")).collect();
+        splits.join(",")
+    }
+
+    fn cause_name(&self) -> &'static str {
+        self.cause.map_or("", CrashCause::name)
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        Some(Self {
+            date: fields.next()?.to_string(),
+            score: fields.next()?.parse().ok()?,
+            duration: fields.next()?.parse().ok()?,
+            flaps: fields.next()?.parse().ok()?,
+            seed: fields.next()?.parse().ok()?,
+            mode: fields.next()?.to_string(),
+            distance: match fields.next() {
+                Some(distance) => distance.parse().ok()?,
+                None => 0.0,
+            },
+            cause: fields.next().and_then(CrashCause::from_name),
+            splits: fields
+                .next()
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|split| split.parse().ok())
+                .collect(),
+        })
+    }
+}
+
+// Flaps so far this run
+#[derive(Resource, Default)]
+struct RunFlaps(u32);
+
+// The last RECENT_RUNS runs, oldest first
+#[derive(Resource, Default)]
+struct RecentRuns(VecDeque<RunRecord>);
+
+fn history_path() -> PathBuf {
+    crate::data_file("history.txt")
+}
+
+fn load() -> Vec<RunRecord> {
+    std::fs::read_to_string(history_path())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(RunRecord::parse)
+        .collect()
+}
+
+fn load_recent() -> VecDeque<RunRecord> {
+    let records = load();
+    let start = records.len().saturating_sub(RECENT_RUNS);
+    records[start..].iter().cloned().collect()
+}
+
+fn append(record: &RunRecord) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let path = history_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", record.to_line())
+}
+
+fn reset_run_flaps(mut flaps: ResMut<RunFlaps>) {
+    flaps.0 = 0;
+}
+
+fn count_run_flaps(mut flapped: EventReader<Flapped>, mut flaps: ResMut<RunFlaps>) {
+    let count = flapped.read().count() as u32;
+    if count > 0 {
+        flaps.0 += count;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_run(
+    ranking: RunRanking,
+    score: Res<Score>,
+    run_tick: Res<RunTick>,
+    run_seed: Res<RunSeed>,
+    run_modifiers: Res<RunModifiers>,
+    preset: Res<ActivePreset>,
+    flaps: Res<RunFlaps>,
+    distance: Res<Distance>,
+    speedrun: Res<Speedrun>,
+    mut crashed: EventReader<Crashed>,
+    mut recent: ResMut<RecentRuns>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let cause = crashed.read().last().map(|crashed| crashed.0);
+    if !ranking.flown_by_player() {
+        return;
+    }
+    let mode = match &preset.0 {
+        Some(preset) => format!("Preset: {}", preset.name.replace('\t', " ")),
+        None if run_modifiers.0.is_empty() => "Normal".to_string(),
+        None => run_modifiers.0.to_string(),
+    };
+    let record = RunRecord {
+        date: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        score: score.0,
+        duration: run_seconds(run_tick.0),
+        flaps: flaps.0,
+        seed: run_seed.0,
+        mode,
+        distance: distance.meters,
+        cause,
+        splits: speedrun.split_seconds(),
+    };
+    if let Err(err) = append(&record) {
+        error!("Cannot save run history: {err}");
+        toasts.send(ShowToast("Couldn't save run history".to_string()));
+    }
+    recent.0.push_back(record);
+    if recent.0.len() > RECENT_RUNS {
+        recent.0.pop_front();
+    }
+}
+
+// Left of the game over text: score, time and what ended each run
+fn show_recent_runs(mut commands: Commands, ranking: RunRanking, recent: Res<RecentRuns>) {
+    if !ranking.flown_by_player() || recent.0.is_empty() {
+        return;
+    }
+    let mut list = String::from("Recent runs\n");
+    for run in recent.0.iter().rev() {
+        let seconds = run.duration as u32;
+        let _ = writeln!(
+            list,
+            "{:>4}  {}:{:02}  {}",
+            run.score,
+            seconds / 60,
+            seconds % 60,
+            run.cause.map_or("-", CrashCause::name)
+        );
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            list,
+            TextStyle {
+                font_size: 16.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(120.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        GameOverUI,
+    ));
+}
+
+// --------------------------------------------
+// Export
+// --------------------------------------------
+
+fn to_csv(records: &[RunRecord]) -> String {
+    let mut out = String::from(
+        "date,score,duration_seconds,points_per_minute,flaps,seed,mode,distance_meters,cause,splits\n",
+    );
+    for r in records {
+        let _ = writeln!(
+            out,
+            "{},{},{:.2},{:.1},{},{},{},{:.1},{},{}",
+            r.date,
+            r.score,
+            r.duration,
+            pace(r.score, r.duration),
+            r.flaps,
+            r.seed,
+            csv_field(&r.mode),
+            r.distance,
+            r.cause_name(),
+            csv_field(&r.splits_text())
+        );
+    }
+    out
+}
+
+// Quoted if it has a comma, a quote or a line break in it, with quotes doubled
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_json(records: &[RunRecord]) -> String {
+    let runs: Vec<String> = records
+        .iter()
+        .map(|r| {
+            format!(
+                "  {{\"date\": {}, \"score\": {}, \"duration_seconds\": {:.2}, \"points_per_minute\": {:.1}, \"flaps\": {}, \"seed\": {}, \"mode\": {}, \"distance_meters\": {:.1}, \"cause\": {}, \"splits\": [{}]}}",
+                json_string(&r.date),
+                r.score,
+                r.duration,
+                pace(r.score, r.duration),
+                r.flaps,
+                r.seed,
+                json_string(&r.mode),
+                r.distance,
+                r.cause.map_or("null".to_string(), |cause| json_string(cause.name())),
+                r.splits_text().replace(',', ", ")
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", runs.join(",\n"))
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Write the whole history to `path`, returning how many runs it had
+fn export_to(path: &Path, format: ExportFormat) -> std::io::Result<usize> {
+    let records = load();
+    let text = match format {
+        ExportFormat::Csv => to_csv(&records),
+        ExportFormat::Json => to_json(&records),
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, text)?;
+    Ok(records.len())
+}
+
+// `--export-stats <file>`: export without opening the game, in the format the
+// file name asks for. Runs before the app exists, so problems go to stderr.
+pub fn export_from_cli(path: &Path) -> bool {
+    match export_to(path, ExportFormat::from_path(path)) {
+        Ok(runs) => {
+            println!("Exported {runs} runs to {}", path.display());
+            true
+        }
+        Err(err) => {
+            eprintln!("Cannot export stats to {}: {err}", path.display());
+            false
+        }
+    }
+}
+
+// <Documents>/Floopy Birb, or ./exports when the OS has no documents folder
+fn exports_dir() -> PathBuf {
+    dirs::document_dir()
+        .map(|dir| dir.join("Floopy Birb"))
+        .unwrap_or_else(|| PathBuf::from("exports"))
+}
+
+fn export_stats(mut events: EventReader<ExportStats>, mut toasts: EventWriter<ShowToast>) {
+    for ExportStats(format) in events.read() {
+        let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+        let path = exports_dir().join(format!("runs_{timestamp}.{}", format.name()));
+        let message = match export_to(&path, *format) {
+            Ok(runs) => format!("Exported {runs} runs to {}", path.display()),
+            Err(err) => {
+                error!("Cannot export stats to {}: {err}", path.display());
+                "Couldn't export run history".to_string()
+            }
+        };
+        toasts.send(ShowToast(message));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> [RunRecord; 2] {
+        [
+            RunRecord {
+                date: "2026-10-15 18:04:31".to_string(),
+                score: 12,
+                duration: 60.0,
+                flaps: 30,
+                seed: 7,
+                mode: "Preset: Rush, \"Hour\"".to_string(),
+                distance: 123.0,
+                cause: Some(CrashCause::Pipe),
+                splits: vec![12.4, 25.1],
+            },
+            RunRecord {
+                date: "2026-10-15 18:05:02".to_string(),
+                score: 0,
+                duration: 0.5,
+                flaps: 1,
+                seed: u64::MAX,
+                mode: "Normal".to_string(),
+                distance: 0.0,
+                cause: None,
+                splits: Vec::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn csv_escaping() {
+        assert_eq!(csv_field("Night, Wind"), "\"Night, Wind\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("a\rb"), "\"a\rb\"");
+        assert_eq!(csv_field("Normal"), "Normal");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn csv_export() {
+        let csv = to_csv(&records());
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "2026-10-15 18:04:31,12,60.00,12.0,30,7,\"Preset: Rush, \"\"Hour\"\"\",123.0,pipe,\"12.40,25.10\""
+        );
+        assert_eq!(
+            lines[2],
+            "2026-10-15 18:05:02,0,0.50,0.0,1,18446744073709551615,Normal,0.0,,"
+        );
+    }
+
+    #[test]
+    fn json_escaping() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(
+            json_string("tab\tline\n\u{1}"),
+            "\"tab\\u0009line\\u000a\\u0001\""
+        );
+        assert_eq!(json_string("Nuit étoilée 🌙"), "\"Nuit étoilée 🌙\"");
+    }
+
+    #[test]
+    fn json_export() {
+        let json = to_json(&records());
+        assert!(json.starts_with("[\n") && json.ends_with("\n]\n"));
+        assert!(json.contains("\"mode\": \"Preset: Rush, \\\"Hour\\\"\""));
+        assert!(json.contains("\"cause\": \"pipe\", \"splits\": [12.40, 25.10]}"));
+        assert!(json.contains("\"cause\": null, \"splits\": []}"));
+        assert_eq!(to_json(&[]), "[\n\n]\n");
+    }
+
+    #[test]
+    fn history_lines() {
+        for record in records() {
+            let line = record.to_line();
+            assert_eq!(RunRecord::parse(&line).unwrap().to_line(), line);
+        }
+        // Older lines, without the distance, cause and splits
+        let old = RunRecord::parse("2026-01-01 10:00:00\t3\t9.50\t8\t42\tNormal").unwrap();
+        assert_eq!(old.distance, 0.0);
+        assert!(old.cause.is_none() && old.splits.is_empty());
+
+        for line in ["", "garbage", "date\tlots\t1\t2\t3\tNormal", "d\t1\t2\t3"] {
+            assert!(RunRecord::parse(line).is_none(), "{line}");
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// Hitbox overlay (F3): outlines everything the collision check uses, where the
+// last fixed step put it, for tuning the hitbox presets and new obstacles by
+// eye. The bird's forgiving hitbox is red with the full sprite it scores with
+// in white, pipes green, coins yellow, hazards orange, and the floor and
+// ceiling blue.
+
+use bevy::prelude::*;
+
+use crate::growth::{Coin, COIN_RADIUS};
+use crate::hazards::{Hazard, HAZARD_SIZE};
+use crate::{Bird, FixedTransform, Pipe, RunHitbox, RunModifiers, BIRD_SIZE, WINDOW_H, WINDOW_W};
+
+const HITBOX_COLOR: Color = Color::srgb(1.0, 0.1, 0.1);
+const SPRITE_COLOR: Color = Color::WHITE;
+const PIPE_COLOR: Color = Color::srgb(0.2, 1.0, 0.3);
+const COIN_COLOR: Color = Color::srgb(1.0, 0.9, 0.1);
+const HAZARD_COLOR: Color = Color::srgb(1.0, 0.55, 0.1);
+const BOUNDARY_COLOR: Color = Color::srgb(0.2, 0.6, 1.0);
+
+pub struct HitboxesPlugin;
+
+impl Plugin for HitboxesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShowHitboxes>().add_systems(
+            Update,
+            (
+                toggle_hitboxes,
+                draw_hitboxes.run_if(|show: Res<ShowHitboxes>| show.0),
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct ShowHitboxes(bool);
+
+fn toggle_hitboxes(input: Res<ButtonInput<KeyCode>>, mut show: ResMut<ShowHitboxes>) {
+    if input.just_pressed(KeyCode::F3) {
+        show.0 = !show.0;
+    }
+}
+
+// Collision happens in course space; boxes are drawn through the same view as
+// everything else, so they line up on a vertical course too
+#[allow(clippy::too_many_arguments)]
+fn draw_hitboxes(
+    mut gizmos: Gizmos,
+    run_modifiers: Res<RunModifiers>,
+    run_hitbox: Res<RunHitbox>,
+    birds: Query<&FixedTransform, With<Bird>>,
+    pipes: Query<(&FixedTransform, &Pipe)>,
+    coins: Query<&FixedTransform, With<Coin>>,
+    hazards: Query<&FixedTransform, With<Hazard>>,
+) {
+    let view = run_modifiers.0.course_to_world();
+    let rotation = view.rotation.to_euler(EulerRot::XYZ).2;
+    let scale = view.scale.truncate();
+    let mut rect = |center: Vec2, size: Vec2, color: Color| {
+        let center = view.transform_point(center.extend(0.0)).truncate();
+        gizmos.rect_2d(center, rotation, size * scale, color);
+    };
+
+    for fixed in &birds {
+        let tf = fixed.current;
+        let size = BIRD_SIZE * tf.scale.truncate();
+        rect(tf.translation.truncate(), size, SPRITE_COLOR);
+        rect(
+            tf.translation.truncate(),
+            size * (1.0 - run_hitbox.0),
+            HITBOX_COLOR,
+        );
+    }
+    for (fixed, pipe) in &pipes {
+        rect(fixed.current.translation.truncate(), pipe.size, PIPE_COLOR);
+    }
+    // A coin is picked up when its box touches the bird's full sprite
+    for fixed in &coins {
+        rect(
+            fixed.current.translation.truncate(),
+            Vec2::splat(COIN_RADIUS * 2.0),
+            COIN_COLOR,
+        );
+    }
+    for fixed in &hazards {
+        rect(
+            fixed.current.translation.truncate(),
+            HAZARD_SIZE,
+            HAZARD_COLOR,
+        );
+    }
+
+    let half_h = WINDOW_H * 0.5;
+    for y in [-half_h, half_h] {
+        rect(Vec2::new(0.0, y), Vec2::new(WINDOW_W, 0.0), BOUNDARY_COLOR);
+    }
+}
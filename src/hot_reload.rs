@@ -0,0 +1,147 @@
+// Asset hot-reloading for development (debug builds with the `hot_reload`
+// feature, on by default): Bevy watches assets/ and mods/ and reloads any file
+// that changes under the running game, so a new bird.png, city background or
+// music track shows up (or plays) without a restart.
+//
+// Images reload by themselves, everything drawn with them follows. A few things
+// need a hand:
+//   - the sprite sheet's layout: atlas.txt isn't an asset, so it's checked
+//     every so often, and when it or the sheet changes the layout is read again
+//     and scaled to the sheet if their sizes no longer agree (see
+//     `SpriteAtlas::reload_layout`)
+//   - the colors: palette.txt is checked the same way, and read again when it
+//     changes (see palette.rs)
+//   - music: a playing track keeps the sound it was started with, so a
+//     reloaded one starts over
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use bevy::prelude::*;
+
+use crate::atlas::{layout_file, SpriteAtlas};
+use crate::mods::Mods;
+use crate::music::MusicTrack;
+use crate::palette::{palette_file, Palette};
+use crate::{Bird, MusicState};
+
+// How often the config files are checked for changes
+const CONFIG_POLL_SECS: f32 = 0.5;
+
+pub struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, watch_config_files).add_systems(
+            Update,
+            (
+                tick_config_watch,
+                (reload_sprite_sheet, reload_palette),
+                restart_reloaded_music,
+            )
+                .chain(),
+        );
+        info!("Hot-reloading assets");
+    }
+}
+
+// Files read without the asset server, which Bevy doesn't watch
+#[derive(Resource)]
+struct ConfigWatch {
+    timer: Timer,
+    // When each was last changed, as of the last check
+    layout: Option<SystemTime>,
+    palette: Option<SystemTime>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+// Whether the file changed since `seen`, which is brought up to date
+fn file_changed(seen: &mut Option<SystemTime>, path: &Path) -> bool {
+    let now = modified(path);
+    let changed = now != *seen;
+    *seen = now;
+    changed
+}
+
+fn watch_config_files(mut commands: Commands, mods: Res<Mods>) {
+    commands.insert_resource(ConfigWatch {
+        timer: Timer::new(
+            Duration::from_secs_f32(CONFIG_POLL_SECS),
+            TimerMode::Repeating,
+        ),
+        layout: modified(&layout_file(&mods)),
+        palette: modified(&palette_file(&mods)),
+    });
+}
+
+fn tick_config_watch(time: Res<Time<Real>>, mut watch: ResMut<ConfigWatch>) {
+    watch.timer.tick(time.delta());
+}
+
+// The birds flap through the regions named bird_..., which may have moved
+fn reload_sprite_sheet(
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut watch: ResMut<ConfigWatch>,
+    mods: Res<Mods>,
+    images: Res<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut atlas: ResMut<SpriteAtlas>,
+    mut birds: Query<(&mut TextureAtlas, &mut Bird)>,
+) {
+    let sheet_changed = image_events
+        .read()
+        .any(|event| event.is_modified(&atlas.texture));
+    let layout_changed =
+        watch.timer.just_finished() && file_changed(&mut watch.layout, &layout_file(&mods));
+    if !sheet_changed && !layout_changed {
+        return;
+    }
+    // Not loaded yet; the sheet's own event comes once it is
+    let Some(sheet) = images.get(&atlas.texture) else {
+        return;
+    };
+    atlas.reload_layout(&mods, sheet.size(), &mut layouts);
+    info!("Reloaded the sprite sheet");
+
+    let frames = atlas.frames("bird");
+    for (mut sprite_atlas, mut bird) in &mut birds {
+        sprite_atlas.index = frames.first().copied().unwrap_or_default();
+        bird.frames = frames.clone();
+    }
+}
+
+// The theme picks the new colors up from there
+fn reload_palette(mut watch: ResMut<ConfigWatch>, mods: Res<Mods>, mut palette: ResMut<Palette>) {
+    if !watch.timer.just_finished() || !file_changed(&mut watch.palette, &palette_file(&mods)) {
+        return;
+    }
+    let new_palette = Palette::load(&mods);
+    if *palette != new_palette {
+        *palette = new_palette;
+        info!("Reloaded the palette");
+    }
+}
+
+// Dropping the sink stops the old sound, and Bevy plays the track again, now
+// with the new one
+fn restart_reloaded_music(
+    mut commands: Commands,
+    mut audio_events: EventReader<AssetEvent<AudioSource>>,
+    music_state: Res<MusicState>,
+    mut tracks: Query<(Entity, &Handle<AudioSource>, &mut PlaybackSettings), With<MusicTrack>>,
+) {
+    for event in audio_events.read() {
+        for (e, source, mut settings) in &mut tracks {
+            if event.is_modified(source) {
+                info!("Reloaded the music track");
+                settings.paused = music_state.muted;
+                commands.entity(e).remove::<AudioSink>();
+            }
+        }
+    }
+}
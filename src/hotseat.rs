@@ -0,0 +1,296 @@
+// Hot-seat: G on the main menu. Two players sharing the keyboard type in their
+// names and then take turns, one run each, for as long as they like. The HUD
+// says whose turn it is, and every game over shows both players' running
+// totals side by side. Esc on the game over screen ends it.
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::{GameOverUI, GameState, Score, WINDOW_W};
+
+const MAX_NAME_LEN: usize = 12;
+
+pub struct HotSeatPlugin;
+
+impl Plugin for HotSeatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, open_hot_seat.run_if(in_state(GameState::Menu)))
+            .add_systems(OnEnter(GameState::HotSeat), show_name_entry)
+            .add_systems(OnExit(GameState::HotSeat), despawn_name_entry)
+            .add_systems(
+                Update,
+                (
+                    name_entry_input,
+                    update_name_entry.run_if(in_state(GameState::HotSeat)),
+                )
+                    .chain(),
+            )
+            .add_systems(Startup, spawn_turn_text)
+            .add_systems(Update, update_turn_text)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                show_comparison.run_if(resource_exists::<HotSeat>),
+            )
+            .add_systems(
+                Update,
+                leave_hot_seat
+                    .run_if(in_state(GameState::GameOver).and_then(resource_exists::<HotSeat>)),
+            )
+            .add_systems(OnEnter(GameState::Menu), end_hot_seat);
+    }
+}
+
+// The names typed in so far, while the name entry screen is up
+#[derive(Resource, Default)]
+struct NameEntry {
+    names: Vec<String>,
+    entry: String,
+}
+
+// Two players taking turns; exists only while hot-seat is on
+#[derive(Resource)]
+pub struct HotSeat {
+    seats: [Seat; 2],
+    // Whose run is next, or under way
+    turn: usize,
+}
+
+struct Seat {
+    name: String,
+    runs: u32,
+    total: u32,
+    best: u32,
+    last: Option<u32>,
+}
+
+impl Seat {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            runs: 0,
+            total: 0,
+            best: 0,
+            last: None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct NameEntryUI;
+
+#[derive(Component)]
+struct NameEntryText;
+
+#[derive(Component)]
+struct TurnText;
+
+fn open_hot_seat(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if input.just_pressed(KeyCode::KeyG) {
+        next_state.set(GameState::HotSeat);
+    }
+}
+
+fn show_name_entry(mut commands: Commands) {
+    commands.init_resource::<NameEntry>();
+    commands.spawn((
+        TextBundle::from_section(
+            "Hot-seat",
+            TextStyle {
+                font_size: 56.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(100.0),
+            left: Val::Px(WINDOW_W * 0.5 - 110.0),
+            ..default()
+        }),
+        NameEntryUI,
+    ));
+    // Filled in by update_name_entry
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 28.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(200.0),
+            left: Val::Px(WINDOW_W * 0.5 - 220.0),
+            ..default()
+        }),
+        NameEntryUI,
+        NameEntryText,
+    ));
+}
+
+fn despawn_name_entry(mut commands: Commands, q: Query<Entity, With<NameEntryUI>>) {
+    commands.remove_resource::<NameEntry>();
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// Runs in every state so it has always read past the keys pressed before the
+// screen opened, G included
+fn name_entry_input(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    mut keys: EventReader<KeyboardInput>,
+    entry: Option<ResMut<NameEntry>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(mut entry) = entry.filter(|_| *state.get() == GameState::HotSeat) else {
+        keys.clear();
+        return;
+    };
+    for key in keys.read() {
+        if !key.state.is_pressed() {
+            continue;
+        }
+        match &key.logical_key {
+            Key::Escape => next_state.set(GameState::Menu),
+            Key::Backspace => {
+                entry.entry.pop();
+            }
+            // An empty name is "Player 1" or "Player 2"
+            Key::Enter => {
+                let mut name = std::mem::take(&mut entry.entry).trim().to_string();
+                if name.is_empty() {
+                    name = format!("Player {}", entry.names.len() + 1);
+                }
+                entry.names.push(name);
+                if let [first, second] = &entry.names[..] {
+                    commands.insert_resource(HotSeat {
+                        seats: [Seat::new(first.clone()), Seat::new(second.clone())],
+                        turn: 0,
+                    });
+                    next_state.set(GameState::Playing);
+                    return;
+                }
+            }
+            Key::Space if entry.entry.chars().count() < MAX_NAME_LEN => entry.entry.push(' '),
+            Key::Character(text) => {
+                for c in text.chars() {
+                    if entry.entry.chars().count() < MAX_NAME_LEN
+                        && (c.is_alphanumeric() || c == ' ')
+                    {
+                        entry.entry.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_name_entry(entry: Res<NameEntry>, mut q: Query<&mut Text, With<NameEntryText>>) {
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    let mut value = String::from("Two players take turns, one run each\n\n");
+    for (i, name) in entry.names.iter().enumerate() {
+        value.push_str(&format!("Player {}: {name}\n", i + 1));
+    }
+    value.push_str(&format!(
+        "Player {}: {}_\n\nType a name and press Enter\nEsc to go back",
+        entry.names.len() + 1,
+        entry.entry
+    ));
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+fn spawn_turn_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        TurnText,
+    ));
+}
+
+// Only during a hot-seat run
+fn update_turn_text(
+    state: Res<State<GameState>>,
+    hot_seat: Option<Res<HotSeat>>,
+    mut q: Query<&mut Text, With<TurnText>>,
+) {
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    let value = match hot_seat {
+        Some(hot_seat) if *state.get() == GameState::Playing => {
+            format!("{}'s turn", hot_seat.seats[hot_seat.turn].name)
+        }
+        _ => String::new(),
+    };
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+// Counts the run that just ended and passes the keyboard on, above the game
+// over text
+fn show_comparison(mut commands: Commands, score: Res<Score>, mut hot_seat: ResMut<HotSeat>) {
+    let turn = hot_seat.turn;
+    let seat = &mut hot_seat.seats[turn];
+    seat.runs += 1;
+    seat.total += score.0;
+    seat.best = seat.best.max(score.0);
+    seat.last = Some(score.0);
+    hot_seat.turn = 1 - turn;
+
+    for (i, seat) in hot_seat.seats.iter().enumerate() {
+        let next = if i == hot_seat.turn { " - up next" } else { "" };
+        let last = seat.last.map_or("-".to_string(), |last| last.to_string());
+        commands.spawn((
+            TextBundle::from_section(
+                format!(
+                    "{}{next}\nLast run: {last}\nTotal: {} in {} runs\nBest: {}",
+                    seat.name, seat.total, seat.runs, seat.best
+                ),
+                TextStyle {
+                    font_size: 22.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(30.0),
+                left: Val::Px(WINDOW_W * 0.5 - 220.0 + 240.0 * i as f32),
+                ..default()
+            }),
+            GameOverUI,
+        ));
+    }
+}
+
+fn leave_hot_seat(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn end_hot_seat(mut commands: Commands) {
+    commands.remove_resource::<HotSeat>();
+}
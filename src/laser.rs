@@ -0,0 +1,410 @@
+// Laser power-up: now and then a glowing charge floats in the middle of a gap.
+// Flying through it gives the bird a laser shot, shown under the mute toggle; F
+// (or X / Square on a controller) fires it ahead along the course, and the
+// first pipe segment it meets is blown apart in a burst of sparks. A panic
+// button for a gap you aren't going to make.
+//
+// Charges are placed from their own stream of the run seed, and shots are
+// recorded in replays alongside flaps, so both play back exactly.
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::controls::GameInput;
+use crate::settings::AccessibilitySettings;
+use crate::{
+    check_collisions_and_scoring, new_gap_center, simulation_running, start_game, sweep_pipes,
+    Course, FixedTransform, GameState, LeadBird, NewPipes, Pipe, PipeSweep, RestartDemo, Rewound,
+    RunModifiers, RunSeed, BIRD_SIZE, PIPE_DESPAWN_X, WINDOW_W,
+};
+
+// Mixed into the run seed for placing charges, so they don't move the pipes
+const LASER_STREAM: u64 = 0x1a5e_1a5e_1a5e_1a5e;
+// Chance that a new pair of pipes has a charge in its gap
+const PICKUP_CHANCE: f64 = 0.08;
+const PICKUP_SIZE: f32 = 16.0;
+const PICKUP_COLOR: Color = Color::srgb(1.0, 0.35, 0.9);
+// Shots the bird can hold at once
+const MAX_SHOTS: u32 = 3;
+const SHOT_SPEED: f32 = 700.0; // px / s along the course
+const SHOT_SIZE: Vec2 = Vec2::new(28.0, 4.0);
+const SHOT_COLOR: Color = Color::srgb(1.0, 0.2, 0.25);
+const SPARKS: usize = 24;
+const SPARK_SIZE: f32 = 4.0;
+const SPARK_SPEED: f32 = 260.0; // px / s at most
+const SPARK_SECS: f32 = 0.5;
+
+pub struct LaserPlugin;
+
+impl Plugin for LaserPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Laser>()
+            .init_resource::<LaserInput>()
+            .add_event::<LaserFired>()
+            .add_event::<PipeBlasted>()
+            .add_systems(Startup, spawn_laser_text)
+            .add_systems(OnEnter(GameState::Menu), reset_laser.after(start_game))
+            .add_systems(OnEnter(GameState::Playing), reset_laser.after(start_game))
+            .add_systems(OnEnter(GameState::Replay), reset_laser.after(start_game))
+            .add_systems(
+                Update,
+                reset_laser
+                    .after(start_game)
+                    .run_if(on_event::<RestartDemo>()),
+            )
+            .add_systems(Update, clear_laser.run_if(on_event::<Rewound>()))
+            .add_systems(
+                Update,
+                buffer_fire_input.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    move_pickups,
+                    move_shots,
+                    spawn_pickups,
+                    collect_pickups,
+                    fire_laser,
+                    blast_pipes,
+                )
+                    .chain()
+                    .after(sweep_pipes)
+                    .before(check_collisions_and_scoring)
+                    .run_if(simulation_running),
+            )
+            .add_systems(Update, (spawn_sparks, fade_sparks, update_laser_text));
+    }
+}
+
+// Shots held, and the stream charges are placed from
+#[derive(Resource)]
+pub struct Laser {
+    shots: u32,
+    rng: StdRng,
+}
+
+impl Default for Laser {
+    fn default() -> Self {
+        Self {
+            shots: 0,
+            rng: StdRng::seed_from_u64(LASER_STREAM),
+        }
+    }
+}
+
+// Fire presses buffered from Update to FixedUpdate, like FlapInput
+#[derive(Resource, Default)]
+pub struct LaserInput {
+    pub requested: bool,
+}
+
+// A shot left the bird, for the replay recorder
+#[derive(Event)]
+pub struct LaserFired;
+
+// A shot destroyed the pipe at this point of the course
+#[derive(Event)]
+struct PipeBlasted(Vec2);
+
+#[derive(Component)]
+struct LaserPickup;
+
+#[derive(Component)]
+struct LaserShot;
+
+#[derive(Component)]
+struct Spark {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct LaserText;
+
+fn spawn_laser_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 22.0,
+                color: SHOT_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Below the mute toggle
+            top: Val::Px(40.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        LaserText,
+    ));
+}
+
+type LaserObject = Or<(With<LaserPickup>, With<LaserShot>, With<Spark>)>;
+
+fn reset_laser(
+    mut commands: Commands,
+    run_seed: Res<RunSeed>,
+    mut laser: ResMut<Laser>,
+    mut input: ResMut<LaserInput>,
+    objects: Query<Entity, LaserObject>,
+) {
+    for e in &objects {
+        commands.entity(e).despawn_recursive();
+    }
+    *laser = Laser {
+        shots: 0,
+        rng: StdRng::seed_from_u64(run_seed.0 ^ LASER_STREAM),
+    };
+    input.requested = false;
+}
+
+// Likewise charges and shots on a rewind (see rewind.rs); charges held stay held
+fn clear_laser(
+    mut commands: Commands,
+    mut input: ResMut<LaserInput>,
+    objects: Query<Entity, LaserObject>,
+) {
+    for e in &objects {
+        commands.entity(e).despawn_recursive();
+    }
+    input.requested = false;
+}
+
+fn buffer_fire_input(controls: GameInput, mut input: ResMut<LaserInput>) {
+    if controls.fire() {
+        input.requested = true;
+    }
+}
+
+// Charges scroll with the pipes
+fn move_pickups(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    course: Course,
+    mut pickups: Query<(Entity, &mut Transform), With<LaserPickup>>,
+) {
+    let facing = course.facing();
+    let step = course.pipe_speed() * time.delta_seconds() * facing;
+    for (e, mut tf) in &mut pickups {
+        tf.translation.x += step;
+        if tf.translation.x * facing < PIPE_DESPAWN_X {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+// Shots fly the other way, until they leave the screen
+fn move_shots(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    run_modifiers: Res<RunModifiers>,
+    mut shots: Query<(Entity, &mut Transform), With<LaserShot>>,
+) {
+    let facing = run_modifiers.0.facing();
+    for (e, mut tf) in &mut shots {
+        tf.translation.x += SHOT_SPEED * time.delta_seconds() * facing;
+        if tf.translation.x * facing > WINDOW_W * 0.5 + SHOT_SIZE.x {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+// A pair of pipes spawned this tick may carry a charge in its gap
+fn spawn_pickups(mut commands: Commands, mut laser: ResMut<Laser>, new_pipes: NewPipes) {
+    let Some(center) = new_gap_center(&new_pipes) else {
+        return;
+    };
+    if !laser.rng.gen_bool(PICKUP_CHANCE) {
+        return;
+    }
+    let transform = Transform::from_translation(center.extend(0.5))
+        .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4));
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: PICKUP_COLOR,
+                custom_size: Some(Vec2::splat(PICKUP_SIZE)),
+                ..default()
+            },
+            transform,
+            ..default()
+        },
+        FixedTransform::new(transform),
+        LaserPickup,
+    ));
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    mut laser: ResMut<Laser>,
+    bird_q: Query<&Transform, LeadBird>,
+    pickups: Query<(Entity, &Transform), With<LaserPickup>>,
+) {
+    // The menu demo never fires, so it leaves them be
+    if *state.get() == GameState::Menu {
+        return;
+    }
+    let Ok(bird_tf) = bird_q.get_single() else {
+        return;
+    };
+    let reach = (BIRD_SIZE + PICKUP_SIZE) * 0.5;
+    for (e, tf) in &pickups {
+        let distance = (bird_tf.translation - tf.translation).truncate().abs();
+        if distance.x > reach.x || distance.y > reach.y {
+            continue;
+        }
+        commands.entity(e).despawn_recursive();
+        laser.shots = (laser.shots + 1).min(MAX_SHOTS);
+    }
+}
+
+// Consume a buffered fire press, if there's a shot to fire
+pub fn fire_laser(
+    mut commands: Commands,
+    run_modifiers: Res<RunModifiers>,
+    mut laser: ResMut<Laser>,
+    mut input: ResMut<LaserInput>,
+    bird_q: Query<&Transform, LeadBird>,
+    mut fired: EventWriter<LaserFired>,
+) {
+    if !std::mem::take(&mut input.requested) || laser.shots == 0 {
+        return;
+    }
+    let Ok(bird_tf) = bird_q.get_single() else {
+        return;
+    };
+    laser.shots -= 1;
+    fired.send(LaserFired);
+
+    // From the bird's beak, level whichever way it's tilted
+    let facing = run_modifiers.0.facing();
+    let start = bird_tf.translation.truncate() + Vec2::X * facing * BIRD_SIZE.x * 0.5;
+    let transform = Transform::from_translation(start.extend(0.8));
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: SHOT_COLOR,
+                custom_size: Some(SHOT_SIZE),
+                ..default()
+            },
+            transform,
+            ..default()
+        },
+        FixedTransform::new(transform),
+        LaserShot,
+    ));
+}
+
+// A shot destroys the first pipe segment it touches and is spent. Only one pipe
+// of a pair scores, so blasting that one hands the point to the other.
+fn blast_pipes(
+    mut commands: Commands,
+    sweep: Res<PipeSweep>,
+    shots: Query<(Entity, &Transform), With<LaserShot>>,
+    mut pipes: Query<(Entity, &Transform, &mut Pipe), Without<LaserShot>>,
+    mut blasted: EventWriter<PipeBlasted>,
+) {
+    let shot_half = SHOT_SIZE * 0.5;
+    for (shot, shot_tf) in &shots {
+        let pos = shot_tf.translation.truncate();
+        let Some((target, target_pos, unscored)) = sweep
+            .overlapping(pos.x - shot_half.x, pos.x + shot_half.x)
+            .filter_map(|e| pipes.get(e).ok())
+            .find(|(_, tf, pipe)| {
+                let distance = (pos - tf.translation.truncate()).abs();
+                distance.x <= shot_half.x + pipe.size.x * 0.5
+                    && distance.y <= shot_half.y + pipe.size.y * 0.5
+            })
+            .map(|(e, tf, pipe)| (e, tf.translation, !pipe.scored))
+        else {
+            continue;
+        };
+
+        if unscored {
+            let partner = pipes
+                .iter_mut()
+                .find(|(e, tf, _)| *e != target && tf.translation.x == target_pos.x);
+            if let Some((_, _, mut pipe)) = partner {
+                pipe.scored = false;
+            }
+        }
+        commands.entity(target).despawn_recursive();
+        commands.entity(shot).despawn_recursive();
+        blasted.send(PipeBlasted(pos));
+    }
+}
+
+fn spawn_sparks(
+    mut commands: Commands,
+    mut blasted: EventReader<PipeBlasted>,
+    accessibility: Res<AccessibilitySettings>,
+    run_modifiers: Res<RunModifiers>,
+) {
+    let view = run_modifiers.0.course_to_world();
+    for PipeBlasted(pos) in blasted.read() {
+        if accessibility.reduced_motion {
+            continue;
+        }
+        // Cosmetic, so not from the run's random streams
+        let mut rng = rand::thread_rng();
+        let origin = view.transform_point(pos.extend(0.9));
+        for _ in 0..SPARKS {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let speed = rng.gen_range(0.3..1.0) * SPARK_SPEED;
+            let color = if rng.gen_bool(0.5) {
+                SHOT_COLOR
+            } else {
+                Color::srgb(1.0, 0.85, 0.4)
+            };
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(SPARK_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(origin),
+                    ..default()
+                },
+                Spark {
+                    velocity: Vec2::from_angle(angle) * speed,
+                    timer: Timer::from_seconds(SPARK_SECS, TimerMode::Once),
+                },
+            ));
+        }
+    }
+}
+
+fn fade_sparks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sparks: Query<(Entity, &mut Transform, &mut Sprite, &mut Spark)>,
+) {
+    for (e, mut tf, mut sprite, mut spark) in &mut sparks {
+        if spark.timer.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        tf.translation += (spark.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_alpha(spark.timer.fraction_remaining());
+    }
+}
+
+fn update_laser_text(laser: Res<Laser>, mut q: Query<&mut Text, With<LaserText>>) {
+    if !laser.is_changed() {
+        return;
+    }
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = match laser.shots {
+        0 => String::new(),
+        1 => "Laser ready [F]".to_string(),
+        n => format!("Laser x{n} [F]"),
+    };
+}
@@ -0,0 +1,1795 @@
+use std::fmt::Write;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Time the rest of the enclosing block as a named span in Tracy, with the
+// `profiling` feature; compiles to nothing otherwise. Takes `info_span!` args.
+macro_rules! profile_span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "profiling")]
+        let _span = bevy::log::info_span!($($args)*).entered();
+    };
+}
+
+mod achievements;
+pub mod agent;
+mod atlas;
+mod attract;
+mod autopilot;
+mod branding;
+#[cfg(feature = "broadcast")]
+mod broadcast;
+#[cfg(feature = "capture")]
+mod capture;
+mod challenge;
+mod cheats;
+mod cli;
+mod console;
+mod controls;
+mod decay;
+#[cfg(feature = "discord")]
+mod discord;
+mod distance;
+mod flock;
+mod gamepad;
+mod growth;
+mod hazards;
+mod history;
+mod hitboxes;
+#[cfg(all(feature = "hot_reload", debug_assertions))]
+mod hot_reload;
+mod hotseat;
+mod laser;
+mod lobby;
+mod modifiers;
+mod mods;
+mod music;
+mod notify;
+mod overlay;
+mod palette;
+mod pause;
+mod photo;
+mod power;
+mod presets;
+mod prestige;
+mod race;
+mod replay;
+mod replay_code;
+mod replay_viewer;
+mod restart;
+mod rewind;
+mod roulette;
+mod rumble;
+mod screen_reader;
+mod screenshot;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod session;
+mod settings;
+mod shop;
+mod sound_cues;
+mod speed;
+mod speedrun;
+#[cfg(feature = "steamworks")]
+mod steam;
+mod story;
+mod streak;
+mod theme;
+mod timer;
+mod toast;
+mod tournament;
+mod twitch;
+mod wallet;
+
+const WINDOW_W: f32 = 800.0;
+const WINDOW_H: f32 = 512.0;
+
+// Fixed-step game logic at 60 FPS for smooth physics
+const FIXED_HZ: f64 = 60.0;
+
+// Bird
+const BIRD_SIZE: Vec2 = Vec2::new(34.0, 24.0); // matches bird.png size
+const BIRD_START_X: f32 = -150.0;
+const BIRD_START_Y: f32 = 0.0;
+const GRAVITY: f32 = -980.0; // px / s^2 (slightly reduced for better feel)
+const FLAP_VELOCITY: f32 = 340.0; // px / s (strong upward impulse)
+const MAX_FALL_SPEED: f32 = -500.0; // Limit fall speed so it doesn't feel too heavy
+const BIRD_MAX_UP_ANGLE: f32 = -25.0; // degrees when flapping up (negative because bird is flipped)
+const BIRD_MAX_DOWN_ANGLE: f32 = 70.0; // degrees when diving down (positive because bird is flipped)
+const BIRD_ROTATION_SPEED: f32 = 8.0; // how fast the bird rotates toward target angle
+const ICE_SPRING: f32 = 2.0; // 1 / s^2 pull back to the bird's spot after sliding forward on ice
+const ICE_FRICTION: f32 = 1.5; // 1 / s drag on the slide
+
+// Background parallax
+const BG_BASE_SCROLL_SPEED: f32 = 5.0; // px/s - sky moves very slowly
+const BG_MAX_SCROLL_SPEED: f32 = 100.0; // px/s - closest layer moves fast
+const NUM_CITIES: usize = 8;
+const MAX_BG_LAYERS: usize = 7; // most cities have 5-6 layers
+const SCENERY_STREAM: u64 = 0x5eed_c17e_5eed_c17e; // mixed into the run seed for the city pick
+
+// Pipes
+const PIPE_WIDTH: f32 = 80.0;
+const PIPE_SPEED: f32 = -150.0; // px / s (to the left)
+const PIPE_GAP: f32 = 150.0; // vertical gap
+const PIPE_SPAWN_INTERVAL: f32 = 1.6; // seconds between spawns
+const PIPE_SPAWN_X: f32 = WINDOW_W * 0.5 + 60.0;
+const PIPE_DESPAWN_X: f32 = -WINDOW_W * 0.5 - 100.0;
+const GAP_MARGIN: f32 = 32.0; // margin from top/bottom so gaps aren't unfair
+const NEAR_MISS_DISTANCE: f32 = 6.0; // clearance under which passing a pipe counts as a close call
+
+// Title screen
+const LOGO_SIZE: Vec2 = Vec2::new(320.0, 90.0); // box a branding logo image is fit into
+
+#[derive(States, Default, Clone, Eq, PartialEq, Hash, Debug)]
+enum GameState {
+    #[default]
+    Menu,
+    Playing,
+    // Watching a saved replay, with the viewer controls
+    Replay,
+    GameOver,
+    // Mod menu, opened from the main menu
+    Mods,
+    // Settings menu, opened from the main menu
+    Settings,
+    // Typing in a challenge code, opened from the main menu
+    ChallengeEntry,
+    // A dialogue scene between story mode levels
+    Story,
+    // Browsing and hosting LAN races, opened from the main menu
+    Lobby,
+    // Name entry and the bracket of a local tournament, opened from the main menu
+    Tournament,
+    // Typing in the two players' names for hot-seat, opened from the main menu
+    HotSeat,
+    // Picking a challenge preset, opened from the challenge code screen
+    Presets,
+    // Buying and wearing cosmetics, opened from the main menu
+    Shop,
+}
+
+#[derive(Component)]
+struct Bird {
+    vy: f32,
+    // Along the course; only ice moves the bird off its spot
+    vx: f32,
+    anim_timer: Timer,
+    // Sprite sheet regions to flap through
+    frames: Vec<usize>,
+}
+
+// The bird the player follows: the only one, except in a flock run where the
+// rest are Flockmates (see flock.rs)
+type LeadBird = (With<Bird>, Without<flock::Flockmate>);
+
+// One quad and two materials shared by every pipe, so all of them draw as a
+// single batch however many there are. Pipes scale the quad to their size and
+// the theme recolors the materials.
+#[derive(Resource)]
+struct PipeAssets {
+    quad: Mesh2dHandle,
+    body: Handle<ColorMaterial>,
+    // Outlines and stripes
+    accent: Handle<ColorMaterial>,
+}
+
+impl FromWorld for PipeAssets {
+    fn from_world(world: &mut World) -> Self {
+        let quad = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Rectangle::default());
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        Self {
+            quad: Mesh2dHandle(quad),
+            body: materials.add(Color::WHITE),
+            accent: materials.add(Color::WHITE),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct MusicState {
+    muted: bool,
+}
+
+#[derive(Component)]
+struct MuteIcon;
+
+#[derive(Component)]
+struct BackgroundLayer {
+    speed: f32, // scroll speed for this layer
+}
+
+#[derive(Component, Clone)]
+struct Pipe {
+    is_top: bool,
+    // Only one pipe of a pair scores, to avoid double counting: the bottom
+    // one, as the top one starts out scored
+    scored: bool,
+    near_missed: bool,
+    size: Vec2,
+}
+
+impl Pipe {
+    fn new(size: Vec2, is_top: bool) -> Self {
+        Self {
+            is_top,
+            scored: is_top,
+            near_missed: false,
+            size,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct Score(u32);
+
+// Best score this session
+#[derive(Resource, Default)]
+struct BestScore(u32);
+
+// Counters across all runs this session
+#[derive(Resource, Default, Clone, Copy)]
+struct Stats {
+    runs_played: u32,
+    flaps: u32,
+    pipes_passed: u32,
+    // Distance flown, whichever way the runs were scored (see distance.rs)
+    meters_flown: u32,
+}
+
+#[derive(Resource, Clone)]
+struct PipeSpawnTimer {
+    timer: Timer,
+    // Pairs spawned this run, to step through a mod's pipe pattern
+    spawned: usize,
+}
+
+// Seed for the current run; with the flap ticks it fully determines a run
+#[derive(Resource, Default)]
+struct RunSeed(u64);
+
+// Seed to use for the next run instead of a random one (replays, bots)
+#[derive(Resource, Default)]
+struct NextRunSeed(Option<u64>);
+
+// Fraction the bird's collision box shrinks by this run: the hitbox setting,
+// or whatever the replay being watched was recorded with
+#[derive(Resource, Default)]
+struct RunHitbox(f32);
+
+// Modifiers this run is played with: the challenge's, the preset's or the replay's
+#[derive(Resource, Default)]
+struct RunModifiers(modifiers::Modifiers);
+
+// Gravity, pipe gap and speed, and hazards this run is played with: the
+// preset's or the replay's, else the standard ones
+#[derive(Resource, Default)]
+struct RunRules(modifiers::Rules);
+
+// Which way along the course the run goes and how fast, for the systems that
+// move things along it
+#[derive(SystemParam)]
+struct Course<'w> {
+    modifiers: Res<'w, RunModifiers>,
+    rules: Res<'w, RunRules>,
+    tick: Res<'w, RunTick>,
+    roulette: Res<'w, roulette::Roulette>,
+}
+
+impl Course<'_> {
+    fn facing(&self) -> f32 {
+        self.modifiers.0.facing()
+    }
+
+    // Pipe scroll speed along x, in px / s; negative, towards the bird. Slower
+    // during the warm-up, faster under the roulette's fast pipes.
+    fn pipe_speed(&self) -> f32 {
+        self.rules.0.pipe_speed
+            * self.modifiers.0.speed_scale()
+            * self.warm_up_speed_scale()
+            * self.roulette.speed_scale()
+    }
+
+    fn warm_up_speed_scale(&self) -> f32 {
+        self.rules.0.warm_up_speed_scale(self.tick.0)
+    }
+
+    // Gap between a pair of pipes spawned now; wider during the warm-up
+    fn pipe_gap(&self) -> f32 {
+        self.rules.0.pipe_gap
+            * self.modifiers.0.gap_scale()
+            * self.rules.0.warm_up_gap_scale(self.tick.0)
+    }
+}
+
+// Every random choice in a run, reseeded from RunSeed when it starts, so the
+// seed (which replays store) reproduces the whole run. The course and scenery
+// draw from separate streams, so picking a background never moves the pipes.
+#[derive(Resource, Clone)]
+struct GameRng {
+    course: StdRng,
+    scenery: StdRng,
+}
+
+impl GameRng {
+    fn new(seed: u64) -> Self {
+        Self {
+            course: StdRng::seed_from_u64(seed),
+            scenery: StdRng::seed_from_u64(seed ^ SCENERY_STREAM),
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+// Fixed steps since the current run started
+#[derive(Resource, Default)]
+struct RunTick(u32);
+
+// Broad phase for collisions: every pipe's horizontal extent, sorted by left
+// edge and rebuilt each tick, so the bird is only tested against the few pipes
+// level with it. The buffer is kept between ticks to avoid reallocating.
+#[derive(Resource, Default)]
+struct PipeSweep {
+    // (left, right, pipe)
+    spans: Vec<(f32, f32, Entity)>,
+    widest: f32,
+}
+
+impl PipeSweep {
+    // Pipes whose span reaches into [left, right]
+    fn overlapping(&self, left: f32, right: f32) -> impl Iterator<Item = Entity> + '_ {
+        let start = self
+            .spans
+            .partition_point(|span| span.0 < left - self.widest);
+        let end = self.spans.partition_point(|span| span.0 <= right);
+        self.spans[start..end]
+            .iter()
+            .filter(move |span| span.1 >= left)
+            .map(|span| span.2)
+    }
+}
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct MenuUI;
+
+#[derive(Component)]
+struct GameOverUI;
+
+// Resource to buffer flap input from Update to FixedUpdate
+#[derive(Resource, Default)]
+struct FlapInput {
+    requested: bool,
+}
+
+// Whether the current run counts towards the best score, stats and achievements:
+// not if the autopilot flew it, it was slowed down for practice, it's a replay,
+// its score was multiplied by a flock, grown by coins or topped up in decay
+// mode, a preset or the mutator roulette changed the rules, it was played with
+// cheats, it was rewound after a crash, the developer console changed the run,
+// or something replays don't record shaped the course
+#[derive(SystemParam)]
+struct RunRanking<'w> {
+    autopilot: Res<'w, autopilot::Autopilot>,
+    speed: Res<'w, speed::RunSpeed>,
+    playback: Option<Res<'w, replay::ReplayPlayback>>,
+    modifiers: Res<'w, RunModifiers>,
+    rules: Res<'w, RunRules>,
+    console: Res<'w, console::ConsoleUsed>,
+    cheats: Res<'w, cheats::RunCheats>,
+    rewind: Res<'w, rewind::Rewind>,
+    course: UnrecordedCourse<'w>,
+}
+
+impl RunRanking<'_> {
+    fn ranked(&self) -> bool {
+        !self.autopilot.assisted()
+            && !self.speed.slowed()
+            && self.playback.is_none()
+            && !self.modifiers.0.has(modifiers::Modifier::Flock)
+            && !self.modifiers.0.has(modifiers::Modifier::Growth)
+            && !self.modifiers.0.has(modifiers::Modifier::Decay)
+            && self.rules.0 == modifiers::Rules::default()
+            && !self.console.0
+            && !self.cheats.any()
+            && !self.rewind.used()
+            && self.course.reason().is_none()
+    }
+
+    // Whether the run counts towards its challenge preset's best: as for
+    // ranked, but whatever the preset set
+    fn ranked_under_own_rules(&self) -> bool {
+        !self.autopilot.assisted()
+            && !self.speed.slowed()
+            && self.playback.is_none()
+            && !self.console.0
+            && !self.cheats.any()
+            && !self.rewind.used()
+            && self.course.reason().is_none()
+    }
+
+    // Any run the player flew themselves: coins picked up go into the wallet,
+    // and it goes into the run history
+    fn flown_by_player(&self) -> bool {
+        !self.autopilot.assisted() && self.playback.is_none()
+    }
+}
+
+// What shapes the course besides its seed, rules and modifiers: a mod's pipe
+// pattern or a script's `on_spawn_pipes`. Replays, replay and challenge codes
+// and race starts don't carry it, so they'd be played on a different course.
+#[derive(SystemParam)]
+struct UnrecordedCourse<'w> {
+    mods: Res<'w, mods::Mods>,
+    #[cfg(feature = "scripting")]
+    scripts: Option<Res<'w, scripting::Scripts>>,
+}
+
+impl UnrecordedCourse<'_> {
+    // Why the run can't be saved or shared, if it can't
+    fn reason(&self) -> Option<&'static str> {
+        if self.mods.pipe_pattern().is_some() {
+            return Some("Runs on a mod's pipe pattern can't be saved or shared");
+        }
+        #[cfg(feature = "scripting")]
+        if self.scripts.as_ref().is_some_and(|s| s.reshapes_course()) {
+            return Some("Runs with scripted pipes can't be saved or shared");
+        }
+        None
+    }
+}
+
+// Where a new run's rules come from: the replay being watched, else the active
+// challenge or preset, else the player's settings (hitbox and mode)
+#[derive(SystemParam)]
+struct RunSource<'w> {
+    settings: Res<'w, settings::Settings>,
+    playback: Option<Res<'w, replay::ReplayPlayback>>,
+    challenge: Res<'w, challenge::ActiveChallenge>,
+    preset: Res<'w, presets::ActivePreset>,
+}
+
+impl RunSource<'_> {
+    fn challenge(&self) -> Option<challenge::ChallengeCode> {
+        self.challenge.0.filter(|_| self.playback.is_none())
+    }
+
+    fn hitbox_shrink(&self) -> f32 {
+        match (&self.playback, self.challenge()) {
+            (Some(p), _) => p.replay.config.hitbox_shrink,
+            (None, Some(c)) => c.hitbox.shrink(),
+            (None, None) => self.settings.hitbox.shrink(),
+        }
+    }
+
+    fn modifiers(&self) -> modifiers::Modifiers {
+        match (&self.playback, self.challenge(), &self.preset.0) {
+            (Some(p), _, _) => p.replay.modifiers,
+            (None, Some(c), _) => c.modifiers,
+            (None, None, Some(preset)) => preset.modifiers,
+            (None, None, None) => self.settings.modifiers(),
+        }
+    }
+
+    fn rules(&self) -> modifiers::Rules {
+        match (&self.playback, self.challenge(), &self.preset.0) {
+            (Some(p), _, _) => p.replay.rules(),
+            (None, None, Some(preset)) => preset.rules,
+            (None, Some(_), _) => modifiers::Rules::default(),
+            (None, None, None) => modifiers::Rules {
+                roulette: self.settings.roulette,
+                ..default()
+            },
+        }
+    }
+}
+
+// Gameplay events, for systems that react to what happens during a run
+#[derive(Event)]
+struct Flapped;
+
+#[derive(Event)]
+struct PipePassed;
+
+#[derive(Event)]
+struct Crashed(CrashCause);
+
+// What brought the bird down
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum CrashCause {
+    Pipe,
+    Ground,
+    Ceiling,
+    Hazard,
+    // The score drained away in decay mode
+    Decay,
+}
+
+impl CrashCause {
+    const ALL: [CrashCause; 5] = [
+        CrashCause::Pipe,
+        CrashCause::Ground,
+        CrashCause::Ceiling,
+        CrashCause::Hazard,
+        CrashCause::Decay,
+    ];
+
+    // For the run history
+    fn name(self) -> &'static str {
+        match self {
+            CrashCause::Pipe => "pipe",
+            CrashCause::Ground => "ground",
+            CrashCause::Ceiling => "ceiling",
+            CrashCause::Hazard => "hazard",
+            CrashCause::Decay => "decay",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|cause| cause.name() == name)
+    }
+}
+
+// The bird scraped past a pipe without touching it
+#[derive(Event)]
+struct NearMiss {
+    // Grazed the top pipe rather than the bottom one
+    above: bool,
+}
+
+// The menu demo crashed; start it over
+#[derive(Event)]
+struct RestartDemo;
+
+// The run was rewound after a crash (see rewind.rs); whatever else flies along
+// the course is cleared
+#[derive(Event)]
+struct Rewound;
+
+// Transforms at the last two fixed steps, so rendering can interpolate between them
+#[derive(Component, Clone, Copy)]
+struct FixedTransform {
+    previous: Transform,
+    current: Transform,
+}
+
+impl FixedTransform {
+    fn new(transform: Transform) -> Self {
+        Self {
+            previous: transform,
+            current: transform,
+        }
+    }
+}
+
+// Launch the game in a window; this is all the binary does
+pub fn run() {
+    let options = cli::LaunchOptions::from_args();
+    // `--export-stats` only writes the file, without opening the game
+    if let Some(path) = &options.export_stats {
+        let exported = history::export_from_cli(path);
+        std::process::exit(if exported { 0 } else { 1 });
+    }
+    build_app(options).run();
+}
+
+// The windowed game, configured from the command line and ready to run. Add
+// extra plugins (e.g. `agent::AgentPlugin`) before calling `run` on it.
+pub fn app() -> App {
+    build_app(cli::LaunchOptions::from_args())
+}
+
+fn build_app(options: cli::LaunchOptions) -> App {
+    let mut window = Window {
+        title: "Floopy Birb".to_string(),
+        resolution: (WINDOW_W, WINDOW_H).into(),
+        resizable: false,
+        ..default()
+    };
+    if options.overlay {
+        overlay::configure_window(&mut window);
+    }
+
+    let mut app = App::new();
+    mods::register_asset_source(&mut app);
+    app.insert_resource(ClearColor(Color::srgb(0.53, 0.81, 0.92))) // light sky blue fallback
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(window),
+                    ..default()
+                })
+                // Release builds never watch, whatever the features
+                .set(AssetPlugin {
+                    watch_for_changes_override: Some(cfg!(all(
+                        feature = "hot_reload",
+                        debug_assertions
+                    ))),
+                    ..default()
+                }),
+        )
+        .add_plugins(GamePlugin)
+        .add_plugins((
+            mods::ModsPlugin,
+            settings::SettingsPlugin,
+            challenge::ChallengePlugin,
+            presets::PresetsPlugin,
+            story::StoryPlugin,
+            rumble::RumblePlugin,
+            pause::PausePlugin,
+            photo::PhotoModePlugin,
+            gamepad::GamepadPlugin,
+            screenshot::ScreenshotPlugin,
+            replay::ReplayPlugin {
+                open_on_start: options.replay_path,
+            },
+            replay_viewer::ReplayViewerPlugin,
+            screen_reader::ScreenReaderPlugin,
+            sound_cues::SoundCuesPlugin,
+            wallet::WalletPlugin,
+        ))
+        // After ModsPlugin, so a mod can rebrand and recolor the game
+        .add_plugins((branding::BrandingPlugin, palette::PalettePlugin))
+        .add_plugins((
+            history::HistoryPlugin,
+            timer::RunTimerPlugin,
+            shop::ShopPlugin,
+            prestige::PrestigePlugin,
+            hitboxes::HitboxesPlugin,
+            session::SessionPlugin,
+            attract::AttractPlugin,
+            notify::NotifyPlugin,
+            replay_code::ReplayCodePlugin,
+            speedrun::SpeedrunPlugin,
+            roulette::RoulettePlugin,
+            rewind::RewindPlugin,
+        ))
+        // Music
+        .insert_resource(MusicState::default())
+        .add_plugins(music::MusicPlugin)
+        .add_systems(Update, toggle_mute);
+
+    #[cfg(feature = "broadcast")]
+    app.add_plugins(broadcast::BroadcastPlugin);
+    #[cfg(feature = "capture")]
+    app.add_plugins(capture::CapturePlugin);
+    #[cfg(feature = "discord")]
+    app.add_plugins(discord::DiscordPresencePlugin);
+    #[cfg(all(feature = "hot_reload", debug_assertions))]
+    app.add_plugins(hot_reload::HotReloadPlugin);
+    #[cfg(feature = "scripting")]
+    app.add_plugins(scripting::ScriptingPlugin);
+    #[cfg(feature = "steamworks")]
+    app.add_plugins(steam::SteamPlugin);
+    app.add_plugins(power::PowerPlugin {
+        background_play: options.overlay
+            || options.twitch_channel.is_some()
+            || options.race.is_some(),
+    });
+    if options.overlay {
+        app.add_plugins(overlay::OverlayPlugin);
+    }
+    if let Some(channel) = options.twitch_channel {
+        app.add_plugins(twitch::TwitchPlugin { channel });
+    }
+    app.add_plugins((
+        race::RacePlugin { role: options.race },
+        lobby::LobbyPlugin,
+        tournament::TournamentPlugin,
+        hotseat::HotSeatPlugin,
+    ));
+
+    app
+}
+
+// The game itself, without the window, audio output or platform integrations,
+// so it can also run headless (see `agent::Env`)
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        // Fixed timestep for game logic at 60 Hz for smooth physics
+        app.insert_resource(Time::<Fixed>::from_hz(FIXED_HZ))
+            .init_state::<GameState>()
+            .insert_resource(Score::default())
+            .insert_resource(BestScore::default())
+            .insert_resource(Stats::default())
+            .insert_resource(RunSeed::default())
+            .insert_resource(NextRunSeed::default())
+            .insert_resource(RunHitbox::default())
+            .insert_resource(RunModifiers::default())
+            .insert_resource(RunRules::default())
+            .init_resource::<GameRng>()
+            .insert_resource(RunTick::default())
+            .init_resource::<PipeSweep>()
+            // No mods unless ModsPlugin finds some, default settings unless
+            // SettingsPlugin loads the player's, the game's own name unless
+            // BrandingPlugin reads another, and its own colors unless
+            // PalettePlugin reads others
+            .init_resource::<mods::Mods>()
+            .init_resource::<challenge::ActiveChallenge>()
+            .init_resource::<presets::ActivePreset>()
+            .init_resource::<settings::Settings>()
+            .init_resource::<branding::Branding>()
+            .init_resource::<palette::Palette>()
+            .init_resource::<settings::AccessibilitySettings>()
+            // No rewinds unless RewindPlugin hands some out
+            .init_resource::<rewind::Rewind>()
+            // An empty wallet unless WalletPlugin loads the player's
+            .init_resource::<wallet::Wallet>()
+            // Default controller buttons unless GamepadPlugin loads the player's
+            .init_resource::<controls::GamepadBindings>()
+            // No mutators unless RoulettePlugin spins some
+            .init_resource::<roulette::Roulette>()
+            .add_event::<Flapped>()
+            .add_event::<PipePassed>()
+            .add_event::<Crashed>()
+            .add_event::<NearMiss>()
+            .add_event::<RestartDemo>()
+            .add_event::<Rewound>()
+            // The console pauses the run; PausePlugin, where it's added, does it
+            .add_event::<pause::PauseRequest>()
+            .add_plugins((
+                achievements::AchievementsPlugin,
+                autopilot::AutopilotPlugin,
+                cheats::CheatsPlugin,
+                console::ConsolePlugin,
+                decay::DecayPlugin,
+                distance::DistancePlugin,
+                flock::FlockPlugin,
+                growth::GrowthPlugin,
+                hazards::HazardsPlugin,
+                laser::LaserPlugin,
+                restart::RestartPlugin,
+                streak::StreakPlugin,
+                theme::ThemePlugin,
+                speed::GameSpeedPlugin,
+                toast::ToastPlugin,
+            ))
+            .insert_resource(FlapInput::default())
+            .init_resource::<PipeAssets>()
+            .add_systems(Startup, (load_assets, setup).chain())
+            // Menu, with the autopilot flying a demo run behind it
+            .add_systems(OnEnter(GameState::Menu), (start_game, show_menu_ui))
+            .add_systems(Update, start_game.run_if(on_event::<RestartDemo>()))
+            .add_systems(OnExit(GameState::Menu), despawn_menu_ui)
+            .add_systems(Update, menu_input.run_if(in_state(GameState::Menu)))
+            // Playing - input handling in Update to catch all key presses
+            .add_systems(OnEnter(GameState::Playing), start_game)
+            .add_systems(OnEnter(GameState::Replay), start_game)
+            .add_systems(
+                Update,
+                buffer_flap_input.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_run_tick,
+                    handle_flap_input,
+                    animate_bird,
+                    apply_bird_physics,
+                    move_pipes,
+                    spawn_pipes,
+                    sweep_pipes,
+                    check_collisions_and_scoring,
+                )
+                    .chain()
+                    .run_if(simulation_running),
+            )
+            // Render interpolation between fixed steps
+            .add_systems(FixedFirst, restore_fixed_transforms)
+            .add_systems(FixedLast, record_fixed_transforms)
+            .add_systems(
+                PostUpdate,
+                interpolate_fixed_transforms.before(TransformSystem::TransformPropagate),
+            )
+            .add_systems(Update, (update_score_text, scroll_background, count_stats))
+            // Game Over
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (update_best_score, show_game_over_ui).chain(),
+            )
+            .add_systems(OnExit(GameState::GameOver), despawn_game_over_ui)
+            .add_systems(
+                Update,
+                game_over_input.run_if(
+                    in_state(GameState::GameOver)
+                        .and_then(not(resource_exists::<photo::PhotoMode>))
+                        .and_then(not(resource_exists::<tournament::TournamentRun>)),
+                ),
+            );
+    }
+}
+
+// A file in the game's data folder, where settings, saves and records are kept
+fn data_file(name: &str) -> std::path::PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("floopybirb"))
+        .unwrap_or_default()
+        .join(name)
+}
+
+// --------------------------------------------
+// Startup
+// --------------------------------------------
+
+fn load_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mods: Res<mods::Mods>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let atlas = atlas::SpriteAtlas::load(&mods, &asset_server, &mut layouts);
+    commands.insert_resource(atlas);
+}
+
+fn toggle_mute(
+    input: Res<ButtonInput<KeyCode>>,
+    mut music_state: ResMut<MusicState>,
+    music_sinks: Query<&AudioSink>,
+    mut mute_icon_q: Query<&mut Text, With<MuteIcon>>,
+) {
+    if input.just_pressed(KeyCode::KeyM) {
+        music_state.muted = !music_state.muted;
+
+        for sink in &music_sinks {
+            if music_state.muted {
+                sink.pause();
+            } else {
+                sink.play();
+            }
+        }
+
+        // Update mute text
+        if let Ok(mut text) = mute_icon_q.get_single_mut() {
+            if let Some(section) = text.sections.get_mut(0) {
+                section.value.clear();
+                section.value.push_str(if music_state.muted {
+                    "[M] OFF"
+                } else {
+                    "[M] ON"
+                });
+            }
+        }
+    }
+}
+
+fn spawn_background_layers(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    mods: &mods::Mods,
+    rng: &mut GameRng,
+) {
+    // Pick a random city (1-8)
+    let city_num = rng.scenery.gen_range(1..=NUM_CITIES);
+
+    // Each city has 5-6 layers
+    // Layer files are named 1.png, 2.png, etc. (1 = furthest back, higher = closer)
+    // We try loading up to MAX_BG_LAYERS (missing files just won't render)
+    for layer_num in 1..=MAX_BG_LAYERS {
+        let path = format!("textures/city {}/{}.png", city_num, layer_num);
+        let texture: Handle<Image> = asset_server.load(mods.resolve(&path));
+
+        // Calculate speed: back layers are slower, front layers are faster
+        // Linearly interpolate between base and max speed based on layer position
+        let layer_idx = layer_num - 1;
+        let t = layer_idx as f32 / (MAX_BG_LAYERS - 1) as f32;
+        let speed = BG_BASE_SCROLL_SPEED + t * (BG_MAX_SCROLL_SPEED - BG_BASE_SCROLL_SPEED);
+
+        // Z position: layer 0 at -100, going up towards 0 for front layers
+        let z = -100.0 + (layer_idx as f32 * 10.0);
+
+        // Spawn two copies of each layer for seamless scrolling
+        for i in 0..2 {
+            commands.spawn((
+                SpriteBundle {
+                    texture: texture.clone(),
+                    transform: Transform::from_xyz(WINDOW_W * i as f32, 0.0, z),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(WINDOW_W, WINDOW_H)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                BackgroundLayer { speed },
+            ));
+        }
+    }
+}
+
+fn setup(mut commands: Commands, atlas: Res<atlas::SpriteAtlas>) {
+    // Camera
+    commands.spawn(Camera2dBundle::default());
+
+    // Background layers are spawned by start_game, which first runs on entering
+    // the menu
+
+    // Bird, flapping through its frames of the sprite sheet
+    let frames = atlas.frames("bird");
+    let bird_transform = Transform::from_xyz(BIRD_START_X, BIRD_START_Y, 1.0);
+    commands.spawn((
+        SpriteBundle {
+            texture: atlas.texture.clone(),
+            transform: bird_transform,
+            ..default()
+        },
+        TextureAtlas {
+            layout: atlas.layout.clone(),
+            // Start mid-flap
+            index: frames.get(1).copied().unwrap_or_default(),
+        },
+        Bird {
+            vy: 0.0,
+            vx: 0.0,
+            anim_timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+            frames,
+        },
+        FixedTransform::new(bird_transform),
+    ));
+
+    // Score text (top-center)
+    commands.spawn((
+        TextBundle::from_section(
+            score_buffer(),
+            TextStyle {
+                font_size: 40.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(WINDOW_W * 0.5 - 10.0),
+            ..default()
+        }),
+        ScoreText,
+    ));
+
+    // Mute text (top-right)
+    commands.spawn((
+        TextBundle::from_section(
+            "[M] ON",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        MuteIcon,
+    ));
+}
+
+// --------------------------------------------
+// Menu UI and input
+// --------------------------------------------
+
+fn show_menu_ui(
+    mut commands: Commands,
+    settings: Res<settings::Settings>,
+    branding: Res<branding::Branding>,
+    mods: Res<mods::Mods>,
+    asset_server: Res<AssetServer>,
+) {
+    // Title: the branding's logo image, or its name in big letters
+    if let Some(logo) = &branding.logo_image {
+        commands.spawn((
+            ImageBundle {
+                image: UiImage::new(asset_server.load(mods.resolve(logo))),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(120.0),
+                    left: Val::Px((WINDOW_W - LOGO_SIZE.x) * 0.5),
+                    width: Val::Px(LOGO_SIZE.x),
+                    height: Val::Px(LOGO_SIZE.y),
+                    ..default()
+                },
+                ..default()
+            },
+            MenuUI,
+        ));
+    } else {
+        commands.spawn((
+            TextBundle::from_section(
+                branding.logo.clone(),
+                TextStyle {
+                    font_size: 56.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(140.0),
+                left: Val::Px(WINDOW_W * 0.5 - 160.0),
+                ..default()
+            }),
+            MenuUI,
+        ));
+    }
+    // Instructions
+    commands.spawn((
+        TextBundle::from_section(
+            format!(
+                "Press {flap} to Start\n{flap} to flap\nB to let the bot fly, S for the shop\nL to watch last replay\nC to play a challenge code\nN for LAN races, E for a tournament\nT for story mode, G for hot-seat\nO for mods, Tab for settings",
+                flap = settings.controls.flap_hint()
+            ),
+            TextStyle {
+                font_size: 28.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(220.0),
+            left: Val::Px(WINDOW_W * 0.5 - 140.0),
+            ..default()
+        }),
+        MenuUI,
+    ));
+}
+
+fn despawn_menu_ui(mut commands: Commands, q: Query<Entity, With<MenuUI>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn menu_input(controls: controls::GameInput, mut next_state: ResMut<NextState<GameState>>) {
+    if controls.confirm() {
+        next_state.set(GameState::Playing);
+    }
+}
+
+// --------------------------------------------
+// Game start/reset
+// --------------------------------------------
+
+#[allow(clippy::too_many_arguments)]
+fn start_game(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut score: ResMut<Score>,
+    mut bird_q: Query<(&mut Transform, &mut Bird, &mut FixedTransform, &mut Sprite), LeadBird>,
+    pipes_q: Query<Entity, With<Pipe>>,
+    bg_q: Query<Entity, With<BackgroundLayer>>,
+    mut flap_input: ResMut<FlapInput>,
+    mut run_seed: ResMut<RunSeed>,
+    mut next_seed: ResMut<NextRunSeed>,
+    mut rng: ResMut<GameRng>,
+    mut run_tick: ResMut<RunTick>,
+    mut run_hitbox: ResMut<RunHitbox>,
+    mut run_modifiers: ResMut<RunModifiers>,
+    mut run_rules: ResMut<RunRules>,
+    source: RunSource,
+    mods: Res<mods::Mods>,
+) {
+    // Reset score
+    score.0 = 0;
+
+    // New course, unless someone asked for a specific one (a replay, the bot) or
+    // a challenge sets it. Random seeds fit in a challenge code.
+    run_seed.0 = next_seed
+        .0
+        .take()
+        .or(source.challenge().map(|c| c.seed.into()))
+        .unwrap_or_else(|| rand::random::<u32>().into());
+    *rng = GameRng::new(run_seed.0);
+    run_tick.0 = 0;
+    run_hitbox.0 = source.hitbox_shrink();
+    run_modifiers.0 = source.modifiers();
+    run_rules.0 = source.rules();
+
+    // Clear any buffered flap input
+    flap_input.requested = false;
+
+    // Reset bird
+    if let Ok((mut tf, mut bird, mut fixed_tf, mut sprite)) = bird_q.get_single_mut() {
+        // On the right, facing left, when the course runs in reverse
+        let facing = run_modifiers.0.facing();
+        tf.translation.x = BIRD_START_X * facing;
+        sprite.flip_x = facing < 0.0;
+        tf.translation.y = BIRD_START_Y;
+        tf.rotation = Quat::IDENTITY; // Reset rotation to level
+        tf.scale = Vec3::ONE; // Coins may have grown it last run
+        bird.vy = 0.0;
+        bird.vx = 0.0;
+        bird.anim_timer.reset();
+        // Teleport: don't interpolate from where the last run ended
+        *fixed_tf = FixedTransform::new(*tf);
+    }
+
+    // Despawn existing pipes
+    for e in &pipes_q {
+        commands.entity(e).despawn_recursive();
+    }
+
+    // Despawn existing background layers
+    for e in &bg_q {
+        commands.entity(e).despawn_recursive();
+    }
+
+    // Spawn new random background
+    profile_span!("spawn_background");
+    spawn_background_layers(&mut commands, &asset_server, &mods, &mut rng);
+
+    // Reset spawn timer
+    commands.insert_resource(PipeSpawnTimer {
+        timer: Timer::from_seconds(PIPE_SPAWN_INTERVAL, TimerMode::Repeating),
+        spawned: 0,
+    });
+}
+
+// --------------------------------------------
+// Playing: input, physics, spawn, movement
+// --------------------------------------------
+
+// Another player is waiting on the run's result: a LAN race, a tournament or
+// hot-seat run. Those end in a crash, so no restarting or rewinding them.
+fn opponent_waiting(
+    race: Option<Res<race::RaceLink>>,
+    tournament: Option<Res<tournament::TournamentRun>>,
+    hotseat: Option<Res<hotseat::HotSeat>>,
+) -> bool {
+    race.is_some() || tournament.is_some() || hotseat.is_some()
+}
+
+// The simulation runs during play, replays and for the menu demo, but stands
+// still while a crash waits on a rewind
+fn simulation_running(state: Res<State<GameState>>, rewind: Res<rewind::Rewind>) -> bool {
+    matches!(
+        state.get(),
+        GameState::Playing | GameState::Replay | GameState::Menu
+    ) && !rewind.holding()
+}
+
+fn advance_run_tick(mut run_tick: ResMut<RunTick>) {
+    run_tick.0 += 1;
+}
+
+fn buffer_flap_input(controls: controls::GameInput, mut flap_input: ResMut<FlapInput>) {
+    if controls.flap() {
+        flap_input.requested = true;
+    }
+}
+
+// Consume buffered input in FixedUpdate
+fn handle_flap_input(
+    state: Res<State<GameState>>,
+    run_modifiers: Res<RunModifiers>,
+    roulette: Res<roulette::Roulette>,
+    mut flap_input: ResMut<FlapInput>,
+    mut bird_q: Query<&mut Bird>,
+    mut flapped: EventWriter<Flapped>,
+) {
+    if flap_input.requested {
+        // Flap - set velocity directly for consistent jump height, except on
+        // ice where some of the old speed carries through. A flock flaps all
+        // at once. Inverted controls flap downwards.
+        let grip = run_modifiers.0.flap_grip();
+        let drift = run_modifiers.0.flap_drift() * run_modifiers.0.facing();
+        let flap_velocity = FLAP_VELOCITY * roulette.flip();
+        for mut bird in &mut bird_q {
+            bird.vy = if grip < 1.0 {
+                bird.vy * (1.0 - grip) + flap_velocity * grip
+            } else {
+                flap_velocity
+            };
+            bird.vx += drift;
+        }
+        // The menu demo doesn't count
+        if !bird_q.is_empty() && *state.get() != GameState::Menu {
+            flapped.send(Flapped);
+        }
+        flap_input.requested = false;
+    }
+}
+
+fn animate_bird(time: Res<Time<Fixed>>, mut q: Query<(&mut Bird, &mut TextureAtlas)>) {
+    for (mut bird, mut atlas) in &mut q {
+        bird.anim_timer.tick(time.delta());
+        if bird.anim_timer.just_finished() && !bird.frames.is_empty() {
+            let current = bird.frames.iter().position(|&i| i == atlas.index);
+            let next = current.map_or(0, |i| (i + 1) % bird.frames.len());
+            atlas.index = bird.frames[next];
+        }
+    }
+}
+
+fn apply_bird_physics(
+    time: Res<Time<Fixed>>,
+    run_tick: Res<RunTick>,
+    run_modifiers: Res<RunModifiers>,
+    run_rules: Res<RunRules>,
+    roulette: Res<roulette::Roulette>,
+    mut q: Query<(&mut Transform, &mut Bird)>,
+) {
+    let dt = time.delta_seconds();
+    // Gravity as the roulette has it: lower, or pulling up with the controls
+    // inverted
+    let gravity = run_rules.0.gravity * roulette.gravity_scale() * roulette.flip();
+    for (mut tf, mut bird) in &mut q {
+        // Apply gravity, and wind if the run has it
+        bird.vy += (gravity + run_modifiers.0.wind(run_tick.0)) * dt;
+
+        // Clamp fall speed so bird doesn't feel too heavy, whichever way it falls
+        if bird.vy * roulette.flip() < MAX_FALL_SPEED {
+            bird.vy = MAX_FALL_SPEED * roulette.flip();
+        }
+
+        // Update position
+        tf.translation.y += bird.vy * dt;
+
+        // On ice the bird slides forward on a flap and drifts back to its spot
+        if run_modifiers.0.has(modifiers::Modifier::Ice) {
+            let home = BIRD_START_X * run_modifiers.0.facing();
+            bird.vx -= ((tf.translation.x - home) * ICE_SPRING + bird.vx * ICE_FRICTION) * dt;
+            tf.translation.x += bird.vx * dt;
+        }
+
+        // Calculate target rotation based on vertical velocity
+        // Map velocity to angle: positive vy = tilt up, negative vy = tilt down
+        let velocity_ratio = bird.vy / FLAP_VELOCITY; // normalized velocity
+        let target_angle_deg = if bird.vy > 0.0 {
+            // Going up - tilt upward (positive angle)
+            BIRD_MAX_UP_ANGLE * (velocity_ratio).min(1.0)
+        } else {
+            // Falling - tilt downward (negative angle), more aggressive as we fall faster
+            let fall_ratio = bird.vy / MAX_FALL_SPEED; // 0 to 1 as we approach max fall
+            BIRD_MAX_DOWN_ANGLE * fall_ratio.abs().min(1.0)
+        };
+        // Mirrored along with the sprite in reverse
+        let target_angle = target_angle_deg.to_radians() * run_modifiers.0.facing();
+
+        // Smoothly interpolate current rotation toward target
+        let current_angle = tf.rotation.to_euler(EulerRot::ZYX).0;
+        let new_angle = current_angle + (target_angle - current_angle) * BIRD_ROTATION_SPEED * dt;
+        tf.rotation = Quat::from_rotation_z(new_angle);
+    }
+}
+
+fn scroll_background(
+    time: Res<Time>,
+    accessibility: Res<settings::AccessibilitySettings>,
+    run_modifiers: Res<RunModifiers>,
+    mut bg_q: Query<(&mut Transform, &BackgroundLayer)>,
+) {
+    // Reduced motion keeps the city still, and so does a vertical run, whose
+    // course crosses it
+    if accessibility.reduced_motion || run_modifiers.0.has(modifiers::Modifier::Vertical) {
+        return;
+    }
+    let dt = time.delta_seconds();
+    let facing = run_modifiers.0.facing();
+    for (mut tf, layer) in &mut bg_q {
+        tf.translation.x -= layer.speed * dt * facing;
+        // Wrap around when it goes too far behind the bird
+        if tf.translation.x * facing <= -WINDOW_W {
+            tf.translation.x += WINDOW_W * 2.0 * facing;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_pipes(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    mut timer: ResMut<PipeSpawnTimer>,
+    mut rng: ResMut<GameRng>,
+    mods: Res<mods::Mods>,
+    assets: Res<PipeAssets>,
+    course: Course,
+    #[cfg(feature = "scripting")] scripts: Option<Res<scripting::Scripts>>,
+    #[cfg(feature = "scripting")] run_tick: Res<RunTick>,
+    #[cfg(feature = "scripting")] score: Res<Score>,
+) {
+    // Tick spawn timer with fixed dt, slowed with the pipes during the warm-up
+    // so they stay as far apart
+    let speed_scale = course.warm_up_speed_scale();
+    let delta = if speed_scale < 1.0 {
+        time.delta().mul_f32(speed_scale)
+    } else {
+        time.delta()
+    };
+    if timer.timer.tick(delta).just_finished() {
+        let gap = course.pipe_gap();
+        // Choose a random gap center
+        // Keep some margin from the top and bottom edges, more for a preset's
+        // wider gap
+        let half_h = WINDOW_H * 0.5;
+        let min_center = -half_h + GAP_MARGIN + gap.max(PIPE_GAP) * 0.5;
+        let max_center = half_h - GAP_MARGIN - gap.max(PIPE_GAP) * 0.5;
+        // Levels from mods cycle through fixed positions instead
+        let gap_center_y = match mods.pipe_pattern() {
+            Some(pattern) => {
+                let t = pattern[timer.spawned % pattern.len()] * 0.5 + 0.5;
+                min_center + (max_center - min_center) * t
+            }
+            None => rng.course.gen_range(min_center..=max_center),
+        };
+        timer.spawned += 1;
+
+        // Scripts get the last word on where the gap goes; there are none
+        // without ScriptingPlugin, as in the headless `agent::Env`
+        #[cfg(feature = "scripting")]
+        let (gap_center_y, gap) = match &scripts {
+            Some(scripts) => {
+                profile_span!("script_spawn_hook");
+                scripts.on_spawn_pipes(gap_center_y, gap, run_tick.0, score.0)
+            }
+            None => (gap_center_y, gap),
+        };
+
+        // Compute segment heights
+        profile_span!("spawn_pipe_pair");
+        let top_height = half_h - (gap_center_y + gap * 0.5);
+        let bottom_height = half_h + (gap_center_y - gap * 0.5);
+
+        let top_center_y = half_h - top_height * 0.5;
+        let bottom_center_y = -half_h + bottom_height * 0.5;
+        let spawn_x = PIPE_SPAWN_X * course.facing();
+        let top_tf = Transform::from_xyz(spawn_x, top_center_y, 0.0);
+        let bottom_tf = Transform::from_xyz(spawn_x, bottom_center_y, 0.0);
+
+        let top_size = Vec2::new(PIPE_WIDTH, top_height);
+        let bottom_size = Vec2::new(PIPE_WIDTH, bottom_height);
+        spawn_pipe(&mut commands, &assets, top_tf, Pipe::new(top_size, true));
+        spawn_pipe(
+            &mut commands,
+            &assets,
+            bottom_tf,
+            Pipe::new(bottom_size, false),
+        );
+    }
+}
+
+fn spawn_pipe(commands: &mut Commands, assets: &PipeAssets, transform: Transform, pipe: Pipe) {
+    let size = pipe.size;
+    commands
+        .spawn((
+            SpatialBundle::from_transform(transform),
+            pipe,
+            FixedTransform::new(transform),
+        ))
+        .with_children(|parent| {
+            // The body is a child so decorations don't inherit its scale
+            parent.spawn(MaterialMesh2dBundle {
+                mesh: assets.quad.clone(),
+                material: assets.body.clone(),
+                transform: Transform::from_scale(size.extend(1.0)),
+                ..default()
+            });
+        });
+}
+
+// Pipes spawned this tick
+type NewPipes<'w, 's> = Query<'w, 's, (&'static Transform, &'static Pipe), Added<Pipe>>;
+
+// Middle of the gap of a pair of pipes spawned this tick, for pickups to sit in
+fn new_gap_center(new_pipes: &NewPipes) -> Option<Vec2> {
+    let mut top = None;
+    let mut bottom = None;
+    for (tf, pipe) in new_pipes {
+        let edge = if pipe.is_top { -0.5 } else { 0.5 } * pipe.size.y;
+        let slot = if pipe.is_top { &mut top } else { &mut bottom };
+        *slot = Some(tf.translation.truncate() + Vec2::Y * edge);
+    }
+    Some((top? + bottom?) * 0.5)
+}
+
+fn move_pipes(
+    time: Res<Time<Fixed>>,
+    mut commands: Commands,
+    course: Course,
+    mut q: Query<(Entity, &mut Transform), With<Pipe>>,
+) {
+    let facing = course.facing();
+    let step = course.pipe_speed() * time.delta_seconds() * facing;
+    for (e, mut tf) in &mut q {
+        tf.translation.x += step;
+
+        if tf.translation.x * facing < PIPE_DESPAWN_X {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+fn sweep_pipes(mut sweep: ResMut<PipeSweep>, pipes: Query<(Entity, &Transform, &Pipe)>) {
+    let PipeSweep { spans, widest } = &mut *sweep;
+    spans.clear();
+    *widest = 0.0;
+    for (e, tf, pipe) in &pipes {
+        let half_w = pipe.size.x * 0.5;
+        spans.push((tf.translation.x - half_w, tf.translation.x + half_w, e));
+        *widest = widest.max(pipe.size.x);
+    }
+    // Pipes scroll together, so this is already nearly in order
+    spans.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_collisions_and_scoring(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    state: Res<State<GameState>>,
+    mut score: ResMut<Score>,
+    course: Course,
+    sweep: Res<PipeSweep>,
+    mut pipes: Query<(&Transform, &mut Pipe)>,
+    mut bird_q: Query<(&mut Transform, &mut Bird, &mut FixedTransform), (LeadBird, Without<Pipe>)>,
+    flock_q: Query<(Entity, &Transform, &Bird, &FixedTransform), With<flock::Flockmate>>,
+    run_hitbox: Res<RunHitbox>,
+    dangers: Dangers,
+    mut passed: EventWriter<PipePassed>,
+    mut near_miss: EventWriter<NearMiss>,
+    mut outcome: CrashOutcome,
+) {
+    let Ok((mut bird_tf, mut bird, mut bird_fixed)) = bird_q.get_single_mut() else {
+        return;
+    };
+    let demo = *state.get() == GameState::Menu;
+
+    // Collisions use the forgiving hitbox, scoring the full sprite, both grown
+    // with the bird in a growth run
+    let bird_half = BIRD_SIZE * 0.5;
+    let hitbox_half = bird_half * (1.0 - run_hitbox.0);
+
+    // The rest of the flock only has to stay clear; being level with the lead
+    // bird, they pass each pipe when it does
+    let mut survivors = Vec::new();
+    for (e, tf, mate, fixed_tf) in &flock_q {
+        let mate_half = hitbox_half * tf.scale.truncate();
+        if bird_hits(
+            tf.translation.truncate(),
+            mate_half,
+            &dangers,
+            &sweep,
+            &pipes,
+        )
+        .is_some()
+        {
+            commands.entity(e).despawn_recursive();
+        } else {
+            survivors.push((e, *tf, mate.vy, mate.vx, *fixed_tf));
+        }
+    }
+
+    let bird_pos = bird_tf.translation.truncate();
+    let bird_half = bird_half * bird_tf.scale.truncate();
+    let hitbox_half = hitbox_half * bird_tf.scale.truncate();
+    let mut hit = bird_hits(bird_pos, hitbox_half, &dangers, &sweep, &pipes);
+
+    // Pipes level with the bird, plus any that slipped past it this tick and
+    // still need scoring
+    let facing = course.facing();
+    let scrolled = course.pipe_speed().abs() * time.delta_seconds();
+    let (behind_left, behind_right) = if facing > 0.0 {
+        (scrolled, 0.0)
+    } else {
+        (0.0, scrolled)
+    };
+    let nearby = sweep.overlapping(
+        bird_pos.x - bird_half.x - behind_left,
+        bird_pos.x + bird_half.x + behind_right,
+    );
+
+    // A crashed bird doesn't score
+    for e in nearby.filter(|_| hit.is_none()) {
+        let Ok((tf, mut pipe)) = pipes.get_mut(e) else {
+            continue;
+        };
+        let pipe_pos = tf.translation.truncate();
+        let pipe_half = pipe.size * 0.5;
+        let overlap_x = (bird_pos.x - pipe_pos.x).abs() <= (hitbox_half.x + pipe_half.x);
+
+        // Close call: level with the pipe and only just clear of it
+        if overlap_x && !pipe.near_missed {
+            let clearance = (bird_pos.y - pipe_pos.y).abs() - (hitbox_half.y + pipe_half.y);
+            if clearance < NEAR_MISS_DISTANCE {
+                pipe.near_missed = true;
+                if !demo {
+                    near_miss.send(NearMiss { above: pipe.is_top });
+                }
+            }
+        }
+
+        // Scoring: only once per pair, a point for every bird still flying,
+        // once its trailing edge is behind the bird's tail
+        if !pipe.scored {
+            let pipe_trailing = pipe_pos.x * facing + pipe_half.x;
+            let bird_tail = bird_pos.x * facing - bird_half.x;
+            if pipe_trailing < bird_tail {
+                score.0 += 1 + survivors.len() as u32;
+                pipe.scored = true;
+                if !demo {
+                    passed.send(PipePassed);
+                }
+            }
+        }
+    }
+
+    // A crashed lead bird hands over to a flockmate, so the camera, autopilot
+    // and everything else watching the lead bird carry on with it
+    if hit.is_some() {
+        if let Some(&(e, tf, vy, vx, fixed_tf)) = survivors.first() {
+            *bird_tf = tf;
+            bird.vy = vy;
+            bird.vx = vx;
+            *bird_fixed = fixed_tf;
+            commands.entity(e).despawn_recursive();
+            hit = None;
+        }
+    }
+
+    if let Some(cause) = hit {
+        if demo {
+            outcome.restart_demo.send(RestartDemo);
+        } else if !outcome.rewind.offer(cause) {
+            outcome.next_state.set(GameState::GameOver);
+            outcome.crashed.send(Crashed(cause));
+        }
+    }
+}
+
+// Where a crash takes the game: a new demo run on the menu, a chance to rewind
+// (see rewind.rs), or the game over screen
+#[derive(SystemParam)]
+struct CrashOutcome<'w> {
+    next_state: ResMut<'w, NextState<GameState>>,
+    crashed: EventWriter<'w, Crashed>,
+    restart_demo: EventWriter<'w, RestartDemo>,
+    rewind: ResMut<'w, rewind::Rewind>,
+}
+
+// What can bring a bird down besides the floor, the ceiling and the pipes, and
+// the streak's shield that makes it and the pipes harmless for a while
+#[derive(SystemParam)]
+struct Dangers<'w, 's> {
+    streak: Res<'w, streak::Streak>,
+    hazards: hazards::Hazards<'w, 's>,
+}
+
+// What a bird at `pos` touches, if anything: the floor, the ceiling or
+// (unless shielded) a hazard or a pipe
+fn bird_hits(
+    pos: Vec2,
+    hitbox_half: Vec2,
+    dangers: &Dangers,
+    sweep: &PipeSweep,
+    pipes: &Query<(&Transform, &mut Pipe)>,
+) -> Option<CrashCause> {
+    let half_h = WINDOW_H * 0.5;
+    if pos.y - hitbox_half.y <= -half_h {
+        return Some(CrashCause::Ground);
+    }
+    if pos.y + hitbox_half.y >= half_h {
+        return Some(CrashCause::Ceiling);
+    }
+    if dangers.streak.shielded() {
+        return None;
+    }
+    if dangers.hazards.hit(pos, hitbox_half) {
+        return Some(CrashCause::Hazard);
+    }
+    sweep
+        .overlapping(pos.x - hitbox_half.x, pos.x + hitbox_half.x)
+        .filter_map(|e| pipes.get(e).ok())
+        .any(|(tf, pipe)| {
+            // AABB overlap
+            let distance = (pos - tf.translation.truncate()).abs();
+            distance.x <= hitbox_half.x + pipe.size.x * 0.5
+                && distance.y <= hitbox_half.y + pipe.size.y * 0.5
+        })
+        .then_some(CrashCause::Pipe)
+}
+
+// --------------------------------------------
+// Stats
+// --------------------------------------------
+
+fn count_stats(
+    ranking: RunRanking,
+    mut stats: ResMut<Stats>,
+    mut flapped: EventReader<Flapped>,
+    mut passed: EventReader<PipePassed>,
+    mut crashed: EventReader<Crashed>,
+    distance: Res<distance::Distance>,
+) {
+    let flaps = flapped.read().count() as u32;
+    let pipes = passed.read().count() as u32;
+    let runs = crashed.read().count() as u32;
+    if !ranking.ranked() {
+        return;
+    }
+    // Don't touch the resource (and trip change detection) on quiet frames
+    if flaps + pipes + runs > 0 {
+        stats.flaps += flaps;
+        stats.pipes_passed += pipes;
+        stats.runs_played += runs;
+        if runs > 0 {
+            stats.meters_flown += distance.meters as u32;
+        }
+    }
+}
+
+// --------------------------------------------
+// Render interpolation
+// --------------------------------------------
+
+// Put the simulated transform back before each fixed step, so physics never sees
+// the interpolated value written for rendering
+fn restore_fixed_transforms(mut q: Query<(&mut Transform, &mut FixedTransform)>) {
+    for (mut tf, mut fixed_tf) in &mut q {
+        *tf = fixed_tf.current;
+        fixed_tf.previous = fixed_tf.current;
+    }
+}
+
+fn record_fixed_transforms(mut q: Query<(&Transform, &mut FixedTransform)>) {
+    for (tf, mut fixed_tf) in &mut q {
+        fixed_tf.current = *tf;
+    }
+}
+
+// Blend between the last two fixed steps by how far we are into the next one,
+// and place the result on screen. Fixed steps work in course space; this is the
+// only place that knows a vertical course is drawn turned.
+fn interpolate_fixed_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    run_modifiers: Res<RunModifiers>,
+    mut q: Query<(&mut Transform, &FixedTransform)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    let view = run_modifiers.0.course_to_world();
+    for (mut tf, fixed_tf) in &mut q {
+        let (prev, curr) = (fixed_tf.previous, fixed_tf.current);
+        let blended = Transform {
+            translation: prev.translation.lerp(curr.translation, alpha),
+            rotation: prev.rotation.slerp(curr.rotation, alpha),
+            scale: curr.scale,
+        };
+        *tf = view * blended;
+    }
+}
+
+// --------------------------------------------
+// Score UI
+// --------------------------------------------
+
+// "0", with room for the longest score
+fn score_buffer() -> String {
+    // As many digits as u32::MAX
+    let mut buffer = String::with_capacity(10);
+    buffer.push('0');
+    buffer
+}
+
+// Rewrites the digits in place; the buffer has room for any u32, so this never
+// allocates. Meters instead of pipes when the run is scored by distance.
+fn update_score_text(
+    score: Res<Score>,
+    distance: Res<distance::Distance>,
+    mut q: Query<&mut Text, With<ScoreText>>,
+) {
+    if !score.is_changed() && !distance.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = q.get_single_mut() {
+        if let Some(section) = text.sections.get_mut(0) {
+            section.value.clear();
+            let _ = if distance.scored {
+                write!(section.value, "{:.0} m", distance.meters)
+            } else {
+                write!(section.value, "{}", score.0)
+            };
+        }
+    }
+}
+
+// --------------------------------------------
+// Game Over UI and input
+// --------------------------------------------
+
+fn update_best_score(score: Res<Score>, ranking: RunRanking, mut best: ResMut<BestScore>) {
+    if ranking.ranked() {
+        best.0 = best.0.max(score.0);
+    }
+}
+
+fn show_game_over_ui(
+    mut commands: Commands,
+    score: Res<Score>,
+    settings: Res<settings::Settings>,
+    run_tick: Res<RunTick>,
+    distance: Res<distance::Distance>,
+    tournament_run: Option<Res<tournament::TournamentRun>>,
+) {
+    let result = if distance.scored {
+        format!("Distance: {:.0} m ({} pipes)", distance.meters, score.0)
+    } else {
+        format!("Score: {}", score.0)
+    };
+    // The run timer's summary takes the blank line under the score
+    let time = if settings.run_timer {
+        timer::summary(score.0, run_tick.0)
+    } else {
+        String::new()
+    };
+    // A tournament run goes back to the bracket instead
+    let (retry, same_course) = if tournament_run.is_some() {
+        ("for the bracket", "")
+    } else {
+        ("to Retry", "T to retry the same course\n")
+    };
+    // Game over text
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "Game Over\n",
+                TextStyle {
+                    font_size: 56.0,
+                    color: Color::srgb(1.0, 0.0, 0.0),
+                    ..default()
+                },
+            ),
+            TextSection::new(
+                format!(
+                    "{result}\n{time}\nPress {} {retry}\n{same_course}S to save replay, F for photo mode",
+                    settings.controls.retry_hint()
+                ),
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(160.0),
+            left: Val::Px(WINDOW_W * 0.5 - 220.0),
+            ..default()
+        }),
+        GameOverUI,
+    ));
+}
+
+fn despawn_game_over_ui(mut commands: Commands, q: Query<Entity, With<GameOverUI>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn game_over_input(
+    controls: controls::GameInput,
+    run_seed: Res<RunSeed>,
+    mut next_seed: ResMut<NextRunSeed>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if controls.retry_same_course() {
+        // Same seed, same pipes, so a section that keeps killing you comes back
+        next_seed.0 = Some(run_seed.0);
+        next_state.set(GameState::Playing);
+    } else if controls.retry() {
+        next_state.set(GameState::Playing);
+    }
+}
@@ -0,0 +1,339 @@
+// LAN lobby: N on the main menu lists the ghost races hosted on the local
+// network, so two players can race without typing addresses. Hosts announce
+// themselves over UDP multicast once a second until a rival joins:
+//
+//   FBLOBBY1 <id> <port> <name>
+//
+// where the id tells a game its own announcements apart, the port is the
+// host's race socket (see race.rs) and the name carries the host's prestige
+// badge, if any. H hosts, Up / Down and Enter join. Once
+// connected, both players press R when they're ready and the host's game starts
+// the first run for both; from then on it's a ghost race like any other.
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+
+use bevy::prelude::*;
+
+use crate::prestige::Prestige;
+use crate::race::RaceLink;
+use crate::toast::ShowToast;
+use crate::{GameState, WINDOW_W};
+
+const MAGIC: &str = "FBLOBBY1";
+// In the organisation-local multicast range
+const DISCOVERY_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 70, 66);
+const DISCOVERY_PORT: u16 = 7880;
+const ANNOUNCE_SECS: f32 = 1.0;
+// Races not heard from for this long drop off the list
+const FORGET_SECS: f32 = 3.5;
+
+pub struct LobbyPlugin;
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Announcer {
+            socket: UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], 0))).ok(),
+            id: rand::random(),
+            name: host_name(),
+            timer: Timer::from_seconds(ANNOUNCE_SECS, TimerMode::Repeating),
+        })
+        .add_systems(Update, open_lobby.run_if(in_state(GameState::Menu)))
+        .add_systems(OnEnter(GameState::Lobby), show_lobby)
+        .add_systems(OnExit(GameState::Lobby), close_lobby)
+        .add_systems(Update, announce_race)
+        .add_systems(
+            Update,
+            (discover_races, lobby_input, start_when_ready, update_lobby)
+                .chain()
+                .run_if(in_state(GameState::Lobby)),
+        );
+    }
+}
+
+// Sends this game's announcements while it hosts a race nobody has joined
+#[derive(Resource)]
+struct Announcer {
+    socket: Option<UdpSocket>,
+    id: u32,
+    name: String,
+    timer: Timer,
+}
+
+// The races heard about while the lobby is open
+#[derive(Resource)]
+struct Discovery {
+    // None if the discovery port couldn't be opened; hosting still works
+    socket: Option<UdpSocket>,
+    races: Vec<LanRace>,
+    selected: usize,
+}
+
+struct LanRace {
+    addr: SocketAddr,
+    name: String,
+    // Real seconds since startup
+    last_seen: f32,
+}
+
+#[derive(Component)]
+struct LobbyUI;
+
+#[derive(Component)]
+struct LobbyText;
+
+// "alice's race", from the account name
+fn host_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .map(|user| format!("{user}'s race"))
+        .unwrap_or_else(|_| "Floopy Birb race".to_string())
+}
+
+fn open_discovery_socket() -> std::io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT))?;
+    socket.join_multicast_v4(&DISCOVERY_GROUP, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+fn open_lobby(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if input.just_pressed(KeyCode::KeyN) {
+        next_state.set(GameState::Lobby);
+    }
+}
+
+fn show_lobby(mut commands: Commands, mut toasts: EventWriter<ShowToast>) {
+    let socket = match open_discovery_socket() {
+        Ok(socket) => Some(socket),
+        Err(err) => {
+            warn!("Cannot look for LAN races on port {DISCOVERY_PORT}: {err}");
+            toasts.send(ShowToast(
+                "Can't look for races on this network, but you can host one".to_string(),
+            ));
+            None
+        }
+    };
+    commands.insert_resource(Discovery {
+        socket,
+        races: Vec::new(),
+        selected: 0,
+    });
+
+    commands.spawn((
+        TextBundle::from_section(
+            "LAN races",
+            TextStyle {
+                font_size: 56.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(WINDOW_W * 0.5 - 130.0),
+            ..default()
+        }),
+        LobbyUI,
+    ));
+    // Filled in by update_lobby
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(120.0),
+            left: Val::Px(WINDOW_W * 0.5 - 250.0),
+            ..default()
+        }),
+        LobbyUI,
+        LobbyText,
+    ));
+}
+
+// The race itself stays open when leaving the lobby
+fn close_lobby(mut commands: Commands, q: Query<Entity, With<LobbyUI>>) {
+    commands.remove_resource::<Discovery>();
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn announce_race(
+    time: Res<Time<Real>>,
+    mut announcer: ResMut<Announcer>,
+    link: Option<Res<RaceLink>>,
+    prestige: Res<Prestige>,
+) {
+    if !announcer.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Some(link) = link.filter(|link| link.hosting() && !link.connected()) else {
+        return;
+    };
+    let (Some(socket), Some(port)) = (&announcer.socket, link.port()) else {
+        return;
+    };
+    let name = match prestige.badge() {
+        Some(badge) => format!("{} ({badge})", announcer.name),
+        None => announcer.name.clone(),
+    };
+    let message = format!("{MAGIC} {} {port} {name}", announcer.id);
+    let _ = socket.send_to(message.as_bytes(), (DISCOVERY_GROUP, DISCOVERY_PORT));
+}
+
+fn discover_races(
+    time: Res<Time<Real>>,
+    announcer: Res<Announcer>,
+    mut discovery: ResMut<Discovery>,
+) {
+    let now = time.elapsed_seconds();
+    let mut buf = [0; 256];
+    while let Some(Ok((len, from))) = discovery.socket.as_ref().map(|s| s.recv_from(&mut buf)) {
+        let Some((id, port, name)) = std::str::from_utf8(&buf[..len])
+            .ok()
+            .and_then(parse_announcement)
+        else {
+            continue;
+        };
+        if id == announcer.id {
+            continue;
+        }
+        let addr = SocketAddr::new(from.ip(), port);
+        match discovery.races.iter_mut().find(|race| race.addr == addr) {
+            Some(race) => {
+                race.name = name.to_string();
+                race.last_seen = now;
+            }
+            None => discovery.races.push(LanRace {
+                addr,
+                name: name.to_string(),
+                last_seen: now,
+            }),
+        }
+    }
+
+    let before = discovery.races.len();
+    discovery
+        .races
+        .retain(|race| now - race.last_seen <= FORGET_SECS);
+    if discovery.races.len() != before {
+        discovery.selected = discovery
+            .selected
+            .min(discovery.races.len().saturating_sub(1));
+    }
+}
+
+fn parse_announcement(text: &str) -> Option<(u32, u16, &str)> {
+    let rest = text.strip_prefix(MAGIC)?.trim_start();
+    let (id, rest) = rest.split_once(' ')?;
+    let (port, name) = rest.split_once(' ')?;
+    Some((id.parse().ok()?, port.parse().ok()?, name.trim()))
+}
+
+fn lobby_input(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    mut discovery: ResMut<Discovery>,
+    link: Option<ResMut<RaceLink>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+        return;
+    }
+    let count = discovery.races.len();
+    if count > 0 {
+        if input.just_pressed(KeyCode::ArrowDown) {
+            discovery.selected = (discovery.selected + 1) % count;
+        }
+        if input.just_pressed(KeyCode::ArrowUp) {
+            discovery.selected = (discovery.selected + count - 1) % count;
+        }
+    }
+
+    if let Some(mut link) = link.filter(|link| link.connected()) {
+        if input.just_pressed(KeyCode::KeyR) {
+            let ready = !link.ready();
+            link.set_ready(ready);
+        }
+        return;
+    }
+
+    // Hosting or joining replaces any race already open
+    let opened = if input.just_pressed(KeyCode::KeyH) {
+        RaceLink::host(0)
+    } else if input.just_pressed(KeyCode::Enter) {
+        match discovery.races.get(discovery.selected) {
+            Some(race) => RaceLink::join(&race.addr.to_string()),
+            None => return,
+        }
+    } else {
+        return;
+    };
+    match opened {
+        Ok(link) => commands.insert_resource(link),
+        Err(err) => {
+            error!("Cannot start the race: {err}");
+            toasts.send(ShowToast("Starting the race failed".to_string()));
+        }
+    }
+}
+
+// The host starts the first run once both are ready, which starts the rival's
+fn start_when_ready(link: Option<Res<RaceLink>>, mut next_state: ResMut<NextState<GameState>>) {
+    let Some(link) = link else {
+        return;
+    };
+    if link.hosting() && link.connected() && link.ready() && link.rival_ready() {
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn update_lobby(
+    discovery: Res<Discovery>,
+    link: Option<Res<RaceLink>>,
+    mut q: Query<&mut Text, With<LobbyText>>,
+) {
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    let mut value = String::new();
+    match link.as_deref() {
+        Some(link) if link.connected() => {
+            let word = |ready| if ready { "ready" } else { "not ready" };
+            value.push_str(&format!(
+                "Rival connected\n\nYou: {}\nRival: {}\n\nR to toggle ready; the race starts when you both are\n\n",
+                word(link.ready()),
+                word(link.rival_ready())
+            ));
+        }
+        Some(link) if link.hosting() => value.push_str(&format!(
+            "Hosting on port {}, waiting for a rival...\n\n",
+            link.port().unwrap_or_default()
+        )),
+        _ => {}
+    }
+    if link.as_deref().is_none_or(|link| !link.connected()) {
+        value.push_str("Races on this network:\n\n");
+        if discovery.races.is_empty() {
+            value.push_str("  (none found yet)\n");
+        }
+        for (i, race) in discovery.races.iter().enumerate() {
+            let marker = if i == discovery.selected { '>' } else { ' ' };
+            value.push_str(&format!("{marker} {} ({})\n", race.name, race.addr));
+        }
+        value.push_str("\nUp / Down to pick, Enter to join, H to host\n");
+    }
+    value.push_str("Esc to go back");
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
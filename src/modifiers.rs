@@ -0,0 +1,322 @@
+// Run modifiers: rule changes a run can be played with, on top of the hitbox
+// preset. They change the simulation, so the set a run used is part of its
+// replay and its challenge code (see challenge.rs). Each has a fixed bit, so
+// codes and replays keep meaning the same thing as modifiers are added.
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::fmt;
+
+use bevy::prelude::{Quat, Transform, Vec3};
+
+use crate::achievements::Achievement;
+use crate::{FIXED_HZ, GRAVITY, PIPE_GAP, PIPE_SPEED, WINDOW_H, WINDOW_W};
+
+// Wind pushes the bird up and down in slow gusts
+const WIND_ACCEL: f32 = 250.0; // px / s^2 at the peak of a gust
+const WIND_PERIOD_TICKS: f32 = 240.0;
+// A vertical course is shrunk so its length fits the window's height
+const VERTICAL_SCALE: f32 = WINDOW_H / WINDOW_W;
+// On ice a flap only replaces part of the bird's vertical speed, and kicks it
+// forward a little
+const ICE_FLAP_GRIP: f32 = 0.65;
+const ICE_FLAP_DRIFT: f32 = 45.0; // px / s
+
+// A run warms up over its first seconds: pipes start slower and with wider
+// gaps, easing into the normal course
+const WARM_UP_SECONDS: f32 = 8.0;
+const WARM_UP_SPEED: f32 = 0.7;
+const WARM_UP_GAP: f32 = 1.25;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Modifier {
+    // Gaps 20% narrower
+    SmallGap,
+    // Pipes scroll 25% faster
+    FastPipes,
+    // Darkened city
+    Night,
+    // Gusts pushing the bird up and down
+    Wind,
+    // Several birds on one flap button (see flock.rs)
+    Flock,
+    // The course runs the other way: pipes come from the left, the bird sits
+    // on the right
+    Reverse,
+    // The course turned on its side: the bird climbs up the screen through
+    // barriers falling towards it, and flaps nudge it sideways
+    Vertical,
+    // Everything drawn flipped left to right, with the rules unchanged
+    Mirror,
+    // A winter course: the bird slides forward on each flap and drifts back,
+    // and flaps keep some of its momentum
+    Ice,
+    // Gravity pulls the bird up and flaps push it down: the course drawn the
+    // other way up, with the rules unchanged
+    UpsideDown,
+    // Coins in the gaps make the bird bigger and its pipes worth more (see
+    // growth.rs)
+    Growth,
+    // The score drains away and pipes are worth more; the run ends if it runs
+    // out (see decay.rs)
+    Decay,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 12] = [
+        Modifier::SmallGap,
+        Modifier::FastPipes,
+        Modifier::Night,
+        Modifier::Wind,
+        Modifier::Flock,
+        Modifier::Reverse,
+        Modifier::Vertical,
+        Modifier::Mirror,
+        Modifier::Ice,
+        Modifier::UpsideDown,
+        Modifier::Growth,
+        Modifier::Decay,
+    ];
+
+    // The ones that can be picked as the game mode in the settings menu
+    pub const MODES: [Modifier; 8] = [
+        Modifier::Flock,
+        Modifier::Reverse,
+        Modifier::Vertical,
+        Modifier::Mirror,
+        Modifier::Ice,
+        Modifier::UpsideDown,
+        Modifier::Growth,
+        Modifier::Decay,
+    ];
+
+    // Modes that only show up in the settings menu once this is earned
+    pub fn unlocked_by(self) -> Option<Achievement> {
+        match self {
+            Modifier::Reverse => Some(Achievement::TenPipes),
+            Modifier::Mirror => Some(Achievement::TwentyFivePipes),
+            Modifier::UpsideDown => Some(Achievement::FiftyPipes),
+            _ => None,
+        }
+    }
+
+    fn bit(self) -> u16 {
+        match self {
+            Modifier::SmallGap => 1 << 0,
+            Modifier::FastPipes => 1 << 1,
+            Modifier::Night => 1 << 2,
+            Modifier::Wind => 1 << 3,
+            Modifier::Flock => 1 << 4,
+            Modifier::Reverse => 1 << 5,
+            Modifier::Vertical => 1 << 6,
+            Modifier::Mirror => 1 << 7,
+            Modifier::Ice => 1 << 8,
+            Modifier::UpsideDown => 1 << 9,
+            Modifier::Growth => 1 << 10,
+            Modifier::Decay => 1 << 11,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Modifier::SmallGap => "Small gap",
+            Modifier::FastPipes => "Fast pipes",
+            Modifier::Night => "Night",
+            Modifier::Wind => "Wind",
+            Modifier::Flock => "Flock",
+            Modifier::Reverse => "Reverse",
+            Modifier::Vertical => "Vertical",
+            Modifier::Mirror => "Mirror",
+            Modifier::Ice => "Ice",
+            Modifier::UpsideDown => "Upside down",
+            Modifier::Growth => "Growth",
+            Modifier::Decay => "Decay",
+        }
+    }
+
+    // For the settings file
+    pub fn name(self) -> &'static str {
+        match self {
+            Modifier::SmallGap => "small_gap",
+            Modifier::FastPipes => "fast_pipes",
+            Modifier::Night => "night",
+            Modifier::Wind => "wind",
+            Modifier::Flock => "flock",
+            Modifier::Reverse => "reverse",
+            Modifier::Vertical => "vertical",
+            Modifier::Mirror => "mirror",
+            Modifier::Ice => "ice",
+            Modifier::UpsideDown => "upside_down",
+            Modifier::Growth => "growth",
+            Modifier::Decay => "decay",
+        }
+    }
+}
+
+// A set of modifiers, as the bits challenge codes and replays store
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Modifiers(u16);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers(0);
+    // Bits a challenge code has room for
+    pub const BITS: u32 = 12;
+
+    pub fn from_bits(bits: u16) -> Self {
+        Self(bits & ((1 << Self::BITS) - 1))
+    }
+
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+
+    pub fn with(self, modifier: Modifier) -> Self {
+        Self(self.0 | modifier.bit())
+    }
+
+    pub fn has(self, modifier: Modifier) -> bool {
+        self.0 & modifier.bit() != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // The game mode among these (see Modifier::MODES), or None for the normal game
+    pub fn mode(self) -> Option<Modifier> {
+        Modifier::MODES.into_iter().find(|&m| self.has(m))
+    }
+
+    // Multiplier on the pipe gap
+    pub fn gap_scale(self) -> f32 {
+        if self.has(Modifier::SmallGap) {
+            0.8
+        } else {
+            1.0
+        }
+    }
+
+    // Vertical push on the bird at a fixed tick of the run, in px / s^2. Driven
+    // by the tick alone, so replays see the same gusts.
+    pub fn wind(self, tick: u32) -> f32 {
+        if self.has(Modifier::Wind) {
+            WIND_ACCEL * (TAU * tick as f32 / WIND_PERIOD_TICKS).sin()
+        } else {
+            0.0
+        }
+    }
+
+    // How much of its vertical speed a flap replaces: all of it, or part on ice
+    pub fn flap_grip(self) -> f32 {
+        if self.has(Modifier::Ice) {
+            ICE_FLAP_GRIP
+        } else {
+            1.0
+        }
+    }
+
+    // Forward kick a flap gives the bird along the course, in px / s
+    pub fn flap_drift(self) -> f32 {
+        if self.has(Modifier::Ice) {
+            ICE_FLAP_DRIFT
+        } else {
+            0.0
+        }
+    }
+
+    // Which way the bird flies along x: 1 to the right, -1 in reverse. The
+    // course is the normal one mirrored, so positions along the course are
+    // world x times this.
+    pub fn facing(self) -> f32 {
+        if self.has(Modifier::Reverse) {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    // From course space, where the simulation always runs with the pipes
+    // scrolling along x and the bird falling along y, to the screen. A vertical
+    // course is turned a quarter anticlockwise, so the pipes fall and gravity
+    // pulls the bird to the right.
+    pub fn course_to_world(self) -> Transform {
+        if self.has(Modifier::Vertical) {
+            Transform::from_rotation(Quat::from_rotation_z(FRAC_PI_2)).with_scale(Vec3::new(
+                VERTICAL_SCALE,
+                VERTICAL_SCALE,
+                1.0,
+            ))
+        } else {
+            Transform::IDENTITY
+        }
+    }
+
+    // Multiplier on the pipe scroll speed
+    pub fn speed_scale(self) -> f32 {
+        if self.has(Modifier::FastPipes) {
+            1.25
+        } else {
+            1.0
+        }
+    }
+}
+
+// "Small gap, Fast pipes", or "None"
+impl fmt::Display for Modifiers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "None");
+        }
+        let labels: Vec<&str> = Modifier::ALL
+            .into_iter()
+            .filter(|&m| self.has(m))
+            .map(Modifier::label)
+            .collect();
+        write!(f, "{}", labels.join(", "))
+    }
+}
+
+// The tuning a run is played with, which challenge presets change (see
+// presets.rs). Kept as the values themselves rather than as multipliers, so a
+// replay stores exactly what its run used.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rules {
+    pub gravity: f32,
+    pub pipe_gap: f32,
+    // Negative: pipes scroll towards the bird
+    pub pipe_speed: f32,
+    pub hazards: bool,
+    // Whether a random mutator takes over every few points (see roulette.rs)
+    pub roulette: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            gravity: GRAVITY,
+            pipe_gap: PIPE_GAP,
+            pipe_speed: PIPE_SPEED,
+            hazards: true,
+            roulette: false,
+        }
+    }
+}
+
+impl Rules {
+    // How far into the warm-up a run is at a fixed tick: 0 at the start, 1 once
+    // it's over. Smoothstep, so the course neither lurches into the ramp nor
+    // out of it.
+    pub fn warm_up(self, tick: u32) -> f32 {
+        let t = (tick as f32 / (WARM_UP_SECONDS * FIXED_HZ as f32)).min(1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    // Multiplier on the pipe scroll speed at a fixed tick
+    pub fn warm_up_speed_scale(self, tick: u32) -> f32 {
+        WARM_UP_SPEED + (1.0 - WARM_UP_SPEED) * self.warm_up(tick)
+    }
+
+    // Multiplier on the gap of pipes spawned at a fixed tick
+    pub fn warm_up_gap_scale(self, tick: u32) -> f32 {
+        WARM_UP_GAP + (1.0 - WARM_UP_GAP) * self.warm_up(tick)
+    }
+}
@@ -0,0 +1,388 @@
+// Mods: skins, themes and levels dropped into `mods/`, one folder per mod.
+//
+//   mods/<id>/mod.txt      manifest, `key = value` lines:
+//                            name, author, description
+//                            pipe_pattern = comma-separated gap positions from
+//                              -1 (lowest) to 1 (highest), cycled instead of random
+//   mods/<id>/textures/... replaces the file at the same path under assets/
+//   mods/<id>/music/...    (bird.png and atlas.txt, city backgrounds, music.ogg);
+//                            other tracks in music/ join the playlist
+//   mods/<id>/story.txt    story mode campaign (see story.rs)
+//
+// Asset paths go through `Mods::resolve`, so an enabled mod shadows the default
+// file of the same name; the first enabled mod wins. Mods are enabled from the
+// mod menu (O on the main menu) and remembered between launches. Replays and
+// codes don't record a pipe pattern, so runs on one aren't ranked or shared.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::asset::io::AssetSource;
+use bevy::prelude::*;
+
+use crate::atlas::SpriteAtlas;
+use crate::toast::ShowToast;
+use crate::{Bird, GameState, WINDOW_W};
+
+const MODS_DIR: &str = "mods";
+const MANIFEST_FILE: &str = "mod.txt";
+// Bevy's default asset folder, which mods shadow
+const ASSETS_DIR: &str = "assets";
+// Asset source name, for paths like "mods://<id>/textures/bird.png"
+const MODS_SOURCE: &str = "mods";
+// How long a mod's files must sit still before a change to them is reloaded
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+// Number keys toggle mods, so the menu shows at most nine
+const MAX_LISTED_MODS: usize = 9;
+
+pub struct ModsPlugin;
+
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Mods::discover())
+            .add_systems(Update, open_mod_menu.run_if(in_state(GameState::Menu)))
+            .add_systems(OnEnter(GameState::Mods), show_mod_menu)
+            .add_systems(OnExit(GameState::Mods), despawn_mod_menu)
+            .add_systems(
+                Update,
+                (mod_menu_input, update_mod_menu, apply_bird_skin)
+                    .chain()
+                    .run_if(in_state(GameState::Mods)),
+            );
+    }
+}
+
+// Must be registered before the asset plugin is built. The watcher only runs
+// when the asset plugin watches for changes (see hot_reload.rs).
+pub fn register_asset_source(app: &mut App) {
+    app.register_asset_source(
+        MODS_SOURCE,
+        AssetSource::build()
+            .with_reader(AssetSource::get_default_reader(MODS_DIR.to_string()))
+            .with_watcher(AssetSource::get_default_watcher(
+                MODS_DIR.to_string(),
+                WATCH_DEBOUNCE,
+            )),
+    );
+}
+
+pub struct ModManifest {
+    // Folder name
+    pub id: String,
+    pub name: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub pipe_pattern: Vec<f32>,
+}
+
+impl ModManifest {
+    fn parse(id: &str, text: &str) -> Self {
+        let mut manifest = Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            author: None,
+            description: None,
+            pipe_pattern: Vec::new(),
+        };
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warn!("{id}/{MANIFEST_FILE}: ignoring line \"{line}\"");
+                continue;
+            };
+            let value = value.trim().to_string();
+            match key.trim() {
+                "name" => manifest.name = value,
+                "author" => manifest.author = Some(value),
+                "description" => manifest.description = Some(value),
+                "pipe_pattern" => {
+                    manifest.pipe_pattern = value
+                        .split(',')
+                        .filter_map(|v| v.trim().parse::<f32>().ok())
+                        .filter(|v| v.is_finite())
+                        .map(|v| v.clamp(-1.0, 1.0))
+                        .collect();
+                }
+                other => warn!("{id}/{MANIFEST_FILE}: unknown key \"{other}\""),
+            }
+        }
+        manifest
+    }
+}
+
+// Installed mods and which of them are enabled
+#[derive(Resource, Default)]
+pub struct Mods {
+    pub available: Vec<ModManifest>,
+    // Ids, in priority order
+    enabled: Vec<String>,
+}
+
+impl Mods {
+    fn discover() -> Self {
+        let root = mods_root();
+        let mut available: Vec<ModManifest> = std::fs::read_dir(&root)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let id = entry.file_name().to_string_lossy().into_owned();
+                let text = std::fs::read_to_string(entry.path().join(MANIFEST_FILE)).ok()?;
+                Some(ModManifest::parse(&id, &text))
+            })
+            .collect();
+        available.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let enabled = std::fs::read_to_string(enabled_list_path())
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|id| available.iter().any(|m| m.id == *id))
+            .map(str::to_string)
+            .collect();
+
+        for manifest in &available {
+            info!("Found mod {} ({})", manifest.name, manifest.id);
+        }
+        Self { available, enabled }
+    }
+
+    pub fn is_enabled(&self, id: &str) -> bool {
+        self.enabled.iter().any(|e| e == id)
+    }
+
+    fn toggle(&mut self, id: &str) {
+        if self.is_enabled(id) {
+            self.enabled.retain(|e| e != id);
+        } else {
+            self.enabled.push(id.to_string());
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let path = enabled_list_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.enabled.join("\n"))
+    }
+
+    // Where to load an asset from: the first enabled mod that ships a file at
+    // `path`, or the game's own assets
+    pub fn resolve(&self, path: &str) -> String {
+        let root = mods_root();
+        self.enabled
+            .iter()
+            .find(|id| root.join(id).join(path).is_file())
+            .map_or_else(
+                || path.to_string(),
+                |id| format!("{MODS_SOURCE}://{id}/{path}"),
+            )
+    }
+
+    // Like `resolve`, but a path on disk, for files read without the asset server
+    pub fn resolve_file(&self, path: &str) -> PathBuf {
+        let root = mods_root();
+        self.enabled
+            .iter()
+            .map(|id| root.join(id).join(path))
+            .find(|file| file.is_file())
+            .unwrap_or_else(|| FileAssetReader::get_base_path().join(ASSETS_DIR).join(path))
+    }
+
+    // Names of the files in the asset folder `dir`, the game's own and those
+    // enabled mods add, sorted; load each through `resolve`
+    pub fn list_dir(&self, dir: &str) -> Vec<String> {
+        let root = mods_root();
+        let dirs = std::iter::once(FileAssetReader::get_base_path().join(ASSETS_DIR).join(dir))
+            .chain(self.enabled.iter().map(|id| root.join(id).join(dir)));
+        let mut names: Vec<String> = dirs
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    // Gap positions to cycle through instead of random ones, if a mod sets them
+    pub fn pipe_pattern(&self) -> Option<&[f32]> {
+        self.enabled
+            .iter()
+            .filter_map(|id| self.available.iter().find(|m| m.id == *id))
+            .map(|m| m.pipe_pattern.as_slice())
+            .find(|pattern| !pattern.is_empty())
+    }
+}
+
+fn mods_root() -> PathBuf {
+    // Same base directory the asset source reads from
+    FileAssetReader::get_base_path().join(MODS_DIR)
+}
+
+fn enabled_list_path() -> PathBuf {
+    crate::data_file("enabled_mods.txt")
+}
+
+// --------------------------------------------
+// Mod menu
+// --------------------------------------------
+
+#[derive(Component)]
+struct ModMenuUI;
+
+#[derive(Component)]
+struct ModList;
+
+fn open_mod_menu(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if input.just_pressed(KeyCode::KeyO) {
+        next_state.set(GameState::Mods);
+    }
+}
+
+fn show_mod_menu(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "Mods",
+            TextStyle {
+                font_size: 56.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(WINDOW_W * 0.5 - 60.0),
+            ..default()
+        }),
+        ModMenuUI,
+    ));
+    // Filled in by update_mod_menu
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(120.0),
+            left: Val::Px(80.0),
+            ..default()
+        }),
+        ModMenuUI,
+        ModList,
+    ));
+}
+
+fn despawn_mod_menu(mut commands: Commands, q: Query<Entity, With<ModMenuUI>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn mod_menu_input(
+    input: Res<ButtonInput<KeyCode>>,
+    mut mods: ResMut<Mods>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if input.just_pressed(KeyCode::Escape) || input.just_pressed(KeyCode::KeyO) {
+        next_state.set(GameState::Menu);
+        return;
+    }
+
+    const NUMBER_KEYS: [KeyCode; MAX_LISTED_MODS] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    let Some(index) = NUMBER_KEYS.iter().position(|&k| input.just_pressed(k)) else {
+        return;
+    };
+    let Some(id) = mods.available.get(index).map(|m| m.id.clone()) else {
+        return;
+    };
+    mods.toggle(&id);
+    if let Err(err) = mods.save() {
+        error!("Cannot save enabled mods: {err}");
+        toasts.send(ShowToast("Couldn't remember enabled mods".to_string()));
+    }
+}
+
+fn update_mod_menu(
+    mods: Res<Mods>,
+    mut q: Query<&mut Text, With<ModList>>,
+    added: Query<(), Added<ModList>>,
+) {
+    if !mods.is_changed() && added.is_empty() {
+        return;
+    }
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+
+    let mut list = String::new();
+    if mods.available.is_empty() {
+        list.push_str("No mods installed.\nAdd them as folders under mods/ (see the README).\n");
+    }
+    for (i, manifest) in mods.available.iter().take(MAX_LISTED_MODS).enumerate() {
+        let check = if mods.is_enabled(&manifest.id) {
+            "x"
+        } else {
+            " "
+        };
+        list.push_str(&format!("{}. [{check}] {}", i + 1, manifest.name));
+        if let Some(author) = &manifest.author {
+            list.push_str(&format!(" by {author}"));
+        }
+        list.push('\n');
+        if let Some(description) = &manifest.description {
+            list.push_str(&format!("      {description}\n"));
+        }
+    }
+    list.push_str("\nNumber keys toggle, Esc to go back");
+    text.sections[0].value = list;
+}
+
+// Swap the sprite sheet as soon as a skin is switched on or off
+fn apply_bird_skin(
+    mods: Res<Mods>,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut atlas: ResMut<SpriteAtlas>,
+    mut bird_q: Query<(&mut Handle<Image>, &mut TextureAtlas, &mut Bird)>,
+) {
+    if !mods.is_changed() {
+        return;
+    }
+    let new_atlas = SpriteAtlas::load(&mods, &asset_server, &mut layouts);
+    if new_atlas.texture == atlas.texture {
+        return;
+    }
+    let frames = new_atlas.frames("bird");
+    for (mut texture, mut sprite_atlas, mut bird) in &mut bird_q {
+        *texture = new_atlas.texture.clone();
+        sprite_atlas.layout = new_atlas.layout.clone();
+        sprite_atlas.index = frames.first().copied().unwrap_or_default();
+        bird.frames = frames.clone();
+    }
+    *atlas = new_atlas;
+}
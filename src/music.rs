@@ -0,0 +1,253 @@
+// Music: every track in assets/music/ (and in enabled mods' music/ folders)
+// plays in turn, in name order or shuffled (Music in the settings menu), moving
+// on when a track ends or when ] is pressed. The new track's name shows for a
+// moment whenever it changes.
+//
+// A crash ducks the music under the crash stinger (sounds/crash.ogg, if the
+// game or a mod ships one), then brings it back up only part way while the
+// game over screen is up. The volume follows a small envelope: it drops fast
+// and rises slowly towards whatever level it should be at.
+
+use bevy::audio::PlaybackMode;
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::settings::Settings;
+use crate::toast::ShowToast;
+use crate::{mods, Crashed, GameState, MusicState};
+
+const MUSIC_DIR: &str = "music";
+const TRACK_EXTENSIONS: [&str; 4] = ["ogg", "mp3", "wav", "flac"];
+const STINGER_FILE: &str = "sounds/crash.ogg";
+// Music volume under the stinger, for this many seconds after a crash
+const DUCK_VOLUME: f32 = 0.2;
+const DUCK_SECS: f32 = 1.2;
+// Music volume on the game over screen once the stinger is done
+const GAME_OVER_VOLUME: f32 = 0.6;
+// How fast the volume falls and rises, in full volume per second
+const DUCK_RATE: f32 = 10.0;
+const RESTORE_RATE: f32 = 0.8;
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicVolume>()
+            .add_systems(Startup, (start_playlist, load_stinger))
+            .add_systems(
+                Update,
+                (advance_playlist, (duck_on_crash, follow_envelope).chain()),
+            );
+    }
+}
+
+// The track playing; despawned by the audio player once it ends
+#[derive(Component)]
+pub struct MusicTrack;
+
+#[derive(Resource, Default)]
+struct Playlist {
+    // File names under music/
+    tracks: Vec<String>,
+    // A shuffled round of the tracks, and how far through it the music is
+    order: Vec<usize>,
+    next: usize,
+    current: Option<usize>,
+}
+
+impl Playlist {
+    // Moves on to the track after the current one, if there are any
+    fn advance(&mut self, shuffle: bool) -> Option<usize> {
+        let count = self.tracks.len();
+        if count == 0 {
+            return None;
+        }
+        let track = if shuffle {
+            if self.next >= self.order.len() {
+                self.order = (0..count).collect();
+                self.order.shuffle(&mut rand::thread_rng());
+                // Not the same track twice running across two rounds
+                if count > 1 && Some(self.order[0]) == self.current {
+                    self.order.swap(0, count - 1);
+                }
+                self.next = 0;
+            }
+            self.next += 1;
+            self.order[self.next - 1]
+        } else {
+            self.current.map_or(0, |current| (current + 1) % count)
+        };
+        self.current = Some(track);
+        Some(track)
+    }
+}
+
+// "night_drive.ogg" plays as "Night drive"
+fn track_title(file: &str) -> String {
+    let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+    let mut title: String = stem
+        .chars()
+        .map(|c| if c == '_' || c == '-' { ' ' } else { c })
+        .collect();
+    if let Some(first) = title.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    title
+}
+
+fn play(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    mods: &mods::Mods,
+    file: &str,
+    muted: bool,
+) {
+    commands.spawn((
+        AudioBundle {
+            source: asset_server.load(mods.resolve(&format!("{MUSIC_DIR}/{file}"))),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                paused: muted,
+                ..default()
+            },
+        },
+        MusicTrack,
+    ));
+}
+
+fn start_playlist(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mods: Res<mods::Mods>,
+    settings: Res<Settings>,
+    music_state: Res<MusicState>,
+) {
+    let mut playlist = Playlist {
+        tracks: mods
+            .list_dir(MUSIC_DIR)
+            .into_iter()
+            .filter(|file| {
+                file.rsplit_once('.')
+                    .is_some_and(|(_, ext)| TRACK_EXTENSIONS.contains(&ext))
+            })
+            .collect(),
+        ..default()
+    };
+    if let Some(track) = playlist.advance(settings.shuffle_music) {
+        let file = &playlist.tracks[track];
+        play(&mut commands, &asset_server, &mods, file, music_state.muted);
+    }
+    commands.insert_resource(playlist);
+}
+
+// On to the next track when this one has ended, or on ]
+#[allow(clippy::too_many_arguments)]
+fn advance_playlist(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mods: Res<mods::Mods>,
+    settings: Res<Settings>,
+    music_state: Res<MusicState>,
+    mut playlist: ResMut<Playlist>,
+    playing: Query<Entity, With<MusicTrack>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let skip = input.just_pressed(KeyCode::BracketRight);
+    if !skip && !playing.is_empty() {
+        return;
+    }
+    for e in &playing {
+        commands.entity(e).despawn_recursive();
+    }
+    let previous = playlist.current;
+    let Some(track) = playlist.advance(settings.shuffle_music) else {
+        return;
+    };
+    let file = &playlist.tracks[track];
+    play(&mut commands, &asset_server, &mods, file, music_state.muted);
+    // A lone track just loops
+    if skip || previous != Some(track) {
+        toasts.send(ShowToast(format!("Now playing: {}", track_title(file))));
+    }
+}
+
+// --------------------------------------------
+// Ducking
+// --------------------------------------------
+
+#[derive(Resource)]
+struct MusicVolume {
+    level: f32,
+    // Seconds left with the music ducked under the stinger
+    ducked_for: f32,
+}
+
+impl Default for MusicVolume {
+    fn default() -> Self {
+        Self {
+            level: 1.0,
+            ducked_for: 0.0,
+        }
+    }
+}
+
+// None when neither the game nor an enabled mod has one
+#[derive(Resource)]
+struct Stinger(Option<Handle<AudioSource>>);
+
+fn load_stinger(mut commands: Commands, asset_server: Res<AssetServer>, mods: Res<mods::Mods>) {
+    let stinger = mods
+        .resolve_file(STINGER_FILE)
+        .is_file()
+        .then(|| asset_server.load(mods.resolve(STINGER_FILE)));
+    commands.insert_resource(Stinger(stinger));
+}
+
+fn duck_on_crash(
+    mut commands: Commands,
+    mut crashed: EventReader<Crashed>,
+    stinger: Res<Stinger>,
+    mut volume: ResMut<MusicVolume>,
+) {
+    if crashed.read().count() == 0 {
+        return;
+    }
+    volume.ducked_for = DUCK_SECS;
+    if let Some(stinger) = &stinger.0 {
+        commands.spawn(AudioBundle {
+            source: stinger.clone(),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+// In real time, so it carries on while the game is paused or slowed
+fn follow_envelope(
+    time: Res<Time<Real>>,
+    state: Res<State<GameState>>,
+    mut volume: ResMut<MusicVolume>,
+    sinks: Query<&AudioSink, With<MusicTrack>>,
+) {
+    let dt = time.delta_seconds();
+    volume.ducked_for = (volume.ducked_for - dt).max(0.0);
+    let target = if volume.ducked_for > 0.0 {
+        DUCK_VOLUME
+    } else if *state.get() == GameState::GameOver {
+        GAME_OVER_VOLUME
+    } else {
+        1.0
+    };
+    let level = if target < volume.level {
+        (volume.level - DUCK_RATE * dt).max(target)
+    } else {
+        (volume.level + RESTORE_RATE * dt).min(target)
+    };
+    volume.level = level;
+    // New tracks start at full volume, so every sink is kept in step
+    for sink in &sinks {
+        if sink.volume() != volume.level {
+            sink.set_volume(volume.level);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// New best notifications: when a run beats the best score, the taskbar (or
+// dock) icon asks for attention, which only shows if the game is in the
+// background. With New best notification on in the settings menu, a desktop
+// notification says so too.
+//
+// The best score to beat is the best ranked run ever, kept in best.txt next to
+// the settings as a `best = score` line; the session's best starts from zero
+// on every launch, so the first run of each would always beat it.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy::winit::WinitWindows;
+use notify_rust::Notification;
+use winit::window::UserAttentionType;
+
+use crate::branding::Branding;
+use crate::settings::Settings;
+use crate::{GameState, RunRanking, Score};
+
+pub struct NotifyPlugin;
+
+impl Plugin for NotifyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AllTimeBest::load())
+            .add_systems(OnEnter(GameState::GameOver), notify_new_best);
+    }
+}
+
+#[derive(Resource, Default)]
+struct AllTimeBest(u32);
+
+impl AllTimeBest {
+    fn load() -> Self {
+        let text = std::fs::read_to_string(best_path()).unwrap_or_default();
+        let best = text
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .find(|(key, _)| key.trim() == "best")
+            .and_then(|(_, value)| value.trim().parse().ok())
+            .unwrap_or(0);
+        Self(best)
+    }
+
+    fn save(&self) {
+        let path = best_path();
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, format!("best = {}\n", self.0)));
+        if let Err(err) = result {
+            error!("Cannot save the best score: {err}");
+        }
+    }
+}
+
+fn best_path() -> PathBuf {
+    crate::data_file("best.txt")
+}
+
+// Bevy has no attention request of its own, so this goes to winit directly
+fn notify_new_best(
+    score: Res<Score>,
+    mut best: ResMut<AllTimeBest>,
+    ranking: RunRanking,
+    settings: Res<Settings>,
+    branding: Res<Branding>,
+    winit_windows: NonSend<WinitWindows>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+) {
+    if !ranking.ranked() || score.0 <= best.0 {
+        return;
+    }
+    best.0 = score.0;
+    best.save();
+    if let Some(window) = windows
+        .get_single()
+        .ok()
+        .and_then(|e| winit_windows.get_window(e))
+    {
+        window.request_user_attention(Some(UserAttentionType::Informational));
+    }
+    if !settings.best_notifications {
+        return;
+    }
+    let title = branding.title.clone();
+    let body = format!("New best: {}!", score.0);
+    // Talking to the notification service can take a moment
+    std::thread::spawn(move || {
+        if let Err(err) = Notification::new().summary(&title).body(&body).show() {
+            error!("Cannot show the new best notification: {err}");
+        }
+    });
+}
@@ -0,0 +1,57 @@
+// Overlay mode (`--overlay`): a borderless, transparent, always-on-top window
+// without the city background, so the bird and pipes float over the desktop or
+// a stream scene.
+//
+// Window transparency can only be chosen when the window is created, hence
+// `configure_window`. Mouse clicks pass through the window while flying (unless
+// playing with the mouse-only controls); on the menu and game over screens it
+// takes clicks again so it can be focused.
+
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowLevel};
+
+use crate::controls::ControlPreset;
+use crate::settings::Settings;
+use crate::{BackgroundLayer, GameState};
+
+pub fn configure_window(window: &mut Window) {
+    window.transparent = true;
+    window.decorations = false;
+    window.window_level = WindowLevel::AlwaysOnTop;
+    // Transparency needs an alpha-aware compositor mode on macOS
+    #[cfg(target_os = "macos")]
+    {
+        window.composite_alpha_mode = bevy::window::CompositeAlphaMode::PostMultiplied;
+    }
+}
+
+pub struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClearColor(Color::NONE))
+            .add_systems(Update, (hide_background, update_click_through));
+    }
+}
+
+// The game respawns the background every run; drop it before it's ever drawn
+fn hide_background(mut commands: Commands, q: Query<Entity, Added<BackgroundLayer>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn update_click_through(
+    state: Res<State<GameState>>,
+    settings: Res<Settings>,
+    mut window_q: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !state.is_changed() && !settings.is_changed() {
+        return;
+    }
+    // Mouse-only players need their clicks even mid-run
+    let mouse_only = settings.controls == ControlPreset::MouseOnly;
+    if let Ok(mut window) = window_q.get_single_mut() {
+        window.cursor.hit_test = *state.get() != GameState::Playing || mouse_only;
+    }
+}
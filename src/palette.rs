@@ -0,0 +1,146 @@
+// Custom colors: the sky, pipe and UI text colors can be overridden from a
+// config file, so a mod can recolor the game without shipping textures. The
+// keys aren't in the settings file because that's the player's, in their data
+// folder; palette.txt is an asset instead, so an enabled mod's replaces the
+// game's like any other file. The game's own is a template with every key
+// commented out, which keeps the classic colors.
+//
+//   palette.txt   `key = value` lines, all optional, colors as hex
+//                 (#rrggbb, #rgb or with alpha):
+//                   sky = clear color, seen behind the city
+//                   pipes = pipe color
+//                   pipe_accent = pipe outline and stripe color
+//                   text = the UI's dark text (menus, prompts)
+//                   light_text = the UI's light text (score, HUD)
+//
+// These replace the classic colors only: the colorblind pipe palettes and high
+// contrast keep theirs, and pipe colors bought in the shop go over the file's
+// (see theme.rs). The file is read again when mods are switched on or off, and
+// with hot-reloading whenever it changes.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::mods::Mods;
+
+const PALETTE_PATH: &str = "palette.txt";
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        // Mods are found when ModsPlugin is built, before this
+        let palette = Palette::load(app.world().resource::<Mods>());
+        app.insert_resource(palette)
+            .add_systems(Update, apply_mod_palette);
+    }
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Debug, Default)]
+pub struct Palette {
+    pub sky: Option<Color>,
+    pub pipes: Option<Color>,
+    pub pipe_accent: Option<Color>,
+    pub text: Option<Color>,
+    pub light_text: Option<Color>,
+}
+
+impl Palette {
+    pub fn load(mods: &Mods) -> Self {
+        let path = palette_file(mods);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text),
+            Err(err) => {
+                warn!("Cannot read palette {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut palette = Self::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warn!("{PALETTE_PATH}: ignoring line \"{line}\"");
+                continue;
+            };
+            let color = match Srgba::hex(value.trim()) {
+                Ok(color) => Some(Color::from(color)),
+                Err(err) => {
+                    warn!("{PALETTE_PATH}: ignoring line \"{line}\": {err}");
+                    continue;
+                }
+            };
+            match key.trim() {
+                "sky" => palette.sky = color,
+                "pipes" => palette.pipes = color,
+                "pipe_accent" => palette.pipe_accent = color,
+                "text" => palette.text = color,
+                "light_text" => palette.light_text = color,
+                other => warn!("{PALETTE_PATH}: unknown key \"{other}\""),
+            }
+        }
+        palette
+    }
+}
+
+// The first enabled mod's, or the game's
+pub fn palette_file(mods: &Mods) -> PathBuf {
+    mods.resolve_file(PALETTE_PATH)
+}
+
+// The theme picks the new colors up from there
+fn apply_mod_palette(mods: Res<Mods>, mut palette: ResMut<Palette>) {
+    if !mods.is_changed() {
+        return;
+    }
+    let new_palette = Palette::load(&mods);
+    if *palette != new_palette {
+        *palette = new_palette;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_key() {
+        let palette = Palette::parse(
+            "# Dusk\n\
+             sky = #87ceeb\n\
+             pipes=f80\n\
+             pipe_accent = #FFFFFF\n\
+             text = #00000080\n\
+             light_text = #fff\n",
+        );
+        assert_eq!(
+            palette,
+            Palette {
+                sky: Some(Color::srgb_u8(0x87, 0xce, 0xeb)),
+                pipes: Some(Color::srgb_u8(0xff, 0x88, 0x00)),
+                pipe_accent: Some(Color::WHITE),
+                text: Some(Color::srgba_u8(0, 0, 0, 0x80)),
+                light_text: Some(Color::WHITE),
+            }
+        );
+    }
+
+    #[test]
+    fn bad_values_ignored() {
+        let palette = Palette::parse(
+            "sky\n\
+             sky = blue\n\
+             pipes = #12345\n\
+             pipe_accent =\n\
+             grass = #00ff00\n\
+             light_text = #ffffff # white\n",
+        );
+        assert_eq!(palette, Palette::default());
+    }
+}
@@ -0,0 +1,107 @@
+// Pausing a run: P (or Start, or the control preset's pause key) toggles it,
+// and other systems can pause through `PauseRequest`, e.g. when the controller
+// in use disconnects.
+//
+// Pausing stops virtual time, which freezes the fixed-step simulation and the
+// background scroll alike.
+
+use bevy::prelude::*;
+
+use crate::controls::GameInput;
+use crate::settings::Settings;
+use crate::{FlapInput, GameState, WINDOW_W};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PauseRequest>()
+            .add_systems(
+                Update,
+                (pause_input, apply_pause_requests)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(GameState::Playing), resume);
+    }
+}
+
+// Pause the current run, if there is one
+#[derive(Event)]
+pub struct PauseRequest;
+
+#[derive(Component)]
+struct PauseUI;
+
+fn pause_input(
+    controls: GameInput,
+    mut time: ResMut<Time<Virtual>>,
+    mut requests: EventWriter<PauseRequest>,
+    mut commands: Commands,
+    mut flap_input: ResMut<FlapInput>,
+    ui: Query<Entity, With<PauseUI>>,
+) {
+    if !controls.pause() {
+        return;
+    }
+    if time.is_paused() {
+        // Flaps queued up while paused (chat, mashed keys) shouldn't fire on resume
+        flap_input.requested = false;
+        time.unpause();
+        for e in &ui {
+            commands.entity(e).despawn_recursive();
+        }
+    } else {
+        requests.send(PauseRequest);
+    }
+}
+
+fn apply_pause_requests(
+    mut commands: Commands,
+    mut requests: EventReader<PauseRequest>,
+    mut time: ResMut<Time<Virtual>>,
+    settings: Res<Settings>,
+) {
+    if requests.read().count() == 0 || time.is_paused() {
+        return;
+    }
+    time.pause();
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "Paused\n",
+                TextStyle {
+                    font_size: 56.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+            TextSection::new(
+                format!("{} to resume", settings.controls.pause_hint()),
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(180.0),
+            left: Val::Px(WINDOW_W * 0.5 - 120.0),
+            ..default()
+        }),
+        PauseUI,
+    ));
+}
+
+fn resume(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    ui: Query<Entity, With<PauseUI>>,
+) {
+    time.unpause();
+    for e in &ui {
+        commands.entity(e).despawn_recursive();
+    }
+}
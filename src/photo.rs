@@ -0,0 +1,197 @@
+// Photo mode, from the game over screen (F): time stops where the bird crashed,
+// WASD pans the camera and the mouse wheel zooms, H hides the HUD, and F12 takes
+// the screenshot as usual. F or Escape goes back to the game over screen, with
+// the camera and HUD as they were.
+
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+use crate::screen_reader::Announce;
+use crate::{GameState, WINDOW_H, WINDOW_W};
+
+const PAN_SPEED: f32 = 300.0; // px / s at 1x zoom
+const ZOOM_STEP: f32 = 0.1; // per wheel notch
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 1.5;
+
+pub struct PhotoModePlugin;
+
+impl Plugin for PhotoModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                photo_mode_input,
+                (move_camera, toggle_hud).run_if(resource_exists::<PhotoMode>),
+            )
+                .chain()
+                .run_if(in_state(GameState::GameOver)),
+        )
+        .add_systems(OnExit(GameState::GameOver), leave_on_exit);
+    }
+}
+
+// Present while in photo mode; game over keys (retry, save replay) wait for it
+// to go, since WASD would otherwise trigger them
+#[derive(Resource, Default)]
+pub struct PhotoMode {
+    // UI hidden with H, and the visibility to give back
+    hidden: Vec<(Entity, Visibility)>,
+}
+
+#[derive(Component)]
+struct PhotoUI;
+
+// Top-level UI nodes; hiding one hides everything under it
+type UiRoot = (With<Node>, Without<Parent>);
+
+// Everything photo mode changes, to set up and put back
+#[derive(SystemParam)]
+struct PhotoScene<'w, 's> {
+    commands: Commands<'w, 's>,
+    time: ResMut<'w, Time<Virtual>>,
+    cameras: Query<
+        'w,
+        's,
+        (&'static mut Transform, &'static mut OrthographicProjection),
+        With<Camera2d>,
+    >,
+    ui: Query<'w, 's, Entity, With<PhotoUI>>,
+    announce: EventWriter<'w, Announce>,
+}
+
+impl PhotoScene<'_, '_> {
+    fn enter(&mut self) {
+        self.commands.init_resource::<PhotoMode>();
+        self.time.pause();
+        self.commands.spawn((
+            TextBundle::from_section(
+                "Photo mode: WASD to pan, wheel to zoom, H to hide HUD, F12 to save, F to leave",
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.0),
+                left: Val::Px(12.0),
+                ..default()
+            }),
+            PhotoUI,
+        ));
+        self.announce.send(Announce("Photo mode".to_string()));
+    }
+
+    fn leave(&mut self, photo: &PhotoMode) {
+        self.commands.remove_resource::<PhotoMode>();
+        self.time.unpause();
+        for &(e, visibility) in &photo.hidden {
+            if let Some(mut entity) = self.commands.get_entity(e) {
+                entity.try_insert(visibility);
+            }
+        }
+        for e in &self.ui {
+            self.commands.entity(e).despawn_recursive();
+        }
+        for (mut tf, mut projection) in &mut self.cameras {
+            recenter(&mut tf, &mut projection);
+        }
+    }
+}
+
+fn photo_mode_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    photo: Option<Res<PhotoMode>>,
+    mut scene: PhotoScene,
+) {
+    match photo {
+        Some(photo) if keys.any_just_pressed([KeyCode::KeyF, KeyCode::Escape]) => {
+            scene.leave(&photo)
+        }
+        None if keys.just_pressed(KeyCode::KeyF) => scene.enter(),
+        _ => {}
+    }
+}
+
+// Retrying or quitting straight out of photo mode
+fn leave_on_exit(photo: Option<Res<PhotoMode>>, mut scene: PhotoScene) {
+    if let Some(photo) = photo {
+        scene.leave(&photo);
+    }
+}
+
+// WASD pans, the mouse wheel zooms; shared with the replay viewer
+pub fn move_camera(
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut camera_q: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let Ok((mut tf, mut projection)) = camera_q.get_single_mut() else {
+        return;
+    };
+
+    for event in wheel.read() {
+        // Touchpads scroll in pixels, many at a time
+        let notches = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 40.0,
+        };
+        projection.scale =
+            (projection.scale * (1.0 - ZOOM_STEP * notches)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    let mut dir = Vec2::ZERO;
+    for (key, step) in [
+        (KeyCode::KeyW, Vec2::Y),
+        (KeyCode::KeyS, Vec2::NEG_Y),
+        (KeyCode::KeyA, Vec2::NEG_X),
+        (KeyCode::KeyD, Vec2::X),
+    ] {
+        if keys.pressed(key) {
+            dir += step;
+        }
+    }
+    // The same speed on screen at any zoom, and the way it looks in a mirrored
+    // or upside-down view
+    dir.x *= tf.scale.x.signum();
+    dir.y *= tf.scale.y.signum();
+    let pan = dir * PAN_SPEED * projection.scale * time.delta_seconds();
+    let limit = Vec2::new(WINDOW_W, WINDOW_H) * 0.5;
+    let pos = (tf.translation.truncate() + pan).clamp(-limit, limit);
+    tf.translation = pos.extend(tf.translation.z);
+}
+
+fn toggle_hud(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut photo: ResMut<PhotoMode>,
+    mut roots: Query<(Entity, &mut Visibility), UiRoot>,
+) {
+    if !keys.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+    if photo.hidden.is_empty() {
+        for (e, mut visibility) in &mut roots {
+            if *visibility != Visibility::Hidden {
+                photo.hidden.push((e, *visibility));
+                *visibility = Visibility::Hidden;
+            }
+        }
+    } else {
+        for (e, visibility) in photo.hidden.drain(..) {
+            if let Ok((_, mut current)) = roots.get_mut(e) {
+                *current = visibility;
+            }
+        }
+    }
+}
+
+// Back to the normal view of the playfield
+pub fn recenter(tf: &mut Transform, projection: &mut OrthographicProjection) {
+    tf.translation.x = 0.0;
+    tf.translation.y = 0.0;
+    projection.scale = 1.0;
+}
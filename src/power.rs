@@ -0,0 +1,67 @@
+// Low-power mode: only a run in a focused window updates flat out. Menus (with
+// the autopilot demo behind them) redraw at MENU_FPS, and an unfocused window at
+// UNFOCUSED_FPS, so idling on the title screen doesn't burn a core and the GPU.
+// Key presses and window events still wake the game straight away.
+//
+// Overlay and Twitch-plays sessions usually run without focus on purpose, so
+// for them an unfocused window runs at full speed too.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::winit::{UpdateMode, WinitSettings};
+
+use crate::GameState;
+
+const MENU_FPS: f64 = 30.0;
+const UNFOCUSED_FPS: f64 = 10.0;
+
+pub struct PowerPlugin {
+    // Someone may be watching or playing without focusing the window
+    pub background_play: bool,
+}
+
+impl Plugin for PowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WinitSettings {
+            focused_mode: idle_mode(MENU_FPS),
+            unfocused_mode: idle_mode(UNFOCUSED_FPS),
+        })
+        .insert_resource(BackgroundPlay(self.background_play))
+        .add_systems(Update, update_power_mode);
+    }
+}
+
+#[derive(Resource)]
+struct BackgroundPlay(bool);
+
+fn idle_mode(fps: f64) -> UpdateMode {
+    UpdateMode::reactive_low_power(Duration::from_secs_f64(1.0 / fps))
+}
+
+fn update_power_mode(
+    state: Res<State<GameState>>,
+    background_play: Res<BackgroundPlay>,
+    mut winit: ResMut<WinitSettings>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    winit.focused_mode = match state.get() {
+        GameState::Playing | GameState::Replay => UpdateMode::Continuous,
+        GameState::Menu
+        | GameState::GameOver
+        | GameState::Mods
+        | GameState::Settings
+        | GameState::ChallengeEntry
+        | GameState::Lobby
+        | GameState::Tournament
+        | GameState::HotSeat
+        | GameState::Presets
+        | GameState::Shop
+        | GameState::Story => idle_mode(MENU_FPS),
+    };
+    if background_play.0 {
+        winit.unfocused_mode = winit.focused_mode;
+    }
+}
@@ -0,0 +1,472 @@
+// Challenge presets: rule sets dropped into `challenges/`, one text file each,
+// so anyone can make up a challenge without touching code. Tab on the challenge
+// code screen lists them, and a number key plays one. Like a challenge code,
+// every run (retries included) is played with the preset until Escape on the
+// game over screen goes back to the menu, and each preset keeps its best.
+//
+//   challenges/<id>.txt    `key = value` lines, all optional:
+//                            name, author, description
+//                            gravity, gap, speed = multipliers on the normal
+//                              gravity, pipe gap and pipe speed
+//                            theme = day, night or ice
+//                            modifiers = comma-separated modifier names, as in
+//                              the settings file (wind, reverse, flock, ...)
+//                            hazards = on or off
+//
+// Presets change the tuning itself (see modifiers::Rules), which challenge codes
+// have no room for, so preset runs have no code. Replays store the tuning and
+// play back the same.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+
+use crate::challenge::ActiveChallenge;
+use crate::modifiers::{Modifier, Modifiers, Rules};
+use crate::photo::PhotoMode;
+use crate::replay::ReplayPlayback;
+use crate::{update_best_score, GameOverUI, GameState, RunRanking, Score, WINDOW_W};
+
+const CHALLENGES_DIR: &str = "challenges";
+const PRESET_EXTENSION: &str = "txt";
+// Number keys pick presets, so the screen shows at most nine
+const MAX_LISTED_PRESETS: usize = 9;
+// How far a preset can stretch the tuning, so every preset stays flyable
+const MULTIPLIER_RANGE: (f32, f32) = (0.25, 3.0);
+const GAP_RANGE: (f32, f32) = (0.5, 2.0);
+
+pub struct PresetsPlugin;
+
+impl Plugin for PresetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Presets::discover())
+            .insert_resource(PresetBests::load())
+            .add_systems(OnEnter(GameState::Presets), show_preset_select)
+            .add_systems(OnExit(GameState::Presets), despawn_preset_select)
+            .add_systems(
+                Update,
+                preset_select_input.run_if(in_state(GameState::Presets)),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                show_preset_result.after(update_best_score),
+            )
+            .add_systems(
+                Update,
+                leave_preset.run_if(
+                    in_state(GameState::GameOver).and_then(not(resource_exists::<PhotoMode>)),
+                ),
+            );
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChallengePreset {
+    // File name, without the extension
+    pub id: String,
+    pub name: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub modifiers: Modifiers,
+    pub rules: Rules,
+}
+
+impl ChallengePreset {
+    fn parse(id: &str, text: &str) -> Self {
+        let mut preset = Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            author: None,
+            description: None,
+            modifiers: Modifiers::NONE,
+            rules: Rules::default(),
+        };
+        let normal = Rules::default();
+        // "nan" and "inf" parse as floats too
+        let multiplier = |value: &str, (min, max): (f32, f32)| {
+            value
+                .parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .map(|v| v.clamp(min, max))
+        };
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warn!("{id}.{PRESET_EXTENSION}: ignoring line \"{line}\"");
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let bad_value = || warn!("{id}.{PRESET_EXTENSION}: bad value \"{value}\" for {key}");
+            match key {
+                "name" => preset.name = value.to_string(),
+                "author" => preset.author = Some(value.to_string()),
+                "description" => preset.description = Some(value.to_string()),
+                "gravity" => match multiplier(value, MULTIPLIER_RANGE) {
+                    Some(m) => preset.rules.gravity = normal.gravity * m,
+                    None => bad_value(),
+                },
+                "gap" => match multiplier(value, GAP_RANGE) {
+                    Some(m) => preset.rules.pipe_gap = normal.pipe_gap * m,
+                    None => bad_value(),
+                },
+                "speed" => match multiplier(value, MULTIPLIER_RANGE) {
+                    Some(m) => preset.rules.pipe_speed = normal.pipe_speed * m,
+                    None => bad_value(),
+                },
+                "theme" => match value {
+                    "day" => {}
+                    "night" => preset.modifiers = preset.modifiers.with(Modifier::Night),
+                    "ice" => preset.modifiers = preset.modifiers.with(Modifier::Ice),
+                    _ => bad_value(),
+                },
+                "modifiers" => {
+                    for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                        match Modifier::ALL.into_iter().find(|m| m.name() == name) {
+                            Some(modifier) => preset.modifiers = preset.modifiers.with(modifier),
+                            None => warn!("{id}.{PRESET_EXTENSION}: unknown modifier \"{name}\""),
+                        }
+                    }
+                }
+                "hazards" => match value {
+                    "on" => preset.rules.hazards = true,
+                    "off" => preset.rules.hazards = false,
+                    _ => bad_value(),
+                },
+                other => warn!("{id}.{PRESET_EXTENSION}: unknown key \"{other}\""),
+            }
+        }
+        preset
+    }
+
+    // "Gravity 1.50x, Gap 0.80x, No hazards, Night, Wind", or "Standard rules"
+    fn summary(&self) -> String {
+        let normal = Rules::default();
+        let mut parts = Vec::new();
+        for (label, value, base) in [
+            ("Gravity", self.rules.gravity, normal.gravity),
+            ("Gap", self.rules.pipe_gap, normal.pipe_gap),
+            ("Speed", self.rules.pipe_speed, normal.pipe_speed),
+        ] {
+            if value != base {
+                parts.push(format!("{label} {:.2}x", value / base));
+            }
+        }
+        if !self.rules.hazards {
+            parts.push("No hazards".to_string());
+        }
+        if !self.modifiers.is_empty() {
+            parts.push(self.modifiers.to_string());
+        }
+        if parts.is_empty() {
+            "Standard rules".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+// Presets found in challenges/ at startup, sorted by file name
+#[derive(Resource, Default)]
+struct Presets(Vec<ChallengePreset>);
+
+impl Presets {
+    fn discover() -> Self {
+        let mut presets: Vec<ChallengePreset> = std::fs::read_dir(challenges_root())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == PRESET_EXTENSION))
+            .filter_map(|path| {
+                let id = path.file_stem()?.to_string_lossy().into_owned();
+                let text = std::fs::read_to_string(&path).ok()?;
+                Some(ChallengePreset::parse(&id, &text))
+            })
+            .collect();
+        presets.sort_by(|a, b| a.id.cmp(&b.id));
+        for preset in &presets {
+            info!("Found challenge preset {} ({})", preset.name, preset.id);
+        }
+        Self(presets)
+    }
+}
+
+fn challenges_root() -> PathBuf {
+    // Next to mods/, where the game's files are
+    FileAssetReader::get_base_path().join(CHALLENGES_DIR)
+}
+
+// The preset being played, if any. Set from the preset screen, read by
+// start_game.
+#[derive(Resource, Default)]
+pub struct ActivePreset(pub Option<ChallengePreset>);
+
+// --------------------------------------------
+// Best scores per preset
+// --------------------------------------------
+
+#[derive(Resource, Default)]
+struct PresetBests(HashMap<String, u32>);
+
+impl PresetBests {
+    fn load() -> Self {
+        let text = std::fs::read_to_string(bests_path()).unwrap_or_default();
+        let bests = text
+            .lines()
+            .filter_map(|line| {
+                let (id, best) = line.split_once('=')?;
+                Some((id.trim().to_string(), best.trim().parse().ok()?))
+            })
+            .collect();
+        Self(bests)
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let path = bests_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut ids: Vec<_> = self.0.iter().collect();
+        ids.sort();
+        let text: String = ids
+            .into_iter()
+            .map(|(id, best)| format!("{id} = {best}\n"))
+            .collect();
+        std::fs::write(path, text)
+    }
+}
+
+fn bests_path() -> PathBuf {
+    crate::data_file("preset_bests.txt")
+}
+
+// --------------------------------------------
+// Preset select screen
+// --------------------------------------------
+
+#[derive(Component)]
+struct PresetSelectUI;
+
+fn show_preset_select(mut commands: Commands, presets: Res<Presets>, bests: Res<PresetBests>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "Challenge presets",
+            TextStyle {
+                font_size: 56.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(WINDOW_W * 0.5 - 210.0),
+            ..default()
+        }),
+        PresetSelectUI,
+    ));
+
+    let mut list = String::new();
+    if presets.0.is_empty() {
+        list.push_str(
+            "No presets installed.\nAdd them as files under challenges/ (see the README).\n",
+        );
+    }
+    for (i, preset) in presets.0.iter().take(MAX_LISTED_PRESETS).enumerate() {
+        let best = bests.0.get(&preset.id).copied().unwrap_or(0);
+        list.push_str(&format!("{}. {}", i + 1, preset.name));
+        if let Some(author) = &preset.author {
+            list.push_str(&format!(" by {author}"));
+        }
+        list.push_str(&format!(" - best {best}\n      {}\n", preset.summary()));
+        if let Some(description) = &preset.description {
+            list.push_str(&format!("      {description}\n"));
+        }
+    }
+    list.push_str("\nNumber keys to play, Esc to go back");
+    commands.spawn((
+        TextBundle::from_section(
+            list,
+            TextStyle {
+                font_size: 22.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(120.0),
+            left: Val::Px(80.0),
+            ..default()
+        }),
+        PresetSelectUI,
+    ));
+}
+
+fn despawn_preset_select(mut commands: Commands, q: Query<Entity, With<PresetSelectUI>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn preset_select_input(
+    input: Res<ButtonInput<KeyCode>>,
+    presets: Res<Presets>,
+    mut active: ResMut<ActivePreset>,
+    mut challenge: ResMut<ActiveChallenge>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::ChallengeEntry);
+        return;
+    }
+
+    const NUMBER_KEYS: [KeyCode; MAX_LISTED_PRESETS] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    let Some(index) = NUMBER_KEYS.iter().position(|&k| input.just_pressed(k)) else {
+        return;
+    };
+    let Some(preset) = presets.0.get(index) else {
+        return;
+    };
+    challenge.0 = None;
+    active.0 = Some(preset.clone());
+    next_state.set(GameState::Playing);
+}
+
+// --------------------------------------------
+// Game over
+// --------------------------------------------
+
+fn show_preset_result(
+    mut commands: Commands,
+    preset: Res<ActivePreset>,
+    score: Res<Score>,
+    ranking: RunRanking,
+    playback: Option<Res<ReplayPlayback>>,
+    mut bests: ResMut<PresetBests>,
+) {
+    let Some(preset) = preset.0.as_ref().filter(|_| playback.is_none()) else {
+        return;
+    };
+    let best = bests.0.entry(preset.id.clone()).or_default();
+    if ranking.ranked_under_own_rules() && score.0 > *best {
+        *best = score.0;
+    }
+    let best = *best;
+    if let Err(err) = bests.save() {
+        error!("Cannot save preset bests: {err}");
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            format!(
+                "{} ({}) - best {best}\nEsc to leave the preset",
+                preset.name,
+                preset.summary()
+            ),
+            TextStyle {
+                font_size: 22.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Where a challenge code's result goes
+            top: Val::Px(400.0),
+            left: Val::Px(WINDOW_W * 0.5 - 220.0),
+            ..default()
+        }),
+        GameOverUI,
+    ));
+}
+
+fn leave_preset(
+    input: Res<ButtonInput<KeyCode>>,
+    mut preset: ResMut<ActivePreset>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if preset.0.is_some() && input.just_pressed(KeyCode::Escape) {
+        preset.0 = None;
+        next_state.set(GameState::Menu);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_key() {
+        let preset = ChallengePreset::parse(
+            "windy",
+            "# A comment\n\
+             name = Windy Day\n\
+             author = Someone\n\
+             description = Gusts = trouble\n\
+             gravity = 1.5\n\
+             gap = 0.8\n\
+             speed = 2\n\
+             theme = ice\n\
+             modifiers = wind, reverse\n\
+             hazards = off\n",
+        );
+        let normal = Rules::default();
+        assert_eq!(preset.id, "windy");
+        assert_eq!(preset.name, "Windy Day");
+        assert_eq!(preset.author.as_deref(), Some("Someone"));
+        assert_eq!(preset.description.as_deref(), Some("Gusts = trouble"));
+        assert_eq!(preset.rules.gravity, normal.gravity * 1.5);
+        assert_eq!(preset.rules.pipe_gap, normal.pipe_gap * 0.8);
+        assert_eq!(preset.rules.pipe_speed, normal.pipe_speed * 2.0);
+        assert!(!preset.rules.hazards);
+        assert_eq!(
+            preset.modifiers,
+            Modifiers::NONE
+                .with(Modifier::Ice)
+                .with(Modifier::Wind)
+                .with(Modifier::Reverse)
+        );
+    }
+
+    #[test]
+    fn clamped() {
+        let preset = ChallengePreset::parse("extreme", "gravity = 100\ngap = 0\nspeed = -5");
+        let normal = Rules::default();
+        assert_eq!(preset.rules.gravity, normal.gravity * MULTIPLIER_RANGE.1);
+        assert_eq!(preset.rules.pipe_gap, normal.pipe_gap * GAP_RANGE.0);
+        assert_eq!(
+            preset.rules.pipe_speed,
+            normal.pipe_speed * MULTIPLIER_RANGE.0
+        );
+    }
+
+    #[test]
+    fn bad_values_ignored() {
+        let preset = ChallengePreset::parse(
+            "junk",
+            "gravity = heavy\n\
+             gap = NaN\n\
+             speed = inf\n\
+             modifiers = wind, bogus\n\
+             hazards = maybe\n",
+        );
+        assert_eq!(preset.rules, Rules::default());
+        assert_eq!(preset.modifiers, Modifiers::NONE.with(Modifier::Wind));
+    }
+}
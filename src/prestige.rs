@@ -0,0 +1,277 @@
+// Prestige: once a ranked run reaches PRESTIGE_SCORE, Y on the main menu
+// (twice, to be sure) starts progression over for a permanent badge. Coins go
+// back to zero, the modes unlocked by achievements lock again along with the
+// achievements themselves, and the best score resets; bought cosmetics stay.
+//
+// Each prestige adds a level to the badge, shown on the menu and after the
+// name of a LAN race the player hosts, and widens a golden aura around the
+// bird. Saved in prestige.txt next to the settings.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+
+use crate::achievements::UnlockedAchievements;
+use crate::modifiers::Modifiers;
+use crate::settings::{self, AccessibilitySettings, Settings};
+use crate::toast::ShowToast;
+use crate::wallet::{self, Wallet};
+use crate::{
+    update_best_score, BestScore, Bird, GameState, LeadBird, MenuUI, RunRanking, Score, BIRD_SIZE,
+    WINDOW_W,
+};
+
+// A ranked run has to reach this to unlock the next prestige
+const PRESTIGE_SCORE: u32 = 100;
+// Seconds to press Y a second time
+const CONFIRM_SECS: f32 = 3.0;
+const BADGE_COLOR: Color = Color::srgb(0.85, 0.65, 0.1);
+const AURA_COLOR: Color = Color::srgba(1.0, 0.85, 0.3, 0.3);
+// The aura stops growing after this many levels
+const MAX_AURA_LEVEL: u32 = 5;
+
+pub struct PrestigePlugin;
+
+impl Plugin for PrestigePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Prestige::load())
+            .init_resource::<AuraAssets>()
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                unlock_prestige.after(update_best_score),
+            )
+            .add_systems(OnEnter(GameState::Menu), show_badge)
+            .add_systems(
+                Update,
+                (prestige_input, update_badge)
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
+            )
+            .add_systems(Update, wear_aura);
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Prestige {
+    pub level: u32,
+    // A ranked run has reached PRESTIGE_SCORE since the last prestige
+    pub eligible: bool,
+}
+
+impl Prestige {
+    // "Prestige 2", or None before the first
+    pub fn badge(&self) -> Option<String> {
+        (self.level > 0).then(|| format!("Prestige {}", self.level))
+    }
+
+    fn to_text(&self) -> String {
+        format!("level = {}\neligible = {}\n", self.level, self.eligible)
+    }
+
+    fn load() -> Self {
+        let mut prestige = Self::default();
+        let text = std::fs::read_to_string(prestige_path()).unwrap_or_default();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match key.trim() {
+                "level" => prestige.level = value.trim().parse().unwrap_or(0),
+                "eligible" => prestige.eligible = value.trim() == "true",
+                _ => {}
+            }
+        }
+        prestige
+    }
+
+    fn save(&self) {
+        let path = prestige_path();
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, self.to_text()));
+        if let Err(err) = result {
+            error!("Cannot save prestige: {err}");
+        }
+    }
+}
+
+fn prestige_path() -> PathBuf {
+    crate::data_file("prestige.txt")
+}
+
+fn unlock_prestige(
+    score: Res<Score>,
+    ranking: RunRanking,
+    mut prestige: ResMut<Prestige>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if prestige.eligible || !ranking.ranked() || score.0 < PRESTIGE_SCORE {
+        return;
+    }
+    prestige.eligible = true;
+    prestige.save();
+    toasts.send(ShowToast(
+        "Prestige unlocked! Press Y on the menu to start over for a badge".to_string(),
+    ));
+}
+
+// --------------------------------------------
+// Menu
+// --------------------------------------------
+
+#[derive(Component)]
+struct BadgeText;
+
+fn badge_text(prestige: &Prestige) -> String {
+    match (prestige.badge(), prestige.eligible) {
+        (Some(badge), true) => format!("{badge} - Y to prestige again"),
+        (Some(badge), false) => badge,
+        (None, true) => "Y to prestige".to_string(),
+        (None, false) => String::new(),
+    }
+}
+
+fn show_badge(mut commands: Commands, prestige: Res<Prestige>) {
+    commands.spawn((
+        TextBundle::from_section(
+            badge_text(&prestige),
+            TextStyle {
+                font_size: 24.0,
+                color: BADGE_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Above the title
+            top: Val::Px(90.0),
+            left: Val::Px(WINDOW_W * 0.5 - 160.0),
+            ..default()
+        }),
+        BadgeText,
+        MenuUI,
+    ));
+}
+
+fn update_badge(prestige: Res<Prestige>, mut q: Query<&mut Text, With<BadgeText>>) {
+    if !prestige.is_changed() {
+        return;
+    }
+    for mut text in &mut q {
+        text.sections[0].value = badge_text(&prestige);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prestige_input(
+    input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time<Real>>,
+    mut prestige: ResMut<Prestige>,
+    mut wallet: ResMut<Wallet>,
+    mut settings: ResMut<Settings>,
+    accessibility: Res<AccessibilitySettings>,
+    mut achievements: ResMut<UnlockedAchievements>,
+    mut best: ResMut<BestScore>,
+    mut toasts: EventWriter<ShowToast>,
+    // When Y was first pressed, in real seconds since startup
+    mut asked_at: Local<Option<f32>>,
+) {
+    if !input.just_pressed(KeyCode::KeyY) || !prestige.eligible {
+        return;
+    }
+    let now = time.elapsed_seconds();
+    if !asked_at.is_some_and(|at| now - at < CONFIRM_SECS) {
+        *asked_at = Some(now);
+        toasts.send(ShowToast(
+            "Press Y again to prestige: coins, unlocked modes and your best start over".to_string(),
+        ));
+        return;
+    }
+    *asked_at = None;
+
+    wallet.coins = 0;
+    wallet::save(&wallet);
+    settings.unlocked = Modifiers::NONE;
+    if settings
+        .mode
+        .is_some_and(|mode| !settings.mode_unlocked(mode))
+    {
+        settings.mode = None;
+    }
+    if let Err(err) = settings::save(&settings, &accessibility) {
+        error!("Cannot save settings: {err}");
+    }
+    achievements.0.clear();
+    best.0 = 0;
+    prestige.level += 1;
+    prestige.eligible = false;
+    prestige.save();
+    toasts.send(ShowToast(format!(
+        "Prestige {}! Your badge is on the menu",
+        prestige.level
+    )));
+}
+
+// --------------------------------------------
+// Aura
+// --------------------------------------------
+
+#[derive(Component)]
+struct Aura;
+
+// One mesh and material for the aura, like the coins' CoinAssets
+#[derive(Resource)]
+struct AuraAssets {
+    circle: Mesh2dHandle,
+    material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for AuraAssets {
+    fn from_world(world: &mut World) -> Self {
+        let circle = world.resource_mut::<Assets<Mesh>>().add(Circle::new(1.0));
+        Self {
+            circle: Mesh2dHandle(circle),
+            material: world
+                .resource_mut::<Assets<ColorMaterial>>()
+                .add(AURA_COLOR),
+        }
+    }
+}
+
+// A glow behind the lead bird, a little wider with each level
+fn wear_aura(
+    mut commands: Commands,
+    prestige: Res<Prestige>,
+    assets: Res<AuraAssets>,
+    birds: Query<(Entity, Ref<Bird>), LeadBird>,
+    auras: Query<(Entity, &Parent), With<Aura>>,
+) {
+    for (e, bird) in &birds {
+        if !prestige.is_changed() && !bird.is_added() {
+            continue;
+        }
+        for (aura, parent) in &auras {
+            if parent.get() == e {
+                commands.entity(aura).despawn_recursive();
+            }
+        }
+        if prestige.level == 0 {
+            continue;
+        }
+        let radius = BIRD_SIZE.x * 0.5 + 4.0 + 3.0 * prestige.level.min(MAX_AURA_LEVEL) as f32;
+        commands.entity(e).with_children(|parent| {
+            parent.spawn((
+                MaterialMesh2dBundle {
+                    mesh: assets.circle.clone(),
+                    material: assets.material.clone(),
+                    transform: Transform::from_xyz(0.0, 0.0, -0.1)
+                        .with_scale(Vec3::new(radius, radius, 1.0)),
+                    ..default()
+                },
+                Aura,
+            ));
+        });
+    }
+}
@@ -0,0 +1,602 @@
+// Ghost race (launch with `--race-host <port>` on one machine and
+// `--race-join <host>:<port>` on the other, or find each other on the LAN
+// through the lobby, see lobby.rs)
+//
+// The two games talk over UDP, one short line of text per datagram:
+//
+//   FBRACE1 hello                            every second, to keep the link up
+//   FBRACE1 ready <0|1>                      ready to start, in the lobby
+//   FBRACE1 start <challenge code>           a run started on this course
+//   FBRACE1 pos <seed> <tick> <x> <y> <angle>  every fixed tick of a run
+//   FBRACE1 end <seed> <tick>                the run ended on this tick
+//
+// Starting a run from the menu or the game over screen starts the rival's on
+// the same challenge code, and the rival's bird flies alongside as a ghost; the
+// two birds never collide. Whoever's run ends on the earlier tick loses. The
+// ghost is placed by the tick its position was sent on, so it sits as far
+// ahead or behind as the rival really is.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::str::{FromStr, SplitWhitespace};
+
+use bevy::prelude::*;
+
+use crate::atlas::SpriteAtlas;
+use crate::challenge::{run_code, ActiveChallenge, ChallengeCode};
+use crate::cli::RaceRole;
+use crate::settings::Settings;
+use crate::toast::ShowToast;
+use crate::{
+    check_collisions_and_scoring, load_assets, start_game, Course, FixedTransform, GameState,
+    LeadBird, RunModifiers, RunRules, RunSeed, RunTick, UnrecordedCourse, FIXED_HZ,
+};
+
+const MAGIC: &str = "FBRACE1";
+const HELLO_SECS: f32 = 1.0;
+// No word from the rival for this long and the link counts as lost
+const TIMEOUT_SECS: f32 = 5.0;
+const GHOST_COLOR: Color = Color::srgba(0.55, 0.75, 1.0, 0.45);
+
+pub struct RacePlugin {
+    // Race straight away, from the command line
+    pub role: Option<RaceRole>,
+}
+
+impl Plugin for RacePlugin {
+    fn build(&self, app: &mut App) {
+        let link = match &self.role {
+            Some(RaceRole::Host(port)) => Some(RaceLink::host(*port)),
+            Some(RaceRole::Join(address)) => Some(RaceLink::join(address)),
+            None => None,
+        };
+        match link {
+            Some(Ok(link)) => {
+                info!("{}", link.waiting);
+                app.insert_resource(link);
+            }
+            Some(Err(err)) => error!("Cannot start the race: {err}"),
+            None => {}
+        }
+
+        let linked = resource_exists::<RaceLink>;
+        app.insert_resource(HelloTimer(Timer::from_seconds(
+            HELLO_SECS,
+            TimerMode::Repeating,
+        )))
+        .init_resource::<Race>()
+        .add_systems(Startup, (spawn_ghost.after(load_assets), spawn_race_status))
+        .add_systems(
+            OnEnter(GameState::Playing),
+            start_race.after(start_game).run_if(linked),
+        )
+        .add_systems(
+            OnExit(GameState::Playing),
+            (end_race.run_if(linked), hide_ghost),
+        )
+        .add_systems(
+            FixedUpdate,
+            (send_position, place_ghost)
+                .chain()
+                .after(check_collisions_and_scoring)
+                .run_if(in_state(GameState::Playing).and_then(linked)),
+        )
+        .add_systems(
+            Update,
+            (
+                (receive_messages, say_hello, decide_race).run_if(linked),
+                update_race_status,
+            )
+                .chain(),
+        );
+    }
+}
+
+// The connection to the rival, while racing
+#[derive(Resource)]
+pub struct RaceLink {
+    socket: UdpSocket,
+    // The host's address when joining; when hosting, whoever said hello first
+    peer: Option<SocketAddr>,
+    hosting: bool,
+    // Status shown until the rival is heard from
+    waiting: String,
+    // Real seconds since startup the rival was last heard from
+    last_heard: Option<f32>,
+    // Lost touch after hearing from the rival (and told the player)
+    lost: bool,
+    // Both players said they're ready in the lobby
+    ready: bool,
+    rival_ready: bool,
+}
+
+impl RaceLink {
+    // Wait for a rival on this UDP port (0 for any free one)
+    pub fn host(port: u16) -> io::Result<Self> {
+        let socket = open_socket(SocketAddr::from(([0, 0, 0, 0], port)))?;
+        let port = socket.local_addr()?.port();
+        Ok(Self::new(
+            socket,
+            None,
+            format!("Race: waiting for a rival on port {port}"),
+        ))
+    }
+
+    // Race whoever is hosting at this address (host:port)
+    pub fn join(address: &str) -> io::Result<Self> {
+        let host = address.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("no address for {address}"))
+        })?;
+        let any = if host.is_ipv6() {
+            SocketAddr::from(([0u16; 8], 0))
+        } else {
+            SocketAddr::from(([0, 0, 0, 0], 0))
+        };
+        Ok(Self::new(
+            open_socket(any)?,
+            Some(host),
+            format!("Race: calling {address}"),
+        ))
+    }
+
+    fn new(socket: UdpSocket, host: Option<SocketAddr>, waiting: String) -> Self {
+        Self {
+            socket,
+            peer: host,
+            hosting: host.is_none(),
+            waiting,
+            last_heard: None,
+            lost: false,
+            ready: false,
+            rival_ready: false,
+        }
+    }
+
+    pub fn hosting(&self) -> bool {
+        self.hosting
+    }
+
+    // The port a host is waiting on
+    pub fn port(&self) -> Option<u16> {
+        self.socket.local_addr().ok().map(|addr| addr.port())
+    }
+
+    // Heard from the rival lately
+    pub fn connected(&self) -> bool {
+        self.last_heard.is_some() && !self.lost
+    }
+
+    pub fn ready(&self) -> bool {
+        self.ready
+    }
+
+    pub fn rival_ready(&self) -> bool {
+        self.rival_ready
+    }
+
+    pub fn set_ready(&mut self, ready: bool) {
+        self.ready = ready;
+        self.send(&RaceMessage::Ready(ready));
+    }
+
+    fn send(&self, message: &RaceMessage) {
+        if let Some(peer) = self.peer {
+            // A lost datagram is soon made up for: positions go every tick, and
+            // the end of a run is repeated with every hello
+            let _ = self.socket.send_to(message.to_text().as_bytes(), peer);
+        }
+    }
+}
+
+fn open_socket(addr: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(addr)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+#[derive(Resource)]
+struct HelloTimer(Timer);
+
+// Our side of the latest race, and what we know of the rival's
+#[derive(Resource, Default)]
+struct Race {
+    // Our run's seed, the last tick it played and the one it ended on
+    seed: Option<u64>,
+    tick: u32,
+    ended_at: Option<u32>,
+    rival: Option<Rival>,
+    // Started by the rival's start message, so not to be echoed back
+    started_by_rival: bool,
+    decided: bool,
+}
+
+#[derive(Clone, Copy)]
+struct Rival {
+    seed: u64,
+    tick: u32,
+    pos: Vec2,
+    angle: f32,
+    ended_at: Option<u32>,
+}
+
+impl Race {
+    fn rival_moved(&mut self, seed: u64, tick: u32, pos: Vec2, angle: f32) {
+        match &mut self.rival {
+            // Datagrams can arrive out of order
+            Some(rival) if rival.seed == seed => {
+                if tick > rival.tick {
+                    rival.tick = tick;
+                    rival.pos = pos;
+                    rival.angle = angle;
+                }
+            }
+            _ => {
+                self.rival = Some(Rival {
+                    seed,
+                    tick,
+                    pos,
+                    angle,
+                    ended_at: None,
+                })
+            }
+        }
+    }
+
+    fn rival_ended(&mut self, seed: u64, tick: u32) {
+        match &mut self.rival {
+            Some(rival) if rival.seed == seed => rival.ended_at = Some(tick),
+            _ => {
+                self.rival = Some(Rival {
+                    seed,
+                    tick,
+                    pos: Vec2::ZERO,
+                    angle: 0.0,
+                    ended_at: Some(tick),
+                })
+            }
+        }
+    }
+}
+
+enum RaceMessage {
+    Hello,
+    Ready(bool),
+    Start(ChallengeCode),
+    Position {
+        seed: u64,
+        tick: u32,
+        pos: Vec2,
+        angle: f32,
+    },
+    End {
+        seed: u64,
+        tick: u32,
+    },
+}
+
+impl RaceMessage {
+    fn to_text(&self) -> String {
+        match self {
+            RaceMessage::Hello => format!("{MAGIC} hello"),
+            RaceMessage::Ready(ready) => format!("{MAGIC} ready {}", u8::from(*ready)),
+            RaceMessage::Start(code) => format!("{MAGIC} start {code}"),
+            RaceMessage::Position {
+                seed,
+                tick,
+                pos,
+                angle,
+            } => format!("{MAGIC} pos {seed} {tick} {} {} {angle}", pos.x, pos.y),
+            RaceMessage::End { seed, tick } => format!("{MAGIC} end {seed} {tick}"),
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        if words.next()? != MAGIC {
+            return None;
+        }
+        let message = match words.next()? {
+            "hello" => RaceMessage::Hello,
+            "ready" => RaceMessage::Ready(next::<u8>(&mut words)? != 0),
+            "start" => RaceMessage::Start(ChallengeCode::parse(words.next()?)?),
+            "pos" => RaceMessage::Position {
+                seed: next(&mut words)?,
+                tick: next(&mut words)?,
+                pos: Vec2::new(next(&mut words)?, next(&mut words)?),
+                angle: next(&mut words)?,
+            },
+            "end" => RaceMessage::End {
+                seed: next(&mut words)?,
+                tick: next(&mut words)?,
+            },
+            _ => return None,
+        };
+        Some(message)
+    }
+}
+
+fn next<T: FromStr>(words: &mut SplitWhitespace) -> Option<T> {
+    words.next()?.parse().ok()
+}
+
+// The rival's bird, drawn see-through
+#[derive(Component)]
+struct RaceGhost;
+
+#[derive(Component)]
+struct RaceStatus;
+
+fn spawn_ghost(mut commands: Commands, atlas: Res<SpriteAtlas>) {
+    let frames = atlas.frames("bird");
+    // Just under the bird
+    let transform = Transform::from_xyz(0.0, 0.0, 0.98);
+    commands.spawn((
+        SpriteBundle {
+            texture: atlas.texture.clone(),
+            sprite: Sprite {
+                color: GHOST_COLOR,
+                ..default()
+            },
+            transform,
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        TextureAtlas {
+            layout: atlas.layout.clone(),
+            index: frames.get(1).copied().unwrap_or_default(),
+        },
+        FixedTransform::new(transform),
+        RaceGhost,
+    ));
+}
+
+fn spawn_race_status(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        RaceStatus,
+    ));
+}
+
+// Tell the rival about a run we started, so theirs starts on the same course
+#[allow(clippy::too_many_arguments)]
+fn start_race(
+    mut link: ResMut<RaceLink>,
+    mut race: ResMut<Race>,
+    run_seed: Res<RunSeed>,
+    challenge: Res<ActiveChallenge>,
+    settings: Res<Settings>,
+    run_modifiers: Res<RunModifiers>,
+    run_rules: Res<RunRules>,
+    course: UnrecordedCourse,
+) {
+    race.seed = Some(run_seed.0);
+    race.tick = 0;
+    race.ended_at = None;
+    race.decided = false;
+    link.ready = false;
+    link.rival_ready = false;
+    if std::mem::take(&mut race.started_by_rival) {
+        return;
+    }
+    let code = run_code(
+        &challenge,
+        &run_seed,
+        &settings,
+        &run_modifiers,
+        &run_rules,
+        &course,
+    );
+    if let Some(code) = code {
+        link.send(&RaceMessage::Start(code));
+    }
+}
+
+// A crash, a finished story level or quitting all end the run
+fn end_race(link: Res<RaceLink>, mut race: ResMut<Race>) {
+    let Some(seed) = race.seed else {
+        return;
+    };
+    race.ended_at = Some(race.tick);
+    link.send(&RaceMessage::End {
+        seed,
+        tick: race.tick,
+    });
+}
+
+fn send_position(
+    link: Res<RaceLink>,
+    mut race: ResMut<Race>,
+    run_tick: Res<RunTick>,
+    bird_q: Query<&Transform, LeadBird>,
+) {
+    let (Some(seed), Ok(tf)) = (race.seed, bird_q.get_single()) else {
+        return;
+    };
+    race.tick = run_tick.0;
+    link.send(&RaceMessage::Position {
+        seed,
+        tick: run_tick.0,
+        pos: tf.translation.truncate(),
+        angle: tf.rotation.to_euler(EulerRot::ZYX).0,
+    });
+}
+
+// The rival's bird, while it flies the same course as ours, shifted by how many
+// ticks of scrolling it is ahead or behind
+fn place_ghost(
+    race: Res<Race>,
+    run_tick: Res<RunTick>,
+    course: Course,
+    mut ghost: Query<(&mut Transform, &mut Visibility, &mut Sprite), With<RaceGhost>>,
+) {
+    let Ok((mut tf, mut visibility, mut sprite)) = ghost.get_single_mut() else {
+        return;
+    };
+    let rival = race
+        .rival
+        .filter(|rival| Some(rival.seed) == race.seed && rival.ended_at.is_none());
+    let Some(rival) = rival else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    let facing = course.facing();
+    let per_tick = course.pipe_speed().abs() / FIXED_HZ as f32;
+    let ahead = (rival.tick as f32 - run_tick.0 as f32) * per_tick * facing;
+    tf.translation.x = rival.pos.x + ahead;
+    tf.translation.y = rival.pos.y;
+    tf.rotation = Quat::from_rotation_z(rival.angle);
+    sprite.flip_x = facing < 0.0;
+    *visibility = Visibility::Inherited;
+}
+
+fn hide_ghost(mut ghost: Query<&mut Visibility, With<RaceGhost>>) {
+    for mut visibility in &mut ghost {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn receive_messages(
+    time: Res<Time<Real>>,
+    state: Res<State<GameState>>,
+    mut link: ResMut<RaceLink>,
+    mut race: ResMut<Race>,
+    mut challenge: ResMut<ActiveChallenge>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+    course: UnrecordedCourse,
+) {
+    let now = time.elapsed_seconds();
+    let mut buf = [0; 256];
+    // Until there's nothing left to read (or an error from a rival that went
+    // away, which only says so once)
+    while let Ok((len, from)) = link.socket.recv_from(&mut buf) {
+        let Some(message) = std::str::from_utf8(&buf[..len])
+            .ok()
+            .and_then(RaceMessage::parse)
+        else {
+            continue;
+        };
+        // A host takes on whoever calls, unless it already has a rival it can
+        // still hear
+        if link.hosting && (link.peer.is_none() || link.lost) {
+            link.peer = Some(from);
+        }
+        if link.peer != Some(from) {
+            continue;
+        }
+        if link.last_heard.is_none() || link.lost {
+            toasts.send(ShowToast("Rival connected".to_string()));
+        }
+        link.last_heard = Some(now);
+        link.lost = false;
+
+        match message {
+            RaceMessage::Hello => {}
+            RaceMessage::Ready(ready) => link.rival_ready = ready,
+            RaceMessage::Start(code) => {
+                // Not in the middle of a run of our own, or a menu
+                let waiting = matches!(
+                    state.get(),
+                    GameState::Menu | GameState::GameOver | GameState::Lobby
+                );
+                if waiting {
+                    // The code can't carry what else shapes our course
+                    if course.reason().is_some() {
+                        toasts.send(ShowToast(
+                            "Can't race while a mod or script changes the course".to_string(),
+                        ));
+                        continue;
+                    }
+                    challenge.0 = Some(code);
+                    race.started_by_rival = true;
+                    next_state.set(GameState::Playing);
+                }
+            }
+            RaceMessage::Position {
+                seed,
+                tick,
+                pos,
+                angle,
+            } => race.rival_moved(seed, tick, pos, angle),
+            RaceMessage::End { seed, tick } => race.rival_ended(seed, tick),
+        }
+    }
+
+    let silent = link
+        .last_heard
+        .is_some_and(|heard| now - heard > TIMEOUT_SECS);
+    if silent && !link.lost {
+        link.lost = true;
+        link.rival_ready = false;
+        toasts.send(ShowToast("Lost touch with your rival".to_string()));
+    }
+}
+
+fn say_hello(
+    time: Res<Time<Real>>,
+    mut timer: ResMut<HelloTimer>,
+    link: Res<RaceLink>,
+    race: Res<Race>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    link.send(&RaceMessage::Hello);
+    if let (Some(seed), Some(tick)) = (race.seed, race.ended_at) {
+        link.send(&RaceMessage::End { seed, tick });
+    }
+}
+
+// First one out loses, as soon as the other has flown past the tick it went out
+// on
+fn decide_race(mut race: ResMut<Race>, mut toasts: EventWriter<ShowToast>) {
+    if race.decided {
+        return;
+    }
+    let Some(rival) = race.rival.filter(|rival| Some(rival.seed) == race.seed) else {
+        return;
+    };
+    let ours = race.ended_at.unwrap_or(race.tick);
+    let theirs = rival.ended_at.unwrap_or(rival.tick);
+    let message = match (race.ended_at, rival.ended_at) {
+        (Some(a), Some(b)) if a == b => "Dead heat!",
+        (_, Some(b)) if ours > b => "Your rival is out - you win the race!",
+        (Some(a), _) if theirs > a => "You're out first - your rival wins the race",
+        _ => return,
+    };
+    race.decided = true;
+    toasts.send(ShowToast(message.to_string()));
+}
+
+// Nothing while there's no race
+fn update_race_status(link: Option<Res<RaceLink>>, mut q: Query<&mut Text, With<RaceStatus>>) {
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    let status = match link.as_deref() {
+        None => "",
+        Some(link) => match (link.last_heard, link.lost) {
+            (None, _) => &link.waiting,
+            (Some(_), false) => "Race: rival connected",
+            (Some(_), true) => "Race: rival lost",
+        },
+    };
+    // The link changes with every datagram; the text rarely does
+    if text.sections[0].value != status {
+        text.sections[0].value = status.to_string();
+    }
+}
@@ -0,0 +1,812 @@
+// Replays: record a run's seed, flap ticks and laser shots, save them from the
+// game over screen, and play them back from the menu.
+//
+// Runs are deterministic given the seed and the fixed tick of every flap and
+// shot, so that's all a replay stores, along with the physics constants, hitbox
+// setting and modifiers it was recorded under and the final score to verify
+// playback against.
+//
+// File layout (little-endian, varints are LEB128):
+//   "FBRP" magic, u8 version, u64 seed, 8 x f32 config, varint modifier bits,
+//   varint streak at the start, u8 flags (1 hazards, 2 mutator roulette),
+//   varint final score, varint flap count, then each flap tick as a varint
+//   delta from the previous one, then the shot ticks the same way
+//
+// A change to the layout, or to how a run plays out from the same inputs, needs
+// a new version; files of other versions are refused rather than misplayed.
+//
+// The gravity, pipe gap and pipe speed in the config are the ones the run was
+// played with, which a challenge preset may have changed, so playback uses them
+// (see `Replay::rules`).
+//
+// replay_code.rs packs the same data into a shorter text code for sharing.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::cheats::RunCheats;
+use crate::laser::{fire_laser, LaserFired, LaserInput};
+use crate::modifiers::{Modifiers, Rules};
+use crate::mods::Mods;
+use crate::photo::PhotoMode;
+use crate::rewind::Rewind;
+use crate::streak::Streak;
+use crate::toast::ShowToast;
+use crate::{
+    advance_run_tick, handle_flap_input, FlapInput, Flapped, GameOverUI, GameState, NextRunSeed,
+    RunHitbox, RunModifiers, RunRules, RunSeed, RunTick, Score, UnrecordedCourse, FIXED_HZ,
+    FLAP_VELOCITY, GRAVITY, MAX_FALL_SPEED, PIPE_GAP, PIPE_SPAWN_INTERVAL, PIPE_SPEED, WINDOW_W,
+};
+
+const REPLAY_MAGIC: &[u8; 4] = b"FBRP";
+const REPLAY_VERSION: u8 = 1;
+// Flags byte
+const FLAG_HAZARDS: u8 = 1;
+const FLAG_ROULETTE: u8 = 2;
+const REPLAY_EXTENSION: &str = "fbr";
+// Bundled replay for the menu's attract mode, under assets/
+const DEMO_REPLAY: &str = "demo.fbr";
+
+pub struct ReplayPlugin {
+    // Replay file to start watching as soon as the game launches
+    pub open_on_start: Option<PathBuf>,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(path) = &self.open_on_start {
+            app.insert_resource(OpenOnStart(path.clone()))
+                .add_systems(Startup, open_replay_on_start);
+        }
+        app.init_resource::<ReplayRecorder>()
+            .add_systems(OnEnter(GameState::Playing), reset_recorder)
+            // Playback and recording both see the tick after it's advanced
+            .add_systems(
+                FixedUpdate,
+                (
+                    play_back_flaps
+                        .after(advance_run_tick)
+                        .before(handle_flap_input)
+                        .run_if(in_state(GameState::Replay)),
+                    record_flaps
+                        .after(handle_flap_input)
+                        .run_if(in_state(GameState::Playing)),
+                    play_back_shots
+                        .after(advance_run_tick)
+                        .before(fire_laser)
+                        .run_if(in_state(GameState::Replay)),
+                    record_shots
+                        .after(fire_laser)
+                        .run_if(in_state(GameState::Playing)),
+                ),
+            )
+            .add_systems(OnEnter(GameState::Replay), show_replay_banner)
+            .add_systems(OnEnter(GameState::GameOver), show_replay_result)
+            .add_systems(OnExit(GameState::GameOver), stop_playback)
+            // Left the viewer before the replay ended
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Replay,
+                    entered: GameState::Menu,
+                },
+                stop_playback,
+            )
+            .add_systems(Update, load_replay_input.run_if(in_state(GameState::Menu)))
+            .add_systems(
+                Update,
+                save_replay_input.run_if(
+                    in_state(GameState::GameOver).and_then(not(resource_exists::<PhotoMode>)),
+                ),
+            );
+    }
+}
+
+// --------------------------------------------
+// Format
+// --------------------------------------------
+
+// The tuning a replay was recorded with; playback only matches if these do.
+// The hitbox shrink is a player setting, so playback uses the recorded one.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ReplayConfig {
+    pub fixed_hz: f32,
+    pub gravity: f32,
+    pub flap_velocity: f32,
+    pub max_fall_speed: f32,
+    pub pipe_speed: f32,
+    pub pipe_gap: f32,
+    pub pipe_spawn_interval: f32,
+    pub hitbox_shrink: f32,
+}
+
+impl ReplayConfig {
+    pub fn current(hitbox_shrink: f32) -> Self {
+        Self {
+            fixed_hz: FIXED_HZ as f32,
+            gravity: GRAVITY,
+            flap_velocity: FLAP_VELOCITY,
+            max_fall_speed: MAX_FALL_SPEED,
+            pipe_speed: PIPE_SPEED,
+            pipe_gap: PIPE_GAP,
+            pipe_spawn_interval: PIPE_SPAWN_INTERVAL,
+            hitbox_shrink,
+        }
+    }
+
+    // With a preset's gravity, pipe gap and speed
+    pub fn with_rules(self, rules: Rules) -> Self {
+        Self {
+            gravity: rules.gravity,
+            pipe_gap: rules.pipe_gap,
+            pipe_speed: rules.pipe_speed,
+            ..self
+        }
+    }
+
+    pub fn to_array(self) -> [f32; 8] {
+        [
+            self.fixed_hz,
+            self.gravity,
+            self.flap_velocity,
+            self.max_fall_speed,
+            self.pipe_speed,
+            self.pipe_gap,
+            self.pipe_spawn_interval,
+            self.hitbox_shrink,
+        ]
+    }
+
+    pub fn from_array(a: [f32; 8]) -> Self {
+        Self {
+            fixed_hz: a[0],
+            gravity: a[1],
+            flap_velocity: a[2],
+            max_fall_speed: a[3],
+            pipe_speed: a[4],
+            pipe_gap: a[5],
+            pipe_spawn_interval: a[6],
+            hitbox_shrink: a[7],
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: ReplayConfig,
+    // Fixed ticks since the run started on which the bird flapped, ascending
+    pub flap_ticks: Vec<u32>,
+    // Likewise for laser shots
+    pub shot_ticks: Vec<u32>,
+    pub final_score: u32,
+    pub modifiers: Modifiers,
+    // Pipes in a row before the run started (see streak.rs)
+    pub streak_start: u32,
+    // Whether the run had hazards (see hazards.rs)
+    pub hazards: bool,
+    // Whether the run had mutator roulette (see roulette.rs)
+    pub roulette: bool,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    NotAReplay,
+    UnsupportedVersion(u8),
+    Truncated,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{err}"),
+            ReplayError::NotAReplay => write!(f, "not a Floopy Birb replay"),
+            ReplayError::UnsupportedVersion(v) => write!(f, "unsupported replay version {v}"),
+            ReplayError::Truncated => write!(f, "replay data is truncated"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(err: std::io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+impl Replay {
+    // What the run was played with
+    pub fn rules(&self) -> Rules {
+        Rules {
+            gravity: self.config.gravity,
+            pipe_gap: self.config.pipe_gap,
+            pipe_speed: self.config.pipe_speed,
+            hazards: self.hazards,
+            roulette: self.roulette,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(48 + self.flap_ticks.len() * 2);
+        out.extend_from_slice(REPLAY_MAGIC);
+        out.push(REPLAY_VERSION);
+        out.extend_from_slice(&self.seed.to_le_bytes());
+        for value in self.config.to_array() {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        write_varint(&mut out, self.modifiers.bits().into());
+        write_varint(&mut out, self.streak_start);
+        let mut flags = 0;
+        if self.hazards {
+            flags |= FLAG_HAZARDS;
+        }
+        if self.roulette {
+            flags |= FLAG_ROULETTE;
+        }
+        out.push(flags);
+        write_varint(&mut out, self.final_score);
+        write_ticks(&mut out, &self.flap_ticks);
+        write_ticks(&mut out, &self.shot_ticks);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut r = Reader(bytes);
+        if r.take(4)? != REPLAY_MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+        let version = r.take(1)?[0];
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let seed = u64::from_le_bytes(r.take(8)?.try_into().unwrap());
+        let mut config = [0.0; 8];
+        for value in &mut config {
+            *value = f32::from_le_bytes(r.take(4)?.try_into().unwrap());
+        }
+        let modifiers = Modifiers::from_bits(r.varint()? as u16);
+        let streak_start = r.varint()?;
+        let flags = r.take(1)?[0];
+        let final_score = r.varint()?;
+        let flap_ticks = r.ticks()?;
+        let shot_ticks = r.ticks()?;
+        Ok(Self {
+            seed,
+            config: ReplayConfig::from_array(config),
+            flap_ticks,
+            shot_ticks,
+            final_score,
+            modifiers,
+            streak_start,
+            hazards: flags & FLAG_HAZARDS != 0,
+            roulette: flags & FLAG_ROULETTE != 0,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+pub fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+// A count, then each tick as a delta from the previous one. Ticks are
+// recorded as they happen, so they're ascending.
+pub fn write_ticks(out: &mut Vec<u8>, ticks: &[u32]) {
+    debug_assert!(ticks.is_sorted());
+    write_varint(out, ticks.len() as u32);
+    let mut last = 0;
+    for &tick in ticks {
+        write_varint(out, tick.saturating_sub(last));
+        last = tick;
+    }
+}
+
+pub struct Reader<'a>(pub &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub fn take(&mut self, n: usize) -> Result<&'a [u8], ReplayError> {
+        if self.0.len() < n {
+            return Err(ReplayError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    pub fn varint(&mut self) -> Result<u32, ReplayError> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReplayError::Truncated)
+    }
+
+    pub fn ticks(&mut self) -> Result<Vec<u32>, ReplayError> {
+        let count = self.varint()?;
+        let mut ticks = Vec::with_capacity(count.min(1 << 16) as usize);
+        let mut tick = 0u32;
+        for _ in 0..count {
+            tick = tick
+                .checked_add(self.varint()?)
+                .ok_or(ReplayError::Truncated)?;
+            ticks.push(tick);
+        }
+        Ok(ticks)
+    }
+}
+
+pub fn replays_dir() -> PathBuf {
+    crate::data_file("replays")
+}
+
+// Most recently saved replay, by file modification time
+fn newest_replay() -> Option<PathBuf> {
+    std::fs::read_dir(replays_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|e| e == REPLAY_EXTENSION)
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+// Highest-scoring saved replay, or else the game's (or a mod's) demo.fbr
+pub fn best_replay(mods: &Mods) -> Option<Replay> {
+    let saved = std::fs::read_dir(replays_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == REPLAY_EXTENSION))
+        .filter_map(|path| Replay::load(&path).ok())
+        .max_by_key(|replay| replay.final_score);
+    saved.or_else(|| Replay::load(&mods.resolve_file(DEMO_REPLAY)).ok())
+}
+
+// --------------------------------------------
+// Recording
+// --------------------------------------------
+
+// Flaps and shots of the current run, recorded as they happen
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    flap_ticks: Vec<u32>,
+    shot_ticks: Vec<u32>,
+    streak_start: u32,
+    // The run's replay is already on disk
+    saved: bool,
+}
+
+fn reset_recorder(mut recorder: ResMut<ReplayRecorder>, streak: Res<Streak>) {
+    recorder.flap_ticks.clear();
+    recorder.shot_ticks.clear();
+    recorder.streak_start = streak.current;
+    recorder.saved = false;
+}
+
+fn record_flaps(
+    tick: Res<RunTick>,
+    mut flapped: EventReader<Flapped>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    for _ in flapped.read() {
+        recorder.flap_ticks.push(tick.0);
+    }
+}
+
+fn record_shots(
+    tick: Res<RunTick>,
+    mut fired: EventReader<LaserFired>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    for _ in fired.read() {
+        recorder.shot_ticks.push(tick.0);
+    }
+}
+
+// The run that just ended, as the game over screen saves or shares it
+#[derive(SystemParam)]
+pub struct RecordedRun<'w> {
+    seed: Res<'w, RunSeed>,
+    run_hitbox: Res<'w, RunHitbox>,
+    run_modifiers: Res<'w, RunModifiers>,
+    run_rules: Res<'w, RunRules>,
+    score: Res<'w, Score>,
+    recorder: ResMut<'w, ReplayRecorder>,
+    playback: Option<Res<'w, ReplayPlayback>>,
+    run_cheats: Res<'w, RunCheats>,
+    rewind: Res<'w, Rewind>,
+    course: UnrecordedCourse<'w>,
+}
+
+impl RecordedRun<'_> {
+    // Err with the reason to show when there's no replay to be had
+    pub fn replay(&self) -> Result<Replay, &'static str> {
+        // The one being watched
+        if let Some(playback) = &self.playback {
+            return Ok(playback.replay.clone());
+        }
+        // Replays don't store cheats, so they wouldn't play back the same
+        if self.run_cheats.any() {
+            return Err("Runs played with cheats can't be saved or shared");
+        }
+        // Nor rewinds, which take the run back in time
+        if self.rewind.used() {
+            return Err("Rewound runs can't be saved or shared");
+        }
+        // Nor whatever else shaped the course
+        if let Some(reason) = self.course.reason() {
+            return Err(reason);
+        }
+        let rules = self.run_rules.0;
+        Ok(Replay {
+            seed: self.seed.0,
+            config: ReplayConfig::current(self.run_hitbox.0).with_rules(rules),
+            flap_ticks: self.recorder.flap_ticks.clone(),
+            shot_ticks: self.recorder.shot_ticks.clone(),
+            final_score: self.score.0,
+            modifiers: self.run_modifiers.0,
+            streak_start: self.recorder.streak_start,
+            hazards: rules.hazards,
+            roulette: rules.roulette,
+        })
+    }
+}
+
+fn save_replay_input(
+    input: Res<ButtonInput<KeyCode>>,
+    mut run: RecordedRun,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !input.just_pressed(KeyCode::KeyS) || run.recorder.saved || run.playback.is_some() {
+        return;
+    }
+    let replay = match run.replay() {
+        Ok(replay) => replay,
+        Err(reason) => {
+            toasts.send(ShowToast(reason.to_string()));
+            return;
+        }
+    };
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+    let path = replays_dir().join(format!(
+        "{timestamp}_score{}.{REPLAY_EXTENSION}",
+        replay.final_score
+    ));
+    match replay.save(&path) {
+        Ok(()) => {
+            run.recorder.saved = true;
+            toasts.send(ShowToast(format!("Replay saved to {}", path.display())));
+        }
+        Err(err) => {
+            error!("Cannot save replay to {}: {err}", path.display());
+            toasts.send(ShowToast("Saving replay failed".to_string()));
+        }
+    }
+}
+
+// --------------------------------------------
+// Playback
+// --------------------------------------------
+
+// Present while a replay is being watched
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    next_flap: usize,
+    next_shot: usize,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_flap: 0,
+            next_shot: 0,
+        }
+    }
+}
+
+#[derive(Component)]
+struct ReplayBanner;
+
+pub fn start_playback(
+    replay: Replay,
+    commands: &mut Commands,
+    next_state: &mut NextState<GameState>,
+    toasts: &mut EventWriter<ShowToast>,
+) {
+    let expected = ReplayConfig::current(replay.config.hitbox_shrink).with_rules(replay.rules());
+    if replay.config != expected {
+        toasts.send(ShowToast(
+            "Replay was recorded with different settings and may not match".to_string(),
+        ));
+    }
+    commands.insert_resource(NextRunSeed(Some(replay.seed)));
+    commands.insert_resource(ReplayPlayback::new(replay));
+    next_state.set(GameState::Replay);
+}
+
+#[derive(Resource)]
+struct OpenOnStart(PathBuf);
+
+fn open_replay_on_start(
+    mut commands: Commands,
+    open: Res<OpenOnStart>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    open_replay(&open.0, &mut commands, &mut next_state, &mut toasts);
+}
+
+fn open_replay(
+    path: &Path,
+    commands: &mut Commands,
+    next_state: &mut NextState<GameState>,
+    toasts: &mut EventWriter<ShowToast>,
+) {
+    match Replay::load(path) {
+        Ok(replay) => start_playback(replay, commands, next_state, toasts),
+        Err(err) => {
+            error!("Cannot load replay {}: {err}", path.display());
+            toasts.send(ShowToast(format!("Cannot load replay: {err}")));
+        }
+    }
+}
+
+fn load_replay_input(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !input.just_pressed(KeyCode::KeyL) {
+        return;
+    }
+    match newest_replay() {
+        Some(path) => open_replay(&path, &mut commands, &mut next_state, &mut toasts),
+        None => {
+            toasts.send(ShowToast("No saved replays yet".to_string()));
+        }
+    }
+}
+
+// Feed recorded flaps instead of live input
+fn play_back_flaps(
+    tick: Res<RunTick>,
+    playback: Option<ResMut<ReplayPlayback>>,
+    mut flap_input: ResMut<FlapInput>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    flap_input.requested = false;
+    while let Some(&flap_tick) = playback.replay.flap_ticks.get(playback.next_flap) {
+        if flap_tick > tick.0 {
+            break;
+        }
+        playback.next_flap += 1;
+        if flap_tick == tick.0 {
+            flap_input.requested = true;
+        }
+    }
+}
+
+// Likewise for laser shots
+fn play_back_shots(
+    tick: Res<RunTick>,
+    playback: Option<ResMut<ReplayPlayback>>,
+    mut laser_input: ResMut<LaserInput>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    laser_input.requested = false;
+    while let Some(&shot_tick) = playback.replay.shot_ticks.get(playback.next_shot) {
+        if shot_tick > tick.0 {
+            break;
+        }
+        playback.next_shot += 1;
+        if shot_tick == tick.0 {
+            laser_input.requested = true;
+        }
+    }
+}
+
+fn show_replay_banner(
+    mut commands: Commands,
+    playback: Option<Res<ReplayPlayback>>,
+    banners: Query<Entity, With<ReplayBanner>>,
+) {
+    for e in &banners {
+        commands.entity(e).despawn_recursive();
+    }
+    if playback.is_none() {
+        return;
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            "REPLAY",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::srgb(1.0, 0.3, 0.3),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        ReplayBanner,
+    ));
+}
+
+fn show_replay_result(
+    mut commands: Commands,
+    score: Res<Score>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    let Some(playback) = playback else {
+        return;
+    };
+    let expected = playback.replay.final_score;
+    let (message, color) = if score.0 == expected {
+        (
+            format!("Replay verified: score {expected}"),
+            Color::srgb(0.1, 0.6, 0.1),
+        )
+    } else {
+        (
+            format!("Replay mismatch: expected {expected}, got {}", score.0),
+            Color::srgb(0.8, 0.1, 0.1),
+        )
+    };
+    commands.spawn((
+        TextBundle::from_section(
+            message,
+            TextStyle {
+                font_size: 22.0,
+                color,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(340.0),
+            left: Val::Px(WINDOW_W * 0.5 - 220.0),
+            ..default()
+        }),
+        GameOverUI,
+    ));
+}
+
+fn stop_playback(mut commands: Commands, banners: Query<Entity, With<ReplayBanner>>) {
+    commands.remove_resource::<ReplayPlayback>();
+    for e in &banners {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::modifiers::Modifier;
+
+    fn sample() -> Replay {
+        Replay {
+            seed: 0x0123_4567_89ab_cdef,
+            config: ReplayConfig::current(0.25).with_rules(Rules {
+                gravity: -1500.0,
+                ..default()
+            }),
+            // Deltas of one, two and three varint bytes
+            flap_ticks: vec![0, 5, 200, 20_000, 20_001],
+            shot_ticks: vec![90, 91],
+            final_score: 300,
+            modifiers: Modifiers::NONE
+                .with(Modifier::SmallGap)
+                .with(Modifier::Night),
+            streak_start: 12,
+            hazards: false,
+            roulette: true,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = sample();
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()).unwrap(), replay);
+
+        let empty = Replay {
+            flap_ticks: Vec::new(),
+            shot_ticks: Vec::new(),
+            hazards: true,
+            roulette: false,
+            ..sample()
+        };
+        assert_eq!(Replay::from_bytes(&empty.to_bytes()).unwrap(), empty);
+    }
+
+    #[test]
+    fn varints() {
+        for value in [0, 1, 127, 128, 16_383, 16_384, u32::MAX] {
+            let mut out = Vec::new();
+            write_varint(&mut out, value);
+            let mut r = Reader(&out);
+            assert_eq!(r.varint().unwrap(), value);
+            assert!(r.0.is_empty());
+        }
+        // Never ends
+        assert!(Reader(&[0xff; 8]).varint().is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = sample().to_bytes();
+        for len in 0..bytes.len() {
+            assert!(
+                Replay::from_bytes(&bytes[..len]).is_err(),
+                "{len} of {} bytes",
+                bytes.len()
+            );
+        }
+    }
+
+    #[test]
+    fn garbage() {
+        assert!(matches!(
+            Replay::from_bytes(b"not a replay at all"),
+            Err(ReplayError::NotAReplay)
+        ));
+        let mut bytes = sample().to_bytes();
+        bytes[4] = REPLAY_VERSION + 1;
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(ReplayError::UnsupportedVersion(_))
+        ));
+
+        // Anything after a valid header fails or parses, but never panics
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..1000 {
+            let mut bytes = REPLAY_MAGIC.to_vec();
+            bytes.push(REPLAY_VERSION);
+            let len = rng.gen_range(0..80);
+            bytes.extend((0..len).map(|_| rng.gen::<u8>()));
+            let _ = Replay::from_bytes(&bytes);
+        }
+    }
+}
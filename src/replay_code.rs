@@ -0,0 +1,272 @@
+// Replay codes: a replay packed into a line of text short enough to paste in a
+// chat message. C on the game over screen copies the code of the run just
+// played (or the replay just watched) to the clipboard; V on the main menu
+// watches the code on the clipboard.
+//
+// A code is URL-safe base64, without padding, of:
+//   u8 code version, u64 seed, u8 flags (1 hazards, 2 tuning follows,
+//   4 mutator roulette), varint modifier bits, varint streak at the
+//   start, f32 hitbox shrink, then the other 7 config values only if the
+//   run's tuning isn't this version's, varint final score, then the flap and
+//   shot ticks as varint deltas like in a replay file
+// A short run comes to a few dozen characters, a long one a few hundred.
+
+use bevy::prelude::*;
+
+use crate::modifiers::Modifiers;
+use crate::photo::PhotoMode;
+use crate::replay::{
+    start_playback, write_ticks, write_varint, Reader, RecordedRun, Replay, ReplayConfig,
+    ReplayError,
+};
+use crate::toast::ShowToast;
+use crate::GameState;
+
+const CODE_VERSION: u8 = 1;
+const CODE_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+const FLAG_HAZARDS: u8 = 1;
+const FLAG_TUNING: u8 = 2;
+const FLAG_ROULETTE: u8 = 4;
+
+pub struct ReplayCodePlugin;
+
+impl Plugin for ReplayCodePlugin {
+    fn build(&self, app: &mut App) {
+        // Not every platform lets the clipboard move between threads
+        app.insert_non_send_resource(Clipboard(arboard::Clipboard::new().ok()))
+            .add_systems(Update, paste_code_input.run_if(in_state(GameState::Menu)))
+            .add_systems(
+                Update,
+                copy_code_input.run_if(
+                    in_state(GameState::GameOver).and_then(not(resource_exists::<PhotoMode>)),
+                ),
+            );
+    }
+}
+
+// None if the system clipboard can't be reached
+struct Clipboard(Option<arboard::Clipboard>);
+
+impl Replay {
+    pub fn to_code(&self) -> String {
+        let config = self.config.to_array();
+        let tuned = self.config != ReplayConfig::current(self.config.hitbox_shrink);
+        let mut flags = 0;
+        if self.hazards {
+            flags |= FLAG_HAZARDS;
+        }
+        if tuned {
+            flags |= FLAG_TUNING;
+        }
+        if self.roulette {
+            flags |= FLAG_ROULETTE;
+        }
+
+        let mut out = Vec::with_capacity(24 + self.flap_ticks.len() * 2);
+        out.push(CODE_VERSION);
+        out.extend_from_slice(&self.seed.to_le_bytes());
+        out.push(flags);
+        write_varint(&mut out, self.modifiers.bits().into());
+        write_varint(&mut out, self.streak_start);
+        out.extend_from_slice(&self.config.hitbox_shrink.to_le_bytes());
+        if tuned {
+            for value in &config[..7] {
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        write_varint(&mut out, self.final_score);
+        write_ticks(&mut out, &self.flap_ticks);
+        write_ticks(&mut out, &self.shot_ticks);
+        to_base64(&out)
+    }
+
+    pub fn from_code(code: &str) -> Result<Self, ReplayError> {
+        let bytes = from_base64(code.trim()).ok_or(ReplayError::NotAReplay)?;
+        let mut r = Reader(&bytes);
+        let version = r.take(1)?[0];
+        if version != CODE_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let seed = u64::from_le_bytes(r.take(8)?.try_into().unwrap());
+        let flags = r.take(1)?[0];
+        let modifiers = Modifiers::from_bits(r.varint()? as u16);
+        let streak_start = r.varint()?;
+        let hitbox_shrink = f32::from_le_bytes(r.take(4)?.try_into().unwrap());
+        let mut config = ReplayConfig::current(hitbox_shrink).to_array();
+        if flags & FLAG_TUNING != 0 {
+            for value in &mut config[..7] {
+                *value = f32::from_le_bytes(r.take(4)?.try_into().unwrap());
+            }
+        }
+        let final_score = r.varint()?;
+        let flap_ticks = r.ticks()?;
+        let shot_ticks = r.ticks()?;
+        Ok(Self {
+            seed,
+            config: ReplayConfig::from_array(config),
+            flap_ticks,
+            shot_ticks,
+            final_score,
+            modifiers,
+            streak_start,
+            hazards: flags & FLAG_HAZARDS != 0,
+            roulette: flags & FLAG_ROULETTE != 0,
+        })
+    }
+}
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        // 2, 3 or 4 characters for 1, 2 or 3 bytes
+        for i in 0..=chunk.len() {
+            out.push(CODE_ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+        }
+    }
+    out
+}
+
+// None if there's anything but base64 characters in it
+fn from_base64(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text.bytes() {
+        let value = CODE_ALPHABET.iter().position(|&a| a == c)? as u32;
+        bits = bits << 6 | value;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    Some(out)
+}
+
+fn copy_code_input(
+    input: Res<ButtonInput<KeyCode>>,
+    run: RecordedRun,
+    mut clipboard: NonSendMut<Clipboard>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !input.just_pressed(KeyCode::KeyC) {
+        return;
+    }
+    let code = match run.replay() {
+        Ok(replay) => replay.to_code(),
+        Err(reason) => {
+            toasts.send(ShowToast(reason.to_string()));
+            return;
+        }
+    };
+    // Also logged, for when the clipboard isn't there
+    info!("Replay code: {code}");
+    let copied = clipboard
+        .0
+        .as_mut()
+        .map(|clipboard| clipboard.set_text(code.as_str()));
+    let message = match copied {
+        Some(Ok(())) => format!("Replay code copied ({} characters)", code.len()),
+        Some(Err(err)) => {
+            error!("Cannot copy the replay code: {err}");
+            "Couldn't copy the replay code - it's in the log".to_string()
+        }
+        None => "No clipboard to copy to - the replay code is in the log".to_string(),
+    };
+    toasts.send(ShowToast(message));
+}
+
+fn paste_code_input(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    mut clipboard: NonSendMut<Clipboard>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !input.just_pressed(KeyCode::KeyV) {
+        return;
+    }
+    let Some(text) = clipboard
+        .0
+        .as_mut()
+        .and_then(|clipboard| clipboard.get_text().ok())
+    else {
+        toasts.send(ShowToast(
+            "Copy a replay code first, then press V".to_string(),
+        ));
+        return;
+    };
+    match Replay::from_code(&text) {
+        Ok(replay) => start_playback(replay, &mut commands, &mut next_state, &mut toasts),
+        Err(err) => {
+            toasts.send(ShowToast(format!("That's not a replay code: {err}")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modifiers::{Modifier, Rules};
+
+    fn sample(config: ReplayConfig) -> Replay {
+        Replay {
+            seed: u64::MAX - 7,
+            config,
+            flap_ticks: vec![3, 40, 41, 900, 30_000],
+            shot_ticks: vec![120],
+            final_score: 57,
+            modifiers: Modifiers::NONE.with(Modifier::Wind),
+            streak_start: 4,
+            hazards: true,
+            roulette: true,
+        }
+    }
+
+    #[test]
+    fn base64() {
+        // Every length of last chunk, and every character
+        let bytes: Vec<u8> = (0..=255).collect();
+        for len in 0..10 {
+            let code = to_base64(&bytes[256 - len..]);
+            assert!(code.bytes().all(|c| CODE_ALPHABET.contains(&c)));
+            assert_eq!(from_base64(&code).unwrap(), &bytes[256 - len..]);
+        }
+        // Padding and the standard alphabet's extra characters aren't ours
+        assert_eq!(from_base64("AAA="), None);
+        assert_eq!(from_base64("AA+/"), None);
+    }
+
+    #[test]
+    fn tuning_only_when_changed() {
+        let untuned = sample(ReplayConfig::current(0.5));
+        let tuned = sample(ReplayConfig::current(0.5).with_rules(Rules {
+            pipe_gap: 123.0,
+            ..default()
+        }));
+        // After the version and the seed
+        let flags = |replay: &Replay| from_base64(&replay.to_code()).unwrap()[9];
+        assert_eq!(flags(&untuned) & FLAG_TUNING, 0);
+        assert_ne!(flags(&tuned) & FLAG_TUNING, 0);
+        assert_eq!(Replay::from_code(&untuned.to_code()).unwrap(), untuned);
+        assert_eq!(Replay::from_code(&tuned.to_code()).unwrap(), tuned);
+    }
+
+    #[test]
+    fn pasted_with_whitespace() {
+        let replay = sample(ReplayConfig::current(0.0));
+        let code = replay.to_code();
+        assert_eq!(Replay::from_code(&format!(" {code}\n")).unwrap(), replay);
+        assert!(matches!(
+            Replay::from_code(&format!("{} {}", &code[..4], &code[4..])),
+            Err(ReplayError::NotAReplay)
+        ));
+    }
+}
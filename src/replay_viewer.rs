@@ -0,0 +1,180 @@
+// Controls while watching a replay (the Replay state): Space or P pauses, Left
+// and Right step the speed between 0.25x and 4x, Period advances one tick while
+// paused, WASD and the mouse wheel move the camera as in photo mode, and Escape
+// goes back to the menu.
+//
+// Speed and pause act on virtual time, so the simulation still runs the
+// recorded run tick for tick and only the pace changes. The practice speed
+// setting comes back once the replay is over.
+
+use bevy::app::FixedMain;
+use bevy::prelude::*;
+
+use crate::photo::{move_camera, recenter};
+use crate::screen_reader::Announce;
+use crate::settings::Settings;
+use crate::GameState;
+
+const SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+const NORMAL_SPEED: usize = 2;
+
+pub struct ReplayViewerPlugin;
+
+impl Plugin for ReplayViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayViewer>()
+            .add_systems(OnEnter(GameState::Replay), open_viewer)
+            .add_systems(
+                Update,
+                (
+                    viewer_input,
+                    step_one_tick.run_if(step_requested),
+                    move_camera,
+                    update_viewer_text,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Replay)),
+            )
+            .add_systems(OnExit(GameState::Replay), close_viewer);
+    }
+}
+
+#[derive(Resource)]
+struct ReplayViewer {
+    // Into SPEEDS
+    speed: usize,
+    paused: bool,
+}
+
+impl Default for ReplayViewer {
+    fn default() -> Self {
+        Self {
+            speed: NORMAL_SPEED,
+            paused: false,
+        }
+    }
+}
+
+#[derive(Component)]
+struct ViewerUI;
+
+fn open_viewer(
+    mut commands: Commands,
+    mut viewer: ResMut<ReplayViewer>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    *viewer = ReplayViewer::default();
+    time.set_relative_speed(SPEEDS[viewer.speed]);
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "",
+                TextStyle {
+                    font_size: 22.0,
+                    color: Color::srgb(1.0, 0.3, 0.3),
+                    ..default()
+                },
+            ),
+            TextSection::new(
+                "   Space pause, Left/Right speed, . step, WASD/wheel camera, Esc exit",
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..default()
+        }),
+        ViewerUI,
+    ));
+}
+
+fn viewer_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut viewer: ResMut<ReplayViewer>,
+    mut time: ResMut<Time<Virtual>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut announce: EventWriter<Announce>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+        return;
+    }
+    if keys.any_just_pressed([KeyCode::Space, KeyCode::KeyP]) {
+        viewer.paused = !viewer.paused;
+        if viewer.paused {
+            time.pause();
+        } else {
+            time.unpause();
+        }
+        let text = if viewer.paused { "Paused" } else { "Resumed" };
+        announce.send(Announce(text.to_string()));
+    }
+    let speed = if keys.just_pressed(KeyCode::ArrowLeft) {
+        viewer.speed.saturating_sub(1)
+    } else if keys.just_pressed(KeyCode::ArrowRight) {
+        (viewer.speed + 1).min(SPEEDS.len() - 1)
+    } else {
+        viewer.speed
+    };
+    if speed != viewer.speed {
+        viewer.speed = speed;
+        time.set_relative_speed(SPEEDS[speed]);
+        announce.send(Announce(format!("{} speed", speed_label(SPEEDS[speed]))));
+    }
+}
+
+fn step_requested(keys: Res<ButtonInput<KeyCode>>, viewer: Res<ReplayViewer>) -> bool {
+    viewer.paused && keys.just_pressed(KeyCode::Period)
+}
+
+// Run the fixed-step simulation exactly once, the way the fixed loop would
+fn step_one_tick(world: &mut World) {
+    let mut fixed = world.resource_mut::<Time<Fixed>>();
+    let timestep = fixed.timestep();
+    fixed.advance_by(timestep);
+    *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+    world.run_schedule(FixedMain);
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+}
+
+fn update_viewer_text(viewer: Res<ReplayViewer>, mut q: Query<&mut Text, With<ViewerUI>>) {
+    if !viewer.is_changed() {
+        return;
+    }
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = if viewer.paused {
+        "Paused".to_string()
+    } else {
+        speed_label(SPEEDS[viewer.speed])
+    };
+}
+
+fn close_viewer(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut time: ResMut<Time<Virtual>>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+    ui: Query<Entity, With<ViewerUI>>,
+) {
+    time.unpause();
+    time.set_relative_speed(settings.game_speed);
+    for (mut tf, mut projection) in &mut cameras {
+        recenter(&mut tf, &mut projection);
+    }
+    for e in &ui {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// 0.25 -> "0.25x", 2.0 -> "2x"
+fn speed_label(speed: f32) -> String {
+    format!("{speed}x")
+}
@@ -0,0 +1,61 @@
+// Instant restart: holding R (or Select on a controller) for half a second
+// during a run starts a new one straight away, without the game over screen.
+// The run is left and entered again on the spot, so start_game and everything
+// else that resets on a new run goes through the same path as a normal start.
+// Not while an opponent is waiting on the result (see `opponent_waiting`).
+
+use bevy::prelude::*;
+
+use crate::{opponent_waiting, GameState};
+
+// How long the key has to be held, in seconds
+const HOLD_TIME: f32 = 0.5;
+
+pub struct RestartPlugin;
+
+impl Plugin for RestartPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RestartRun>().add_systems(
+            Update,
+            (
+                hold_restart.run_if(in_state(GameState::Playing).and_then(not(opponent_waiting))),
+                restart_run.run_if(on_event::<RestartRun>()),
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Event)]
+struct RestartRun;
+
+// Real time, so it still counts while paused
+fn hold_restart(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Res<ButtonInput<GamepadButton>>,
+    time: Res<Time<Real>>,
+    mut held: Local<f32>,
+    mut restart: EventWriter<RestartRun>,
+) {
+    let holding = keys.pressed(KeyCode::KeyR)
+        || pads
+            .get_pressed()
+            .any(|button| button.button_type == GamepadButtonType::Select);
+    if !holding {
+        *held = 0.0;
+        return;
+    }
+    *held += time.delta_seconds();
+    if *held >= HOLD_TIME {
+        // Once per press: nothing more until the key comes back up
+        *held = f32::NEG_INFINITY;
+        restart.send(RestartRun);
+    }
+}
+
+// Bevy skips OnExit and OnEnter when a state is set to itself, so they're run
+// here by hand
+fn restart_run(world: &mut World) {
+    world.run_schedule(OnExit(GameState::Playing));
+    world.run_schedule(OnEnter(GameState::Playing));
+}
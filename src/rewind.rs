@@ -0,0 +1,325 @@
+// Rewind (Rewinds in the settings menu): an assist that gives each run a few
+// second chances. On a crash with a rewind left, the run stands still instead of
+// ending; Backspace (B on a controller) plays the last REWIND_SECS back in
+// reverse and carries on from there, and the confirm key gives up.
+//
+// Every fixed step of a run leaves a snapshot in a ring buffer: the run's tick,
+// score, distance and random streams, the pipe spawner, the lead bird and every
+// pipe. Rewinding plays those back newest first, then restores the oldest. The
+// rest of what flies along the course (hazards, laser charges, coins) isn't
+// kept, so their modules clear it on `Rewound`; a rewound bird keeps its size,
+// which coins and the roulette decide. The rest of a flock doesn't come back.
+//
+// A rewound run doesn't count towards the best score or anything else, and
+// can't be saved as a replay. No rewinds while an opponent is waiting on the
+// result (see `opponent_waiting`).
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::controls::GameInput;
+use crate::distance::Distance;
+use crate::settings::Settings;
+use crate::{
+    check_collisions_and_scoring, opponent_waiting, spawn_pipe, start_game, Bird, CrashCause,
+    Crashed, FixedTransform, FlapInput, GameRng, GameState, LeadBird, Pipe, PipeAssets,
+    PipeSpawnTimer, Rewound, RunTick, Score, FIXED_HZ, WINDOW_W,
+};
+
+pub const MAX_REWINDS: u32 = 3;
+const REWIND_SECS: f64 = 3.0;
+const REWIND_TICKS: usize = (REWIND_SECS * FIXED_HZ) as usize;
+// How long playing the snapshots back takes, in real seconds
+const PLAYBACK_SECS: f32 = 1.0;
+// Keys pressed this soon after the crash are ignored: the player was flapping
+const INPUT_DELAY: f32 = 0.4;
+
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), start_rewinds.after(start_game))
+            // Taken away again when someone's waiting on the result
+            .add_systems(
+                OnEnter(GameState::Playing),
+                stop_rewinds.after(start_rewinds).run_if(opponent_waiting),
+            )
+            .add_systems(OnEnter(GameState::Menu), stop_rewinds)
+            .add_systems(OnEnter(GameState::Replay), stop_rewinds)
+            .add_systems(
+                FixedUpdate,
+                take_snapshot
+                    .after(check_collisions_and_scoring)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (rewind_input, play_back, update_rewind_prompt)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(GameState::Playing), hide_rewind_prompt);
+    }
+}
+
+// The run as it was at one fixed step
+struct Snapshot {
+    tick: u32,
+    score: u32,
+    meters: f32,
+    rng: GameRng,
+    spawn_timer: PipeSpawnTimer,
+    // The lead bird's place, tilt and speed
+    bird: (Vec3, Quat, f32, f32),
+    pipes: Vec<(Transform, Pipe)>,
+}
+
+#[derive(Resource, Default)]
+pub struct Rewind {
+    // Rewinds this run has left
+    left: u32,
+    used: bool,
+    // Oldest first, at most REWIND_TICKS
+    snapshots: VecDeque<Snapshot>,
+    // The crash waiting on the player
+    crash: Option<CrashCause>,
+    // Real seconds since it
+    waited: f32,
+    // How far through playing the snapshots back, from 0 to 1, once rewinding
+    playback: Option<f32>,
+}
+
+impl Rewind {
+    // The run was rewound at least once
+    pub fn used(&self) -> bool {
+        self.used
+    }
+
+    // A crash is waiting on the player, or being rewound
+    pub fn holding(&self) -> bool {
+        self.crash.is_some()
+    }
+
+    // Hold the run on a crash if there's a rewind left and something to go
+    // back to; false if the crash ends the run
+    pub fn offer(&mut self, cause: CrashCause) -> bool {
+        if self.left == 0 || self.snapshots.is_empty() {
+            return false;
+        }
+        self.crash = Some(cause);
+        self.waited = 0.0;
+        true
+    }
+}
+
+fn start_rewinds(settings: Res<Settings>, mut rewind: ResMut<Rewind>) {
+    *rewind = Rewind {
+        left: settings.rewinds,
+        ..default()
+    };
+}
+
+fn stop_rewinds(mut rewind: ResMut<Rewind>) {
+    *rewind = Rewind::default();
+}
+
+// Not on the step that crashed, which is the one to go back from
+#[allow(clippy::too_many_arguments)]
+fn take_snapshot(
+    run_tick: Res<RunTick>,
+    score: Res<Score>,
+    distance: Res<Distance>,
+    rng: Res<GameRng>,
+    spawn_timer: Res<PipeSpawnTimer>,
+    birds: Query<(&Transform, &Bird), LeadBird>,
+    pipes: Query<(&Transform, &Pipe)>,
+    mut rewind: ResMut<Rewind>,
+) {
+    if rewind.left == 0 || rewind.holding() {
+        return;
+    }
+    let Ok((tf, bird)) = birds.get_single() else {
+        return;
+    };
+    if rewind.snapshots.len() == REWIND_TICKS {
+        rewind.snapshots.pop_front();
+    }
+    rewind.snapshots.push_back(Snapshot {
+        tick: run_tick.0,
+        score: score.0,
+        meters: distance.meters,
+        rng: rng.clone(),
+        spawn_timer: spawn_timer.clone(),
+        bird: (tf.translation, tf.rotation, bird.vy, bird.vx),
+        pipes: pipes.iter().map(|(tf, pipe)| (*tf, pipe.clone())).collect(),
+    });
+}
+
+fn rewind_input(
+    controls: GameInput,
+    time: Res<Time<Real>>,
+    mut rewind: ResMut<Rewind>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut crashed: EventWriter<Crashed>,
+) {
+    let Some(cause) = rewind.crash else {
+        return;
+    };
+    if rewind.playback.is_some() {
+        return;
+    }
+    rewind.waited += time.delta_seconds();
+    if rewind.waited < INPUT_DELAY {
+        return;
+    }
+    if controls.rewind() {
+        rewind.left -= 1;
+        rewind.used = true;
+        rewind.playback = Some(0.0);
+    } else if controls.confirm() {
+        rewind.crash = None;
+        next_state.set(GameState::GameOver);
+        crashed.send(Crashed(cause));
+    }
+}
+
+// Put the bird and pipes where a snapshot has them; the bird keeps its size
+fn place(
+    snapshot: &Snapshot,
+    commands: &mut Commands,
+    assets: &PipeAssets,
+    pipes: &Query<Entity, With<Pipe>>,
+    birds: &mut Query<(&mut Transform, &mut Bird, &mut FixedTransform), LeadBird>,
+) {
+    if let Ok((mut tf, mut bird, mut fixed_tf)) = birds.get_single_mut() {
+        let (translation, rotation, vy, vx) = snapshot.bird;
+        tf.translation = translation;
+        tf.rotation = rotation;
+        bird.vy = vy;
+        bird.vx = vx;
+        // Teleport: don't interpolate from the crash
+        *fixed_tf = FixedTransform::new(*tf);
+    }
+    for e in pipes {
+        commands.entity(e).despawn_recursive();
+    }
+    for (tf, pipe) in &snapshot.pipes {
+        spawn_pipe(commands, assets, *tf, pipe.clone());
+    }
+}
+
+// Newest snapshot to oldest over PLAYBACK_SECS, then the run carries on from
+// the oldest, which stays as the first of the next rewind
+#[allow(clippy::too_many_arguments)]
+fn play_back(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut rewind: ResMut<Rewind>,
+    assets: Res<PipeAssets>,
+    pipes: Query<Entity, With<Pipe>>,
+    mut birds: Query<(&mut Transform, &mut Bird, &mut FixedTransform), LeadBird>,
+    mut run_tick: ResMut<RunTick>,
+    mut score: ResMut<Score>,
+    mut distance: ResMut<Distance>,
+    mut rng: ResMut<GameRng>,
+    mut spawn_timer: ResMut<PipeSpawnTimer>,
+    mut flap_input: ResMut<FlapInput>,
+    mut rewound: EventWriter<Rewound>,
+) {
+    let Some(progress) = rewind.playback else {
+        return;
+    };
+    let progress = progress + time.delta_seconds() / PLAYBACK_SECS;
+    if progress < 1.0 {
+        rewind.playback = Some(progress);
+        let newest = rewind.snapshots.len() - 1;
+        let index = ((1.0 - progress) * newest as f32).round() as usize;
+        place(
+            &rewind.snapshots[index],
+            &mut commands,
+            &assets,
+            &pipes,
+            &mut birds,
+        );
+        return;
+    }
+
+    rewind.snapshots.truncate(1);
+    let snapshot = &rewind.snapshots[0];
+    place(snapshot, &mut commands, &assets, &pipes, &mut birds);
+    run_tick.0 = snapshot.tick;
+    score.0 = snapshot.score;
+    distance.meters = snapshot.meters;
+    *rng = snapshot.rng.clone();
+    *spawn_timer = snapshot.spawn_timer.clone();
+    rewind.crash = None;
+    rewind.playback = None;
+    // Flaps mashed during the crash shouldn't fire on resume
+    flap_input.requested = false;
+    rewound.send(Rewound);
+}
+
+#[derive(Component)]
+struct RewindPrompt;
+
+fn update_rewind_prompt(
+    mut commands: Commands,
+    rewind: Res<Rewind>,
+    settings: Res<Settings>,
+    prompt: Query<Entity, With<RewindPrompt>>,
+) {
+    if !rewind.is_changed() {
+        return;
+    }
+    let waiting = rewind.holding() && rewind.playback.is_none();
+    if !waiting {
+        for e in &prompt {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+    if !prompt.is_empty() {
+        return;
+    }
+    let left = match rewind.left {
+        1 => "last one".to_string(),
+        left => format!("{left} left"),
+    };
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "Crashed!\n",
+                TextStyle {
+                    font_size: 56.0,
+                    color: Color::srgb(1.0, 0.0, 0.0),
+                    ..default()
+                },
+            ),
+            TextSection::new(
+                format!(
+                    "Backspace to rewind {REWIND_SECS:.0} seconds ({left})\n{} to give up",
+                    settings.controls.flap_hint()
+                ),
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(160.0),
+            left: Val::Px(WINDOW_W * 0.5 - 220.0),
+            ..default()
+        }),
+        RewindPrompt,
+    ));
+}
+
+fn hide_rewind_prompt(mut commands: Commands, prompt: Query<Entity, With<RewindPrompt>>) {
+    for e in &prompt {
+        commands.entity(e).despawn_recursive();
+    }
+}
@@ -0,0 +1,239 @@
+// Mutator roulette (Mutator roulette in the settings menu): every
+// STRETCH_POINTS points the wheel spins and a random mutator takes over the
+// run until the next spin, announced with a banner. Mutators are short-lived
+// twists on the run's rules, unlike modifiers, which last the whole run.
+//
+// Each mutator hooks into the run in two ways: numbers the physics and the
+// pipes read while it's on (Roulette::gravity_scale and friends), and apply
+// and revert for what it changes on the birds themselves. The wheel draws
+// from its own stream seeded from the run seed, so replays spin the same way.
+// A roulette run plays by rules of its own, so it doesn't count towards the
+// best score.
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::modifiers::Modifier;
+use crate::{
+    check_collisions_and_scoring, simulation_running, start_game, Bird, GameState, RestartDemo,
+    RunModifiers, RunRules, RunSeed, Score, WINDOW_W,
+};
+
+// Mixed into the run seed for the wheel's stream
+const ROULETTE_STREAM: u64 = 0x0b5e_55ed_0b5e_55ed;
+const STRETCH_POINTS: u32 = 10;
+const LOW_GRAVITY: f32 = 0.5;
+const FAST_PIPES: f32 = 1.35;
+const TINY_BIRD_SCALE: f32 = 0.6;
+const BANNER_SECS: f32 = 2.0;
+const BANNER_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+
+pub struct RoulettePlugin;
+
+impl Plugin for RoulettePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Menu), reset_roulette.after(start_game))
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_roulette.after(start_game),
+            )
+            .add_systems(OnEnter(GameState::Replay), reset_roulette.after(start_game))
+            .add_systems(
+                Update,
+                reset_roulette
+                    .after(start_game)
+                    .run_if(on_event::<RestartDemo>()),
+            )
+            .add_systems(
+                FixedUpdate,
+                spin_roulette
+                    .after(check_collisions_and_scoring)
+                    .run_if(simulation_running),
+            )
+            .add_systems(Update, fade_banner);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Mutator {
+    LowGravity,
+    FastPipes,
+    TinyBird,
+    // Gravity pulls up and flaps push down, without turning the view over
+    InvertedControls,
+}
+
+impl Mutator {
+    const ALL: [Mutator; 4] = [
+        Mutator::LowGravity,
+        Mutator::FastPipes,
+        Mutator::TinyBird,
+        Mutator::InvertedControls,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Mutator::LowGravity => "Low gravity",
+            Mutator::FastPipes => "Fast pipes",
+            Mutator::TinyBird => "Tiny bird",
+            Mutator::InvertedControls => "Inverted controls",
+        }
+    }
+
+    fn apply(self, birds: &mut Query<&mut Transform, With<Bird>>) {
+        if self == Mutator::TinyBird {
+            for mut tf in birds.iter_mut() {
+                tf.scale *= TINY_BIRD_SCALE;
+            }
+        }
+    }
+
+    fn revert(self, birds: &mut Query<&mut Transform, With<Bird>>) {
+        if self == Mutator::TinyBird {
+            for mut tf in birds.iter_mut() {
+                tf.scale /= TINY_BIRD_SCALE;
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Roulette {
+    active: bool,
+    rng: StdRng,
+    current: Option<Mutator>,
+    // Spins so far this run
+    spins: u32,
+}
+
+impl Default for Roulette {
+    fn default() -> Self {
+        Self {
+            active: false,
+            rng: StdRng::seed_from_u64(0),
+            current: None,
+            spins: 0,
+        }
+    }
+}
+
+impl Roulette {
+    // Multiplier on gravity
+    pub fn gravity_scale(&self) -> f32 {
+        match self.current {
+            Some(Mutator::LowGravity) => LOW_GRAVITY,
+            _ => 1.0,
+        }
+    }
+
+    // Multiplier on the pipe scroll speed
+    pub fn speed_scale(&self) -> f32 {
+        match self.current {
+            Some(Mutator::FastPipes) => FAST_PIPES,
+            _ => 1.0,
+        }
+    }
+
+    // -1 while the controls are inverted: gravity and flaps both flip
+    pub fn flip(&self) -> f32 {
+        match self.current {
+            Some(Mutator::InvertedControls) => -1.0,
+            _ => 1.0,
+        }
+    }
+}
+
+// The birds are spawned afresh for every run, so nothing needs reverting
+fn reset_roulette(
+    run_rules: Res<RunRules>,
+    run_seed: Res<RunSeed>,
+    mut roulette: ResMut<Roulette>,
+) {
+    *roulette = Roulette {
+        active: run_rules.0.roulette,
+        rng: StdRng::seed_from_u64(run_seed.0 ^ ROULETTE_STREAM),
+        ..default()
+    };
+}
+
+// A growth run sets the birds' size itself, so it never gets a tiny bird
+fn spin_roulette(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    score: Res<Score>,
+    run_modifiers: Res<RunModifiers>,
+    mut roulette: ResMut<Roulette>,
+    mut birds: Query<&mut Transform, With<Bird>>,
+    banners: Query<Entity, With<RouletteBanner>>,
+) {
+    if !roulette.active || score.0 < (roulette.spins + 1) * STRETCH_POINTS {
+        return;
+    }
+    // A flock can score several points at once
+    roulette.spins = score.0 / STRETCH_POINTS;
+
+    let previous = roulette.current;
+    let choices: Vec<Mutator> = Mutator::ALL
+        .into_iter()
+        .filter(|&m| Some(m) != previous)
+        .filter(|&m| m != Mutator::TinyBird || !run_modifiers.0.has(Modifier::Growth))
+        .collect();
+    let Some(&mutator) = choices.choose(&mut roulette.rng) else {
+        return;
+    };
+    if let Some(previous) = previous {
+        previous.revert(&mut birds);
+    }
+    mutator.apply(&mut birds);
+    roulette.current = Some(mutator);
+
+    // Not over the menu
+    if *state.get() == GameState::Menu {
+        return;
+    }
+    for e in &banners {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            format!("Roulette: {}!", mutator.label()),
+            TextStyle {
+                font_size: 40.0,
+                color: BANNER_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(100.0),
+            left: Val::Px(WINDOW_W * 0.5 - 200.0),
+            ..default()
+        }),
+        RouletteBanner(BANNER_SECS),
+    ));
+}
+
+// Seconds left on screen
+#[derive(Component)]
+struct RouletteBanner(f32);
+
+// Fades out over its last second
+fn fade_banner(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut banners: Query<(Entity, &mut RouletteBanner, &mut Text)>,
+) {
+    for (e, mut banner, mut text) in &mut banners {
+        banner.0 -= time.delta_seconds();
+        if banner.0 <= 0.0 {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        let alpha = banner.0.min(1.0);
+        for section in &mut text.sections {
+            section.style.color.set_alpha(alpha);
+        }
+    }
+}
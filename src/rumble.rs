@@ -0,0 +1,59 @@
+// Gamepad rumble: a strong jolt on crashing and a tiny pulse for each point,
+// scaled by the rumble settings
+
+use std::time::Duration;
+
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::{Crashed, PipePassed};
+
+const CRASH_RUMBLE_SECS: f32 = 0.4;
+const POINT_RUMBLE_SECS: f32 = 0.08;
+// Relative to the crash jolt
+const POINT_RUMBLE_STRENGTH: f32 = 0.3;
+
+pub struct RumblePlugin;
+
+impl Plugin for RumblePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, rumble_on_events);
+    }
+}
+
+fn rumble_on_events(
+    settings: Res<Settings>,
+    gamepads: Res<Gamepads>,
+    mut passed: EventReader<PipePassed>,
+    mut crashed: EventReader<Crashed>,
+    mut rumble: EventWriter<GamepadRumbleRequest>,
+) {
+    let scored = passed.read().count() > 0;
+    let died = crashed.read().count() > 0;
+    if !settings.rumble || (!scored && !died) {
+        return;
+    }
+
+    let (intensity, secs) = if died {
+        (
+            GamepadRumbleIntensity {
+                strong_motor: settings.rumble_strength,
+                weak_motor: settings.rumble_strength,
+            },
+            CRASH_RUMBLE_SECS,
+        )
+    } else {
+        (
+            GamepadRumbleIntensity::weak_motor(settings.rumble_strength * POINT_RUMBLE_STRENGTH),
+            POINT_RUMBLE_SECS,
+        )
+    };
+    for gamepad in gamepads.iter() {
+        rumble.send(GamepadRumbleRequest::Add {
+            gamepad,
+            intensity,
+            duration: Duration::from_secs_f32(secs),
+        });
+    }
+}
@@ -0,0 +1,216 @@
+// Screen reader support through AccessKit (bevy_a11y).
+//
+// Every UI text is exposed as static text, so menus, the settings list and the
+// score can be read out. State changes are announced through a live region,
+// worded to be spoken: "Game over, score twelve, best forty". Other modules can
+// announce things with the `Announce` event.
+
+use bevy::a11y::accesskit::{Live, NodeBuilder, Role};
+use bevy::a11y::{AccessibilityNode, AccessibilityRequested};
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::{update_best_score, BestScore, GameState, Score};
+
+pub struct ScreenReaderPlugin;
+
+impl Plugin for ScreenReaderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Announce>()
+            .add_systems(Startup, spawn_announcer)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                announce_game_over.after(update_best_score),
+            )
+            .add_systems(
+                Update,
+                (
+                    announce_state,
+                    announce_pause,
+                    expose_ui_text,
+                    show_announcements,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// Text to read out to screen reader users
+#[derive(Event)]
+pub struct Announce(pub String);
+
+// The live region announcements are read from
+#[derive(Component)]
+struct Announcer;
+
+fn spawn_announcer(mut commands: Commands) {
+    let mut node = NodeBuilder::new(Role::Status);
+    node.set_live(Live::Assertive);
+    commands.spawn((AccessibilityNode(node), Announcer));
+}
+
+fn show_announcements(
+    mut announcements: EventReader<Announce>,
+    mut announcer: Query<&mut AccessibilityNode, With<Announcer>>,
+) {
+    let Some(Announce(text)) = announcements.read().last() else {
+        return;
+    };
+    if let Ok(mut node) = announcer.get_single_mut() {
+        node.set_name(text.as_str());
+    }
+}
+
+// Keep the accessible name of every text (they are all UI) in step with the
+// screen. Nothing happens until a screen reader connects, so the HUD doesn't
+// allocate for everyone else.
+fn expose_ui_text(
+    mut commands: Commands,
+    requested: Res<AccessibilityRequested>,
+    mut was_requested: Local<bool>,
+    texts: Query<(Entity, Ref<Text>)>,
+) {
+    let just_connected = requested.get() && !*was_requested;
+    *was_requested = requested.get();
+    if !requested.get() {
+        return;
+    }
+    for (e, text) in &texts {
+        if !text.is_changed() && !just_connected {
+            continue;
+        }
+        let mut node = NodeBuilder::new(Role::StaticText);
+        node.set_name(
+            text.sections
+                .iter()
+                .map(|s| s.value.as_str())
+                .collect::<String>(),
+        );
+        commands.entity(e).try_insert(AccessibilityNode(node));
+    }
+}
+
+// --------------------------------------------
+// Announcements
+// --------------------------------------------
+
+fn announce_state(
+    state: Res<State<GameState>>,
+    settings: Res<Settings>,
+    mut announce: EventWriter<Announce>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    let text = match state.get() {
+        GameState::Menu => {
+            let flap = settings.controls.flap_hint();
+            announce.send(Announce(format!("Main menu. Press {flap} to start")));
+            return;
+        }
+        GameState::Playing => "Go",
+        GameState::Replay => "Watching replay",
+        // Announced with the score instead
+        GameState::GameOver => return,
+        GameState::Mods => "Mods menu",
+        GameState::Settings => "Settings menu",
+        GameState::ChallengeEntry => "Type a challenge code",
+        GameState::Lobby => "LAN races",
+        GameState::Tournament => "Tournament",
+        GameState::HotSeat => "Hot-seat: type the players' names",
+        GameState::Presets => "Challenge presets",
+        GameState::Shop => "Shop",
+        // Each line is announced as it shows
+        GameState::Story => return,
+    };
+    announce.send(Announce(text.to_string()));
+}
+
+fn announce_game_over(
+    score: Res<Score>,
+    best: Res<BestScore>,
+    mut announce: EventWriter<Announce>,
+) {
+    announce.send(Announce(format!(
+        "Game over, score {}, best {}",
+        spoken_number(score.0),
+        spoken_number(best.0)
+    )));
+}
+
+// Only pausing a run; photo mode stops time too, and announces itself
+fn announce_pause(
+    state: Res<State<GameState>>,
+    time: Res<Time<Virtual>>,
+    mut was_paused: Local<bool>,
+    mut announce: EventWriter<Announce>,
+) {
+    if time.is_paused() == *was_paused {
+        return;
+    }
+    *was_paused = time.is_paused();
+    if *state.get() != GameState::Playing {
+        return;
+    }
+    let text = if *was_paused { "Paused" } else { "Resumed" };
+    announce.send(Announce(text.to_string()));
+}
+
+// 42 -> "forty-two", so speech engines don't read digits one by one
+fn spoken_number(n: u32) -> String {
+    const ONES: [&str; 20] = [
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: [&str; 10] = [
+        "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+    const SCALES: [(u32, &str); 3] = [
+        (1_000_000_000, "billion"),
+        (1_000_000, "million"),
+        (1_000, "thousand"),
+    ];
+
+    for (scale, name) in SCALES {
+        if n >= scale {
+            let rest = n % scale;
+            let head = format!("{} {name}", spoken_number(n / scale));
+            return if rest == 0 {
+                head
+            } else {
+                format!("{head} {}", spoken_number(rest))
+            };
+        }
+    }
+    let (hundreds, tens, ones) = (n / 100, n / 10 % 10, n % 10);
+    if hundreds > 0 {
+        let head = format!("{} hundred", ONES[hundreds as usize]);
+        return match n % 100 {
+            0 => head,
+            rest => format!("{head} {}", spoken_number(rest)),
+        };
+    }
+    match (tens, ones) {
+        (0 | 1, _) => ONES[n as usize].to_string(),
+        (_, 0) => TENS[tens as usize].to_string(),
+        _ => format!("{}-{}", TENS[tens as usize], ONES[ones as usize]),
+    }
+}
@@ -0,0 +1,90 @@
+// Screenshot hotkey (F12)
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::tasks::IoTaskPool;
+use bevy::window::PrimaryWindow;
+
+use crate::toast::ShowToast;
+
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SavedScreenshots>()
+            .add_systems(Update, (screenshot_hotkey, report_screenshots));
+    }
+}
+
+// Filled as screenshots are written, on a task pool thread; drained into toasts
+#[derive(Resource, Default, Clone)]
+struct SavedScreenshots(Arc<Mutex<Vec<Result<PathBuf, String>>>>);
+
+// <Pictures>/Floopy Birb, or ./captures when the OS has no pictures folder
+pub fn captures_dir() -> PathBuf {
+    dirs::picture_dir()
+        .map(|dir| dir.join("Floopy Birb"))
+        .unwrap_or_else(|| PathBuf::from("captures"))
+}
+
+pub fn screenshot_hotkey(
+    input: Res<ButtonInput<KeyCode>>,
+    window_q: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    saved: Res<SavedScreenshots>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !input.just_pressed(KeyCode::F12) {
+        return;
+    }
+    let Ok(window) = window_q.get_single() else {
+        return;
+    };
+
+    let dir = captures_dir();
+    if let Err(err) = std::fs::create_dir_all(&dir) {
+        error!("Cannot create {}: {err}", dir.display());
+        toasts.send(ShowToast("Screenshot failed".to_string()));
+        return;
+    }
+    // Milliseconds, so presses in the same second don't overwrite each other
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S-%3f");
+    let path = dir.join(format!("floopybirb_{timestamp}.png"));
+
+    let saved = saved.clone();
+    let taken = screenshots.take_screenshot(window, move |img| {
+        // Encoding takes a while, so not on the render thread
+        IoTaskPool::get()
+            .spawn(async move {
+                let result = match img.try_into_dynamic() {
+                    Ok(img) => img
+                        .to_rgb8()
+                        .save(&path)
+                        .map(|()| path.clone())
+                        .map_err(|err| format!("{}: {err}", path.display())),
+                    Err(err) => Err(format!("{}: {err}", path.display())),
+                };
+                saved.0.lock().unwrap().push(result);
+            })
+            .detach();
+    });
+    // Only one capture per frame, which another press may already have taken
+    if taken.is_err() {
+        toasts.send(ShowToast("Screenshot failed".to_string()));
+    }
+}
+
+fn report_screenshots(saved: Res<SavedScreenshots>, mut toasts: EventWriter<ShowToast>) {
+    for result in saved.0.lock().unwrap().drain(..) {
+        match result {
+            Ok(path) => toasts.send(ShowToast(format!("Saved {}", path.display()))),
+            Err(err) => {
+                error!("Cannot save screenshot: {err}");
+                toasts.send(ShowToast("Screenshot failed".to_string()))
+            }
+        };
+    }
+}
@@ -0,0 +1,185 @@
+// Rhai scripting hooks (enabled with the `scripting` cargo feature)
+//
+// Every `*.rhai` file in `scripts/` is compiled at startup. A script can define
+// any of these hooks, which run in file name order:
+//
+//   fn on_spawn_pipes(pipes)  - `pipes` is a map with `gap_center`, `gap_height`,
+//                               `tick` (fixed ticks into the run) and `score`;
+//                               return it (changed or not) to reshape the gap
+//   fn on_score(score)        - after each pipe passed
+//   fn on_death(score)        - when the bird crashes
+//
+// Scripts can call `toast(text)` to show a message, and `print` goes to the log.
+// Replays and codes don't record scripts, so while one defines `on_spawn_pipes`
+// runs aren't ranked and can't be saved or shared. A hook that runs too long or
+// recurses too deep is stopped with an error rather than freezing the game.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use rhai::{Dynamic, Engine, FuncArgs, Map, Scope, AST};
+
+use crate::toast::ShowToast;
+use crate::{Crashed, PipePassed, Score, BIRD_SIZE, GAP_MARGIN, WINDOW_H};
+
+const SCRIPTS_DIR: &str = "scripts";
+const SCRIPT_EXTENSION: &str = "rhai";
+// Per hook call; plenty for reshaping a gap, and a runaway loop stops in well
+// under a frame
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Scripts::load(Path::new(SCRIPTS_DIR)))
+            .add_systems(
+                Update,
+                (run_score_hooks, run_death_hooks, show_script_toasts),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct Scripts {
+    engine: Engine,
+    // (file name, compiled script)
+    scripts: Vec<(String, AST)>,
+    // Filled by `toast()` calls, drained into ShowToast events every frame
+    toasts: Arc<Mutex<Vec<String>>>,
+}
+
+impl Scripts {
+    fn load(dir: &Path) -> Self {
+        let toasts = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+        engine.on_print(|text| info!("[script] {text}"));
+        let queue = toasts.clone();
+        engine.register_fn("toast", move |text: &str| {
+            queue.lock().unwrap().push(text.to_string());
+        });
+
+        let mut scripts = Vec::new();
+        for path in script_files(dir) {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            match engine.compile_file(path) {
+                Ok(ast) => {
+                    info!("Loaded script {name}");
+                    scripts.push((name, ast));
+                }
+                Err(err) => error!("Cannot load script {name}: {err}"),
+            }
+        }
+
+        Self {
+            engine,
+            scripts,
+            toasts,
+        }
+    }
+
+    // Call `hook` in every script that defines it, in order
+    fn call(&self, hook: &str, args: impl FuncArgs + Clone) {
+        for (name, ast) in &self.scripts {
+            if !defines(ast, hook) {
+                continue;
+            }
+            profile_span!("script_hook", hook, script = name.as_str());
+            let result = self
+                .engine
+                .call_fn::<Dynamic>(&mut Scope::new(), ast, hook, args.clone());
+            if let Err(err) = result {
+                error!("{name}: {hook} failed: {err}");
+            }
+        }
+    }
+
+    // Whether any script moves the gaps, making a course the seed alone doesn't
+    pub fn reshapes_course(&self) -> bool {
+        self.scripts
+            .iter()
+            .any(|(_, ast)| defines(ast, "on_spawn_pipes"))
+    }
+
+    // Let scripts move and resize the next gap. Returns the new (center, height),
+    // kept on screen and wide enough for the bird.
+    pub fn on_spawn_pipes(&self, center: f32, height: f32, tick: u32, score: u32) -> (f32, f32) {
+        let mut pipes = Map::new();
+        pipes.insert("gap_center".into(), Dynamic::from_float(center as f64));
+        pipes.insert("gap_height".into(), Dynamic::from_float(height as f64));
+        pipes.insert("tick".into(), Dynamic::from_int(tick as i64));
+        pipes.insert("score".into(), Dynamic::from_int(score as i64));
+
+        for (name, ast) in &self.scripts {
+            if !defines(ast, "on_spawn_pipes") {
+                continue;
+            }
+            let result = self.engine.call_fn::<Dynamic>(
+                &mut Scope::new(),
+                ast,
+                "on_spawn_pipes",
+                (pipes.clone(),),
+            );
+            match result.map(Dynamic::try_cast::<Map>) {
+                Ok(Some(map)) => pipes = map,
+                Ok(None) => error!("{name}: on_spawn_pipes must return the pipes map"),
+                Err(err) => error!("{name}: on_spawn_pipes failed: {err}"),
+            }
+        }
+
+        let read = |key: &str, fallback: f32| {
+            pipes
+                .get(key)
+                .and_then(|v| {
+                    v.as_float()
+                        .ok()
+                        .or_else(|| v.as_int().ok().map(|i| i as f64))
+                })
+                .map_or(fallback, |v| v as f32)
+        };
+        let half_h = WINDOW_H * 0.5;
+        let height =
+            read("gap_height", height).clamp(BIRD_SIZE.y * 2.0, WINDOW_H - GAP_MARGIN * 2.0);
+        let limit = half_h - GAP_MARGIN - height * 0.5;
+        let center = read("gap_center", center).clamp(-limit, limit);
+        (center, height)
+    }
+}
+
+fn defines(ast: &AST, hook: &str) -> bool {
+    ast.iter_functions().any(|f| f.name == hook)
+}
+
+fn script_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == SCRIPT_EXTENSION))
+        .collect();
+    files.sort();
+    files
+}
+
+fn run_score_hooks(scripts: Res<Scripts>, score: Res<Score>, mut passed: EventReader<PipePassed>) {
+    for _ in passed.read() {
+        scripts.call("on_score", (score.0 as i64,));
+    }
+}
+
+fn run_death_hooks(scripts: Res<Scripts>, score: Res<Score>, mut crashed: EventReader<Crashed>) {
+    for _ in crashed.read() {
+        scripts.call("on_death", (score.0 as i64,));
+    }
+}
+
+fn show_script_toasts(scripts: Res<Scripts>, mut toasts: EventWriter<ShowToast>) {
+    for text in scripts.toasts.lock().unwrap().drain(..) {
+        toasts.send(ShowToast(text));
+    }
+}
@@ -0,0 +1,81 @@
+// Session summary: when the game closes after any runs were flown, what this
+// session came to (runs played, best score, flaps, pipes and time spent flying)
+// goes to the log and to last_session.txt next to the settings, for a look
+// back at how it went.
+//
+// Runs, flaps, pipes and the best score are the ranked ones the session stats
+// count; time played is every run's.
+
+use std::path::PathBuf;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::timer::format_time;
+use crate::{BestScore, GameState, Stats};
+
+pub struct SessionPlugin;
+
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Session {
+            started: chrono::Local::now(),
+            seconds_played: 0.0,
+        })
+        .add_systems(
+            Update,
+            count_time_played.run_if(in_state(GameState::Playing)),
+        )
+        // Last, so every exit sent this frame has been sent
+        .add_systems(Last, write_session_summary);
+    }
+}
+
+#[derive(Resource)]
+struct Session {
+    started: chrono::DateTime<chrono::Local>,
+    // Flying, not counting pauses
+    seconds_played: f32,
+}
+
+fn session_path() -> PathBuf {
+    crate::data_file("last_session.txt")
+}
+
+fn count_time_played(time: Res<Time>, mut session: ResMut<Session>) {
+    session.seconds_played += time.delta_seconds();
+}
+
+fn summary(session: &Session, stats: &Stats, best: &BestScore) -> String {
+    format!(
+        "Session {} to {}\nRuns played: {}\nBest score: {}\nFlaps: {}\nPipes passed: {}\nTime played: {}\n",
+        session.started.format("%Y-%m-%d %H:%M"),
+        chrono::Local::now().format("%H:%M"),
+        stats.runs_played,
+        best.0,
+        stats.flaps,
+        stats.pipes_passed,
+        format_time(session.seconds_played)
+    )
+}
+
+fn write_session_summary(
+    mut exit: EventReader<AppExit>,
+    session: Res<Session>,
+    stats: Res<Stats>,
+    best: Res<BestScore>,
+) {
+    if exit.read().next().is_none() || session.seconds_played == 0.0 {
+        return;
+    }
+    let summary = summary(&session, &stats, &best);
+    info!("{summary}");
+    let path = session_path();
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, &summary));
+    if let Err(err) = result {
+        error!("Cannot save session summary: {err}");
+    }
+}
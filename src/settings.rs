@@ -0,0 +1,803 @@
+// Player settings, saved between launches, and the settings menu (Tab on the
+// main menu).
+//
+// The file is plain `key = value` lines; unknown keys are ignored so older and
+// newer versions of the game can share it.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::achievements::AchievementUnlocked;
+use crate::controls::ControlPreset;
+use crate::gamepad::RebindGamepad;
+use crate::history::{ExportFormat, ExportStats};
+use crate::modifiers::{Modifier, Modifiers};
+use crate::rewind::MAX_REWINDS;
+use crate::screen_reader::Announce;
+use crate::speed::MIN_GAME_SPEED;
+use crate::speedrun::TARGET_SCORE;
+use crate::toast::ShowToast;
+use crate::{GameState, WINDOW_W};
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct Settings {
+    pub rumble: bool,
+    // 0.1 ..= 1.0
+    pub rumble_strength: f32,
+    // MIN_GAME_SPEED ..= 1.0; see speed.rs
+    pub game_speed: f32,
+    pub hitbox: Hitbox,
+    pub controls: ControlPreset,
+    // One of Modifier::MODES, or None for the normal game
+    pub mode: Option<Modifier>,
+    // Modes earned so far, of those that need unlocking
+    pub unlocked: Modifiers,
+    // What Export run history writes
+    pub export_format: ExportFormat,
+    // Elapsed time and pace in the corner (see timer.rs)
+    pub run_timer: bool,
+    // Modes scored by distance rather than pipes (see distance.rs); None is
+    // the normal game
+    pub distance_modes: Vec<Option<Modifier>>,
+    // Play the music tracks in a random order (see music.rs)
+    pub shuffle_music: bool,
+    // A desktop notification on a new best score (see notify.rs)
+    pub best_notifications: bool,
+    // Every run a race to 50 points, with splits (see speedrun.rs)
+    pub speedrun: bool,
+    // A random mutator every few points (see roulette.rs)
+    pub roulette: bool,
+    // Rewinds after a crash each run, 0 ..= MAX_REWINDS (see rewind.rs)
+    pub rewinds: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            rumble: true,
+            rumble_strength: 0.7,
+            game_speed: 1.0,
+            hitbox: Hitbox::default(),
+            controls: ControlPreset::default(),
+            mode: None,
+            unlocked: Modifiers::NONE,
+            export_format: ExportFormat::default(),
+            run_timer: false,
+            distance_modes: Vec::new(),
+            shuffle_music: false,
+            best_notifications: false,
+            speedrun: false,
+            roulette: false,
+            rewinds: 0,
+        }
+    }
+}
+
+impl Settings {
+    fn to_text(&self) -> String {
+        format!(
+            "rumble = {}\nrumble_strength = {:.1}\ngame_speed = {:.1}\nhitbox = {}\ncontrols = {}\nmode = {}\nunlocked_modes = {}\nexport_format = {}\nrun_timer = {}\ndistance_scoring = {}\nshuffle_music = {}\nbest_notifications = {}\nspeedrun = {}\nroulette = {}\nrewinds = {}\n",
+            self.rumble,
+            self.rumble_strength,
+            self.game_speed,
+            self.hitbox.name(),
+            self.controls.name(),
+            mode_name(self.mode),
+            Modifier::MODES
+                .into_iter()
+                .filter(|&m| self.unlocked.has(m))
+                .map(Modifier::name)
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.export_format.name(),
+            self.run_timer,
+            self.distance_modes
+                .iter()
+                .map(|&m| mode_name(m))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.shuffle_music,
+            self.best_notifications,
+            self.speedrun,
+            self.roulette,
+            self.rewinds
+        )
+    }
+
+    // Decay runs live and die by their score, so it always shows
+    pub fn scores_distance(&self, mode: Option<Modifier>) -> bool {
+        mode != Some(Modifier::Decay) && self.distance_modes.contains(&mode)
+    }
+
+    pub fn mode_unlocked(&self, mode: Modifier) -> bool {
+        mode.unlocked_by().is_none() || self.unlocked.has(mode)
+    }
+
+    // What the mode adds to a run that isn't a replay or a challenge
+    pub fn modifiers(&self) -> Modifiers {
+        self.mode
+            .map_or(Modifiers::NONE, |mode| Modifiers::NONE.with(mode))
+    }
+
+    // Returns false for keys that aren't ours
+    fn apply(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "rumble" => self.rumble = value == "true",
+            "rumble_strength" => {
+                if let Ok(v) = value.parse::<f32>() {
+                    self.rumble_strength = v.clamp(0.1, 1.0);
+                }
+            }
+            "game_speed" => {
+                if let Ok(v) = value.parse::<f32>() {
+                    self.game_speed = v.clamp(MIN_GAME_SPEED, 1.0);
+                }
+            }
+            "hitbox" => {
+                if let Some(hitbox) = Hitbox::ALL.into_iter().find(|h| h.name() == value) {
+                    self.hitbox = hitbox;
+                }
+            }
+            "controls" => {
+                if let Some(preset) = ControlPreset::ALL.into_iter().find(|p| p.name() == value) {
+                    self.controls = preset;
+                }
+            }
+            "mode" => self.mode = mode_from_name(value),
+            "distance_scoring" => {
+                self.distance_modes = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .filter_map(|name| match name {
+                        "normal" => Some(None),
+                        name => mode_from_name(name).map(Some),
+                    })
+                    .collect();
+            }
+            "unlocked_modes" => {
+                self.unlocked = value
+                    .split(',')
+                    .filter_map(|name| {
+                        Modifier::MODES
+                            .into_iter()
+                            .find(|m| m.name() == name.trim())
+                    })
+                    .fold(Modifiers::NONE, Modifiers::with);
+            }
+            "run_timer" => self.run_timer = value == "true",
+            "shuffle_music" => self.shuffle_music = value == "true",
+            "best_notifications" => self.best_notifications = value == "true",
+            "speedrun" => self.speedrun = value == "true",
+            "roulette" => self.roulette = value == "true",
+            "rewinds" => {
+                if let Ok(v) = value.parse::<u32>() {
+                    self.rewinds = v.min(MAX_REWINDS);
+                }
+            }
+            "export_format" => {
+                if let Some(format) = ExportFormat::ALL.into_iter().find(|f| f.name() == value) {
+                    self.export_format = format;
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+// For the settings file: a mode's name, or "normal"
+fn mode_name(mode: Option<Modifier>) -> &'static str {
+    mode.map_or("normal", Modifier::name)
+}
+
+fn mode_from_name(name: &str) -> Option<Modifier> {
+    Modifier::MODES.into_iter().find(|m| m.name() == name)
+}
+
+// How much smaller than the sprite the bird's collision box is. The sprite has
+// transparent corners, so a little shrink feels fairer rather than easier.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Hitbox {
+    Strict,
+    #[default]
+    Normal,
+    Forgiving,
+}
+
+impl Hitbox {
+    pub const ALL: [Hitbox; 3] = [Hitbox::Strict, Hitbox::Normal, Hitbox::Forgiving];
+
+    // Fraction taken off each side of the box
+    pub fn shrink(self) -> f32 {
+        match self {
+            Hitbox::Strict => 0.0,
+            Hitbox::Normal => 0.1,
+            Hitbox::Forgiving => 0.25,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Hitbox::Strict => "strict",
+            Hitbox::Normal => "normal",
+            Hitbox::Forgiving => "forgiving",
+        }
+    }
+
+    fn label(self) -> String {
+        let name = match self {
+            Hitbox::Strict => "Strict",
+            Hitbox::Normal => "Normal",
+            Hitbox::Forgiving => "Forgiving",
+        };
+        format!("{name} (-{:.0}%)", self.shrink() * 100.0)
+    }
+}
+
+// Presentation options for players with visual or motion sensitivities. Every
+// visual effect checks these; gameplay never does, so they don't affect replays.
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+pub struct AccessibilitySettings {
+    pub palette: Palette,
+    pub pipe_style: PipeStyle,
+    // Dark background, bright outlined pipes and bird, bigger text
+    pub high_contrast: bool,
+    // No screen shake, zoom punches, flashes, parallax or particle bursts
+    pub reduced_motion: bool,
+    // Show what the sound effects signal: flaps, points and close calls
+    pub visual_cues: bool,
+}
+
+impl AccessibilitySettings {
+    fn to_text(&self) -> String {
+        format!(
+            "palette = {}\npipe_style = {}\nhigh_contrast = {}\nreduced_motion = {}\nvisual_cues = {}\n",
+            self.palette.name(),
+            self.pipe_style.name(),
+            self.high_contrast,
+            self.reduced_motion,
+            self.visual_cues
+        )
+    }
+
+    fn apply(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "palette" => {
+                if let Some(palette) = Palette::ALL.into_iter().find(|p| p.name() == value) {
+                    self.palette = palette;
+                }
+            }
+            "pipe_style" => {
+                if let Some(style) = PipeStyle::ALL.into_iter().find(|p| p.name() == value) {
+                    self.pipe_style = style;
+                }
+            }
+            "high_contrast" => self.high_contrast = value == "true",
+            "reduced_motion" => self.reduced_motion = value == "true",
+            "visual_cues" => self.visual_cues = value == "true",
+            _ => return false,
+        }
+        true
+    }
+}
+
+// Pipe colors; all but Classic are from the Okabe-Ito colorblind-safe set
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Palette {
+    #[default]
+    Classic,
+    Orange,
+    Blue,
+    Yellow,
+}
+
+impl Palette {
+    const ALL: [Palette; 4] = [
+        Palette::Classic,
+        Palette::Orange,
+        Palette::Blue,
+        Palette::Yellow,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Palette::Classic => "classic",
+            Palette::Orange => "orange",
+            Palette::Blue => "blue",
+            Palette::Yellow => "yellow",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Palette::Classic => "Classic",
+            Palette::Orange => "Orange",
+            Palette::Blue => "Blue",
+            Palette::Yellow => "Yellow",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PipeStyle {
+    #[default]
+    Plain,
+    Outline,
+    Stripes,
+}
+
+impl PipeStyle {
+    const ALL: [PipeStyle; 3] = [PipeStyle::Plain, PipeStyle::Outline, PipeStyle::Stripes];
+
+    fn name(self) -> &'static str {
+        match self {
+            PipeStyle::Plain => "plain",
+            PipeStyle::Outline => "outline",
+            PipeStyle::Stripes => "stripes",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            PipeStyle::Plain => "Plain",
+            PipeStyle::Outline => "Outlined",
+            PipeStyle::Stripes => "Striped",
+        }
+    }
+}
+
+// Step from the normal game through the unlocked modes and back
+fn cycle_mode(settings: &Settings, step: i32) -> Option<Modifier> {
+    let current = settings.mode;
+    let options: Vec<Option<Modifier>> = std::iter::once(None)
+        .chain(
+            Modifier::MODES
+                .into_iter()
+                .filter(|&m| settings.mode_unlocked(m))
+                .map(Some),
+        )
+        .collect();
+    let i = options.iter().position(|&m| m == current).unwrap_or(0) as i32;
+    options[(i + step).rem_euclid(options.len() as i32) as usize]
+}
+
+// Step through `all` from `current`, wrapping around
+fn cycle<T: Copy + PartialEq, const N: usize>(all: [T; N], current: T, step: i32) -> T {
+    let i = all.iter().position(|&v| v == current).unwrap_or(0) as i32;
+    all[(i + step).rem_euclid(N as i32) as usize]
+}
+
+fn load() -> (Settings, AccessibilitySettings) {
+    let mut settings = Settings::default();
+    let mut accessibility = AccessibilitySettings::default();
+    let text = std::fs::read_to_string(settings_path()).unwrap_or_default();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if !settings.apply(key, value) {
+            accessibility.apply(key, value);
+        }
+    }
+    (settings, accessibility)
+}
+
+pub fn save(settings: &Settings, accessibility: &AccessibilitySettings) -> std::io::Result<()> {
+    let path = settings_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(
+        path,
+        format!("{}{}", settings.to_text(), accessibility.to_text()),
+    )
+}
+
+fn settings_path() -> PathBuf {
+    crate::data_file("settings.txt")
+}
+
+// One line of the settings menu
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingRow {
+    Controls,
+    ControllerButtons,
+    GameSpeed,
+    Hitbox,
+    Mode,
+    Scoring,
+    RunTimer,
+    Speedrun,
+    Roulette,
+    Rewinds,
+    Music,
+    BestNotifications,
+    Rumble,
+    RumbleStrength,
+    Palette,
+    PipeStyle,
+    HighContrast,
+    ReducedMotion,
+    VisualCues,
+    ExportStats,
+}
+
+impl SettingRow {
+    const ALL: [SettingRow; 20] = [
+        SettingRow::Controls,
+        SettingRow::ControllerButtons,
+        SettingRow::GameSpeed,
+        SettingRow::Hitbox,
+        SettingRow::Mode,
+        SettingRow::Scoring,
+        SettingRow::RunTimer,
+        SettingRow::Speedrun,
+        SettingRow::Roulette,
+        SettingRow::Rewinds,
+        SettingRow::Music,
+        SettingRow::BestNotifications,
+        SettingRow::Rumble,
+        SettingRow::RumbleStrength,
+        SettingRow::Palette,
+        SettingRow::PipeStyle,
+        SettingRow::HighContrast,
+        SettingRow::ReducedMotion,
+        SettingRow::VisualCues,
+        SettingRow::ExportStats,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SettingRow::Controls => "Controls",
+            SettingRow::ControllerButtons => "Controller buttons",
+            SettingRow::GameSpeed => "Game speed",
+            SettingRow::Hitbox => "Hitbox",
+            SettingRow::Mode => "Mode",
+            SettingRow::Scoring => "Scoring",
+            SettingRow::RunTimer => "Run timer",
+            SettingRow::Speedrun => "Speedrun",
+            SettingRow::Roulette => "Mutator roulette",
+            SettingRow::Rewinds => "Rewinds",
+            SettingRow::Music => "Music",
+            SettingRow::BestNotifications => "New best notification",
+            SettingRow::Rumble => "Controller rumble",
+            SettingRow::RumbleStrength => "Rumble strength",
+            SettingRow::Palette => "Pipe colors",
+            SettingRow::PipeStyle => "Pipe pattern",
+            SettingRow::HighContrast => "High contrast",
+            SettingRow::ReducedMotion => "Reduced motion",
+            SettingRow::VisualCues => "Visual sound cues",
+            SettingRow::ExportStats => "Export run history",
+        }
+    }
+
+    fn value(self, settings: &Settings, accessibility: &AccessibilitySettings) -> String {
+        match self {
+            SettingRow::GameSpeed => {
+                let speed = format!("{:.0}%", settings.game_speed * 100.0);
+                if settings.game_speed < 1.0 {
+                    speed + " (practice)"
+                } else {
+                    speed
+                }
+            }
+            SettingRow::Controls => settings.controls.label().to_string(),
+            SettingRow::ControllerButtons => "Enter to rebind".to_string(),
+            SettingRow::Hitbox => settings.hitbox.label(),
+            SettingRow::Mode => settings.mode.map_or("Normal", Modifier::label).to_string(),
+            SettingRow::Scoring => format!(
+                "{} ({} mode)",
+                if settings.scores_distance(settings.mode) {
+                    "Distance"
+                } else {
+                    "Pipes"
+                },
+                settings.mode.map_or("Normal", Modifier::label)
+            ),
+            SettingRow::RunTimer => on_off(settings.run_timer).to_string(),
+            SettingRow::Speedrun => {
+                if settings.speedrun {
+                    format!("Race to {TARGET_SCORE}")
+                } else {
+                    "Off".to_string()
+                }
+            }
+            SettingRow::Roulette => on_off(settings.roulette).to_string(),
+            SettingRow::Rewinds => match settings.rewinds {
+                0 => "Off".to_string(),
+                rewinds => format!("{rewinds} per run"),
+            },
+            SettingRow::Music => if settings.shuffle_music {
+                "Shuffled"
+            } else {
+                "In order"
+            }
+            .to_string(),
+            SettingRow::BestNotifications => on_off(settings.best_notifications).to_string(),
+            SettingRow::Rumble => on_off(settings.rumble).to_string(),
+            SettingRow::RumbleStrength => format!("{:.0}%", settings.rumble_strength * 100.0),
+            SettingRow::Palette => accessibility.palette.label().to_string(),
+            SettingRow::PipeStyle => accessibility.pipe_style.label().to_string(),
+            SettingRow::HighContrast => on_off(accessibility.high_contrast).to_string(),
+            SettingRow::ReducedMotion => on_off(accessibility.reduced_motion).to_string(),
+            SettingRow::VisualCues => on_off(accessibility.visual_cues).to_string(),
+            SettingRow::ExportStats => {
+                format!("{} (Enter to export)", settings.export_format.label())
+            }
+        }
+    }
+
+    // Step the value left (-1) or right (+1)
+    fn adjust(self, settings: &mut Settings, accessibility: &mut AccessibilitySettings, step: i32) {
+        match self {
+            SettingRow::GameSpeed => {
+                settings.game_speed =
+                    (settings.game_speed + 0.1 * step as f32).clamp(MIN_GAME_SPEED, 1.0);
+            }
+            SettingRow::Controls => {
+                settings.controls = cycle(ControlPreset::ALL, settings.controls, step);
+            }
+            // Rebound by pressing Enter, see settings_input
+            SettingRow::ControllerButtons => {}
+            SettingRow::Hitbox => settings.hitbox = cycle(Hitbox::ALL, settings.hitbox, step),
+            SettingRow::Mode => settings.mode = cycle_mode(settings, step),
+            // For the mode picked above
+            SettingRow::Scoring => {
+                let mode = settings.mode;
+                if settings.scores_distance(mode) {
+                    settings.distance_modes.retain(|&m| m != mode);
+                } else {
+                    settings.distance_modes.push(mode);
+                }
+            }
+            SettingRow::RunTimer => settings.run_timer = !settings.run_timer,
+            SettingRow::Speedrun => settings.speedrun = !settings.speedrun,
+            SettingRow::Roulette => settings.roulette = !settings.roulette,
+            SettingRow::Rewinds => {
+                settings.rewinds =
+                    (settings.rewinds as i32 + step).rem_euclid(MAX_REWINDS as i32 + 1) as u32;
+            }
+            SettingRow::Music => settings.shuffle_music = !settings.shuffle_music,
+            SettingRow::BestNotifications => {
+                settings.best_notifications = !settings.best_notifications;
+            }
+            SettingRow::Rumble => settings.rumble = !settings.rumble,
+            SettingRow::RumbleStrength => {
+                settings.rumble_strength =
+                    (settings.rumble_strength + 0.1 * step as f32).clamp(0.1, 1.0);
+            }
+            SettingRow::Palette => {
+                accessibility.palette = cycle(Palette::ALL, accessibility.palette, step);
+            }
+            SettingRow::PipeStyle => {
+                accessibility.pipe_style = cycle(PipeStyle::ALL, accessibility.pipe_style, step);
+            }
+            SettingRow::HighContrast => accessibility.high_contrast = !accessibility.high_contrast,
+            SettingRow::ReducedMotion => {
+                accessibility.reduced_motion = !accessibility.reduced_motion;
+            }
+            SettingRow::VisualCues => accessibility.visual_cues = !accessibility.visual_cues,
+            SettingRow::ExportStats => {
+                settings.export_format = cycle(ExportFormat::ALL, settings.export_format, step);
+            }
+        }
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let (settings, accessibility) = load();
+        app.insert_resource(settings)
+            .insert_resource(accessibility)
+            .init_resource::<SelectedRow>()
+            .add_systems(Update, open_settings.run_if(in_state(GameState::Menu)))
+            .add_systems(Update, unlock_modes)
+            .add_systems(OnEnter(GameState::Settings), show_settings_menu)
+            .add_systems(
+                OnExit(GameState::Settings),
+                (save_settings, despawn_settings_menu),
+            )
+            .add_systems(
+                Update,
+                (settings_input, update_settings_menu)
+                    .chain()
+                    .run_if(in_state(GameState::Settings)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct SelectedRow(usize);
+
+#[derive(Component)]
+struct SettingsUI;
+
+#[derive(Component)]
+struct SettingsList;
+
+fn open_settings(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if input.just_pressed(KeyCode::Tab) {
+        next_state.set(GameState::Settings);
+    }
+}
+
+fn show_settings_menu(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "Settings",
+            TextStyle {
+                font_size: 56.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(WINDOW_W * 0.5 - 100.0),
+            ..default()
+        }),
+        SettingsUI,
+    ));
+    // Filled in by update_settings_menu
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(120.0),
+            left: Val::Px(120.0),
+            ..default()
+        }),
+        SettingsUI,
+        SettingsList,
+    ));
+}
+
+fn despawn_settings_menu(mut commands: Commands, q: Query<Entity, With<SettingsUI>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn save_settings(
+    settings: Res<Settings>,
+    accessibility: Res<AccessibilitySettings>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if let Err(err) = save(&settings, &accessibility) {
+        error!("Cannot save settings: {err}");
+        toasts.send(ShowToast("Couldn't save settings".to_string()));
+    }
+}
+
+// Earning an achievement can unlock a mode, which is saved straight away
+fn unlock_modes(
+    mut events: EventReader<AchievementUnlocked>,
+    mut settings: ResMut<Settings>,
+    accessibility: Res<AccessibilitySettings>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for AchievementUnlocked(achievement) in events.read() {
+        for mode in Modifier::MODES {
+            if mode.unlocked_by() != Some(*achievement) || settings.unlocked.has(mode) {
+                continue;
+            }
+            settings.unlocked = settings.unlocked.with(mode);
+            toasts.send(ShowToast(format!(
+                "{} mode unlocked! Pick it under Mode in the settings",
+                mode.label()
+            )));
+            if let Err(err) = save(&settings, &accessibility) {
+                error!("Cannot save settings: {err}");
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn settings_input(
+    input: Res<ButtonInput<KeyCode>>,
+    mut selected: ResMut<SelectedRow>,
+    mut settings: ResMut<Settings>,
+    mut accessibility: ResMut<AccessibilitySettings>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut announce: EventWriter<Announce>,
+    mut export: EventWriter<ExportStats>,
+    mut rebind: EventWriter<RebindGamepad>,
+) {
+    if input.just_pressed(KeyCode::Escape) || input.just_pressed(KeyCode::Tab) {
+        next_state.set(GameState::Menu);
+        return;
+    }
+
+    let rows = SettingRow::ALL.len();
+    let mut changed = false;
+    if input.just_pressed(KeyCode::ArrowUp) {
+        selected.0 = (selected.0 + rows - 1) % rows;
+        changed = true;
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        selected.0 = (selected.0 + 1) % rows;
+        changed = true;
+    }
+
+    let row = SettingRow::ALL[selected.0];
+    // Enter on the export row exports, rather than changing the format
+    if row == SettingRow::ExportStats && input.just_pressed(KeyCode::Enter) {
+        export.send(ExportStats(settings.export_format));
+        return;
+    }
+    // Likewise the controller buttons, which are set by pressing them
+    if row == SettingRow::ControllerButtons && input.just_pressed(KeyCode::Enter) {
+        rebind.send(RebindGamepad);
+        return;
+    }
+    if input.just_pressed(KeyCode::ArrowLeft) {
+        row.adjust(&mut settings, &mut accessibility, -1);
+        changed = true;
+    } else if input.just_pressed(KeyCode::ArrowRight) || input.just_pressed(KeyCode::Enter) {
+        row.adjust(&mut settings, &mut accessibility, 1);
+        changed = true;
+    }
+
+    // The list is one block of text, so read out just the row that changed
+    if changed {
+        announce.send(Announce(format!(
+            "{}: {}",
+            row.label(),
+            row.value(&settings, &accessibility)
+        )));
+    }
+}
+
+fn update_settings_menu(
+    settings: Res<Settings>,
+    accessibility: Res<AccessibilitySettings>,
+    selected: Res<SelectedRow>,
+    mut q: Query<&mut Text, With<SettingsList>>,
+    added: Query<(), Added<SettingsList>>,
+) {
+    if !settings.is_changed()
+        && !accessibility.is_changed()
+        && !selected.is_changed()
+        && added.is_empty()
+    {
+        return;
+    }
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+
+    profile_span!("rebuild_settings_list");
+    let mut list = String::new();
+    for (i, row) in SettingRow::ALL.iter().enumerate() {
+        let cursor = if i == selected.0 { ">" } else { " " };
+        list.push_str(&format!(
+            "{cursor} {}: {}\n",
+            row.label(),
+            row.value(&settings, &accessibility)
+        ));
+    }
+    list.push_str("\nUp/Down to pick, Left/Right to change, Esc to go back");
+    text.sections[0].value = list;
+}
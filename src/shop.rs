@@ -0,0 +1,334 @@
+// Shop (S on the main menu): spend the wallet's coins on cosmetics, and put on
+// the ones already owned. Number keys buy an item, or put it on or take it off
+// once it's owned; buying one puts it straight on.
+//
+// Skins tint the bird (see wallet.rs) and pipe colors recolor the pipes (see
+// theme.rs). Trails and death effects are drawn here, from the bird's place on
+// screen, and are left out with reduced motion on.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::modifiers::Modifier;
+use crate::settings::AccessibilitySettings;
+use crate::toast::ShowToast;
+use crate::wallet::{self, Cosmetic, Slot, Wallet};
+use crate::{simulation_running, Course, Crashed, GameState, LeadBird, RunModifiers, WINDOW_W};
+
+// Seconds between trail puffs, and how long each one lasts
+const TRAIL_INTERVAL: f32 = 0.04;
+const TRAIL_SECS: f32 = 0.45;
+const TRAIL_SIZE: f32 = 7.0;
+const SPARK_TRAIL_COLOR: Color = Color::srgb(1.0, 0.9, 0.5);
+// The rainbow trail goes once round the hues in this many seconds
+const RAINBOW_PERIOD: f32 = 1.5;
+const BURST_PIECES: usize = 28;
+const BURST_SPEED: f32 = 220.0; // px / s at most
+const BURST_SECS: f32 = 0.9;
+const BURST_GRAVITY: f32 = 400.0; // px / s^2, so pieces fall as they fade
+const FEATHER_COLOR: Color = Color::srgb(0.98, 0.95, 0.85);
+const FEATHER_SIZE: Vec2 = Vec2::new(10.0, 4.0);
+const CONFETTI_SIZE: Vec2 = Vec2::new(6.0, 6.0);
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, open_shop.run_if(in_state(GameState::Menu)))
+            .add_systems(OnEnter(GameState::Shop), show_shop)
+            .add_systems(OnExit(GameState::Shop), despawn_shop)
+            .add_systems(
+                Update,
+                (shop_input, update_shop_list)
+                    .chain()
+                    .run_if(in_state(GameState::Shop)),
+            )
+            .add_systems(
+                Update,
+                (
+                    spawn_trail.run_if(simulation_running),
+                    spawn_death_effect,
+                    fade_particles,
+                ),
+            );
+    }
+}
+
+// --------------------------------------------
+// Shop screen
+// --------------------------------------------
+
+#[derive(Component)]
+struct ShopUI;
+
+#[derive(Component)]
+struct ShopList;
+
+fn open_shop(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if input.just_pressed(KeyCode::KeyS) {
+        next_state.set(GameState::Shop);
+    }
+}
+
+fn show_shop(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "Shop",
+            TextStyle {
+                font_size: 56.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(WINDOW_W * 0.5 - 70.0),
+            ..default()
+        }),
+        ShopUI,
+    ));
+    // Filled in by update_shop_list
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 22.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(120.0),
+            left: Val::Px(80.0),
+            ..default()
+        }),
+        ShopUI,
+        ShopList,
+    ));
+}
+
+fn despawn_shop(mut commands: Commands, q: Query<Entity, With<ShopUI>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn shop_list(wallet: &Wallet) -> String {
+    let mut list = format!("{} coins\n\n", wallet.coins);
+    for (i, cosmetic) in Cosmetic::ALL.into_iter().enumerate() {
+        let status = if wallet.equipped.contains(&cosmetic) {
+            "wearing".to_string()
+        } else if wallet.owns(cosmetic) {
+            "owned".to_string()
+        } else {
+            match cosmetic.price() {
+                Some(price) => format!("{price} coins"),
+                None => "50 in a row to earn".to_string(),
+            }
+        };
+        list.push_str(&format!(
+            "{}. {} ({}) - {status}\n",
+            i + 1,
+            cosmetic.label(),
+            cosmetic.slot().label()
+        ));
+    }
+    list.push_str("\nNumber keys to buy, or to put on and take off\nEsc to go back");
+    list
+}
+
+fn update_shop_list(wallet: Res<Wallet>, mut q: Query<(&mut Text, Ref<ShopList>)>) {
+    for (mut text, list) in &mut q {
+        if wallet.is_changed() || list.is_added() {
+            text.sections[0].value = shop_list(&wallet);
+        }
+    }
+}
+
+fn shop_input(
+    input: Res<ButtonInput<KeyCode>>,
+    mut wallet: ResMut<Wallet>,
+    mut toasts: EventWriter<ShowToast>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+        return;
+    }
+
+    const NUMBER_KEYS: [KeyCode; Cosmetic::ALL.len()] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    let Some(index) = NUMBER_KEYS.iter().position(|&k| input.just_pressed(k)) else {
+        return;
+    };
+    let cosmetic = Cosmetic::ALL[index];
+    if wallet.owns(cosmetic) {
+        wallet.toggle_equipped(cosmetic);
+    } else if wallet.buy(cosmetic) {
+        toasts.send(ShowToast(format!("Bought {}", cosmetic.label())));
+    } else {
+        let message = match cosmetic.price() {
+            Some(price) => format!("{} costs {price} coins", cosmetic.label()),
+            None => format!("{} can't be bought", cosmetic.label()),
+        };
+        toasts.send(ShowToast(message));
+        return;
+    }
+    wallet::save(&wallet);
+}
+
+// --------------------------------------------
+// Trails and death effects
+// --------------------------------------------
+
+// A puff of trail or a piece of a death effect, drifting and fading out
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    gravity: f32,
+    timer: Timer,
+}
+
+fn particle(color: Color, size: Vec2, at: Vec3, particle: Particle) -> impl Bundle {
+    (
+        SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(at),
+            ..default()
+        },
+        particle,
+    )
+}
+
+// Puffs left behind the bird drift off with the scenery. The menu demo shows
+// the trail too.
+#[allow(clippy::too_many_arguments)]
+fn spawn_trail(
+    mut commands: Commands,
+    time: Res<Time>,
+    wallet: Res<Wallet>,
+    accessibility: Res<AccessibilitySettings>,
+    course: Course,
+    run_modifiers: Res<RunModifiers>,
+    birds: Query<&GlobalTransform, LeadBird>,
+    mut since_puff: Local<f32>,
+) {
+    let Some(trail) = wallet.wearing(Slot::Trail) else {
+        return;
+    };
+    if accessibility.reduced_motion {
+        return;
+    }
+    *since_puff += time.delta_seconds();
+    if *since_puff < TRAIL_INTERVAL {
+        return;
+    }
+    *since_puff = 0.0;
+    let Ok(bird) = birds.get_single() else {
+        return;
+    };
+    let color = match trail {
+        Cosmetic::RainbowTrail => {
+            let hue = (time.elapsed_seconds() / RAINBOW_PERIOD).fract() * 360.0;
+            Color::hsl(hue, 0.9, 0.6)
+        }
+        _ => SPARK_TRAIL_COLOR,
+    };
+    let view = run_modifiers.0.course_to_world();
+    let drift = view.rotation * (view.scale * Vec3::X * course.pipe_speed() * course.facing());
+    // Under the bird
+    let at = bird.translation().truncate().extend(0.4);
+    commands.spawn(particle(
+        color,
+        Vec2::splat(TRAIL_SIZE),
+        at,
+        Particle {
+            velocity: drift.truncate(),
+            gravity: 0.0,
+            timer: Timer::from_seconds(TRAIL_SECS, TimerMode::Once),
+        },
+    ));
+}
+
+fn spawn_death_effect(
+    mut commands: Commands,
+    mut crashed: EventReader<Crashed>,
+    wallet: Res<Wallet>,
+    accessibility: Res<AccessibilitySettings>,
+    run_modifiers: Res<RunModifiers>,
+    birds: Query<&GlobalTransform, LeadBird>,
+) {
+    if crashed.read().count() == 0 {
+        return;
+    }
+    let Some(effect) = wallet.wearing(Slot::DeathEffect) else {
+        return;
+    };
+    let Ok(bird) = birds.get_single() else {
+        return;
+    };
+    if accessibility.reduced_motion {
+        return;
+    }
+    // Pieces fall down the screen, whichever way up the course is drawn
+    let gravity = if run_modifiers.0.has(Modifier::UpsideDown) {
+        -BURST_GRAVITY
+    } else {
+        BURST_GRAVITY
+    };
+    let at = bird.translation().truncate().extend(0.9);
+    // Cosmetic, so not from the run's random streams
+    let mut rng = rand::thread_rng();
+    for _ in 0..BURST_PIECES {
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let speed = rng.gen_range(0.3..1.0) * BURST_SPEED;
+        let (color, size) = match effect {
+            Cosmetic::Confetti => (
+                Color::hsl(rng.gen_range(0.0..360.0), 0.85, 0.6),
+                CONFETTI_SIZE,
+            ),
+            _ => (FEATHER_COLOR, FEATHER_SIZE),
+        };
+        commands.spawn(particle(
+            color,
+            size,
+            at,
+            Particle {
+                velocity: Vec2::from_angle(angle) * speed,
+                gravity,
+                timer: Timer::from_seconds(BURST_SECS, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+fn fade_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Transform, &mut Sprite, &mut Particle)>,
+) {
+    for (e, mut tf, mut sprite, mut particle) in &mut particles {
+        if particle.timer.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        particle.velocity.y -= particle.gravity * time.delta_seconds();
+        tf.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_alpha(particle.timer.fraction_remaining());
+    }
+}
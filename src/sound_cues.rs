@@ -0,0 +1,211 @@
+// Visual sound cues, for deaf and hard-of-hearing players (Visual sound cues in
+// the settings menu): a small flash behind the bird on every flap, a pulse on
+// the score for each point, and a glow along the top or bottom edge of the
+// screen (the sides, on a vertical course) on a close call with a pipe. They
+// hang off the gameplay events that sound effects play on, so they show up
+// exactly when a sound would.
+
+use bevy::prelude::*;
+
+use crate::modifiers::Modifier;
+use crate::settings::AccessibilitySettings;
+use crate::{FixedTransform, Flapped, LeadBird, NearMiss, PipePassed, RunModifiers, ScoreText};
+
+const FLAP_CUE_SECS: f32 = 0.15;
+const FLAP_CUE_SIZE: f32 = 8.0;
+const SCORE_PULSE_SECS: f32 = 0.25;
+const SCORE_PULSE_SCALE: f32 = 1.3;
+const NEAR_MISS_CUE_SECS: f32 = 0.4;
+const NEAR_MISS_CUE_HEIGHT: f32 = 14.0;
+
+pub struct SoundCuesPlugin;
+
+impl Plugin for SoundCuesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (flap_cues, score_cues, near_miss_cues).run_if(cues_enabled),
+                fade_cues,
+                pulse_score,
+            )
+                .chain(),
+        );
+    }
+}
+
+// Fades out and despawns
+#[derive(Component)]
+struct Cue {
+    timer: Timer,
+    alpha: f32,
+}
+
+impl Cue {
+    fn new(secs: f32, alpha: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(secs, TimerMode::Once),
+            alpha,
+        }
+    }
+}
+
+#[derive(Component)]
+struct ScorePulse(Timer);
+
+fn cues_enabled(accessibility: Res<AccessibilitySettings>) -> bool {
+    accessibility.visual_cues
+}
+
+fn flap_cues(
+    mut commands: Commands,
+    mut flapped: EventReader<Flapped>,
+    run_modifiers: Res<RunModifiers>,
+    bird_q: Query<&FixedTransform, LeadBird>,
+) {
+    if flapped.read().count() == 0 {
+        return;
+    }
+    let Ok(bird_tf) = bird_q.get_single() else {
+        return;
+    };
+    // Just behind the bird's tail, and behind the bird itself
+    let behind = Vec3::new(-24.0 * run_modifiers.0.facing(), 0.0, -0.5);
+    let pos = run_modifiers
+        .0
+        .course_to_world()
+        .transform_point(bird_tf.current.translation + behind);
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::WHITE,
+                custom_size: Some(Vec2::splat(FLAP_CUE_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_translation(pos)
+                .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+            ..default()
+        },
+        Cue::new(FLAP_CUE_SECS, 1.0),
+    ));
+}
+
+fn score_cues(
+    mut commands: Commands,
+    mut passed: EventReader<PipePassed>,
+    score_q: Query<Entity, With<ScoreText>>,
+) {
+    if passed.read().count() == 0 {
+        return;
+    }
+    if let Ok(e) = score_q.get_single() {
+        commands.entity(e).insert(ScorePulse(Timer::from_seconds(
+            SCORE_PULSE_SECS,
+            TimerMode::Once,
+        )));
+    }
+}
+
+fn near_miss_cues(
+    mut commands: Commands,
+    mut near_misses: EventReader<NearMiss>,
+    run_modifiers: Res<RunModifiers>,
+) {
+    // A vertical course has its top pipes on the left, and an upside-down one
+    // at the bottom
+    let vertical = run_modifiers.0.has(Modifier::Vertical);
+    let upside_down = run_modifiers.0.has(Modifier::UpsideDown);
+    for near_miss in near_misses.read() {
+        let edge = match (near_miss.above != upside_down, vertical) {
+            (true, false) => Style {
+                top: Val::Px(0.0),
+                ..default()
+            },
+            (false, false) => Style {
+                bottom: Val::Px(0.0),
+                ..default()
+            },
+            (true, true) => Style {
+                left: Val::Px(0.0),
+                ..default()
+            },
+            (false, true) => Style {
+                right: Val::Px(0.0),
+                ..default()
+            },
+        };
+        let (width, height) = if vertical {
+            (Val::Px(NEAR_MISS_CUE_HEIGHT), Val::Percent(100.0))
+        } else {
+            (Val::Percent(100.0), Val::Px(NEAR_MISS_CUE_HEIGHT))
+        };
+        commands.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width,
+                    height,
+                    ..edge
+                },
+                background_color: Color::srgb(1.0, 0.5, 0.1).into(),
+                ..default()
+            },
+            Cue::new(NEAR_MISS_CUE_SECS, 0.8),
+        ));
+    }
+}
+
+fn fade_cues(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut cues: Query<(
+        Entity,
+        &mut Cue,
+        Option<&mut Sprite>,
+        Option<&mut BackgroundColor>,
+    )>,
+) {
+    for (e, mut cue, sprite, background) in &mut cues {
+        if cue.timer.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        let alpha = cue.alpha * cue.timer.fraction_remaining();
+        if let Some(mut sprite) = sprite {
+            sprite.color.set_alpha(alpha);
+        }
+        if let Some(mut background) = background {
+            background.0.set_alpha(alpha);
+        }
+    }
+}
+
+// Highlight behind the score, and a quick swell unless motion is reduced
+fn pulse_score(
+    mut commands: Commands,
+    time: Res<Time>,
+    accessibility: Res<AccessibilitySettings>,
+    mut score_q: Query<(
+        Entity,
+        &mut ScorePulse,
+        &mut BackgroundColor,
+        &mut Transform,
+    )>,
+) {
+    let Ok((e, mut pulse, mut background, mut tf)) = score_q.get_single_mut() else {
+        return;
+    };
+    let left = if pulse.0.tick(time.delta()).finished() {
+        commands.entity(e).remove::<ScorePulse>();
+        0.0
+    } else {
+        pulse.0.fraction_remaining()
+    };
+    background.0 = Color::srgba(1.0, 0.8, 0.2, 0.6 * left);
+    let scale = if accessibility.reduced_motion {
+        1.0
+    } else {
+        1.0 + (SCORE_PULSE_SCALE - 1.0) * left
+    };
+    tf.scale = Vec3::splat(scale);
+}
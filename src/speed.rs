@@ -0,0 +1,103 @@
+// Game speed (practice / accessibility): runs everything at 50-100% by slowing
+// virtual time. Fixed steps keep the same length and just come less often, so a
+// slowed run plays out exactly like a full-speed one, only slower in real time.
+//
+// Runs played below full speed at any point don't count towards the best score,
+// stats or achievements; they keep their own practice best instead.
+
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::{GameOverUI, GameState, Score, WINDOW_W};
+
+pub const MIN_GAME_SPEED: f32 = 0.5;
+
+pub struct GameSpeedPlugin;
+
+impl Plugin for GameSpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunSpeed>()
+            .init_resource::<PracticeBest>()
+            .add_systems(Update, apply_game_speed)
+            .add_systems(OnEnter(GameState::Playing), start_run)
+            .add_systems(OnEnter(GameState::GameOver), show_practice_result);
+    }
+}
+
+#[derive(Resource)]
+pub struct RunSpeed {
+    // Lowest speed used during the current run
+    slowest: f32,
+}
+
+impl Default for RunSpeed {
+    fn default() -> Self {
+        Self { slowest: 1.0 }
+    }
+}
+
+impl RunSpeed {
+    pub fn slowed(&self) -> bool {
+        self.slowest < 1.0
+    }
+}
+
+// Best score this session among slowed runs
+#[derive(Resource, Default)]
+struct PracticeBest(u32);
+
+fn apply_game_speed(
+    settings: Res<Settings>,
+    state: Res<State<GameState>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut run_speed: ResMut<RunSpeed>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let speed = settings.game_speed;
+    if time.relative_speed() != speed {
+        time.set_relative_speed(speed);
+    }
+    if *state.get() == GameState::Playing && speed < run_speed.slowest {
+        run_speed.slowest = speed;
+    }
+}
+
+fn start_run(settings: Res<Settings>, mut run_speed: ResMut<RunSpeed>) {
+    run_speed.slowest = settings.game_speed;
+}
+
+fn show_practice_result(
+    mut commands: Commands,
+    score: Res<Score>,
+    run_speed: Res<RunSpeed>,
+    mut best: ResMut<PracticeBest>,
+) {
+    if !run_speed.slowed() {
+        return;
+    }
+    best.0 = best.0.max(score.0);
+    commands.spawn((
+        TextBundle::from_section(
+            format!(
+                "Practice at {:.0}% speed - practice best {}",
+                run_speed.slowest * 100.0,
+                best.0
+            ),
+            TextStyle {
+                font_size: 22.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Below the replay verification line
+            top: Val::Px(370.0),
+            left: Val::Px(WINDOW_W * 0.5 - 220.0),
+            ..default()
+        }),
+        GameOverUI,
+    ));
+}
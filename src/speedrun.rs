@@ -0,0 +1,312 @@
+// Speedrun mode (Speedrun in the settings menu): each run is a race to
+// TARGET_SCORE points, and reaching it ends the run. The clock in the corner
+// takes a split every SPLIT_POINTS points and compares it with the same split
+// of the personal best for the run's modifiers: ahead in green, behind in red.
+//
+// A faster finish replaces the personal best, kept in speedrun.txt next to the
+// settings as `modifiers = split ticks` lines ("normal" for none). Only runs
+// that count under their own rules set one, and not preset or roulette runs,
+// whose rules differ. Every run's splits also go into the run history (see
+// history.rs).
+//
+// Times are counted in fixed steps like the run timer's (see timer.rs), so a
+// run at practice speed is timed as if it were at full speed.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::modifiers::{Modifier, Modifiers};
+use crate::presets::ActivePreset;
+use crate::settings::Settings;
+use crate::story::StoryMode;
+use crate::timer::{format_time, run_seconds};
+use crate::{
+    check_collisions_and_scoring, start_game, GameState, RunModifiers, RunRanking, RunRules,
+    RunTick, Score,
+};
+
+pub const TARGET_SCORE: u32 = 50;
+const SPLIT_POINTS: u32 = 10;
+const SPLIT_COUNT: usize = (TARGET_SCORE / SPLIT_POINTS) as usize;
+const AHEAD_COLOR: Color = Color::srgb(0.3, 0.9, 0.3);
+const BEHIND_COLOR: Color = Color::srgb(1.0, 0.35, 0.35);
+
+pub struct SpeedrunPlugin;
+
+impl Plugin for SpeedrunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Speedrun>()
+            .insert_resource(SpeedrunBests::load())
+            .add_systems(Startup, spawn_splits_text)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                start_speedrun.after(start_game),
+            )
+            .add_systems(OnEnter(GameState::Menu), stop_speedrun)
+            .add_systems(OnEnter(GameState::Replay), stop_speedrun)
+            .add_systems(
+                FixedUpdate,
+                take_splits
+                    .after(check_collisions_and_scoring)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(GameState::GameOver), save_best_splits)
+            .add_systems(Update, update_splits_text);
+    }
+}
+
+// The current run's race, if it's a speedrun
+#[derive(Resource, Default)]
+pub struct Speedrun {
+    active: bool,
+    // "normal", or the run's modifiers ("night+wind")
+    course: String,
+    // Fixed ticks into the run at which each split's points were reached
+    splits: Vec<u32>,
+    // The personal best's splits for the course
+    best: Option<Vec<u32>>,
+    new_best: bool,
+}
+
+impl Speedrun {
+    // Seconds into the run of each split taken, none if this isn't a speedrun
+    pub fn split_seconds(&self) -> Vec<f32> {
+        if !self.active {
+            return Vec::new();
+        }
+        self.splits.iter().map(|&tick| run_seconds(tick)).collect()
+    }
+
+    fn finished(&self) -> bool {
+        self.splits.len() == SPLIT_COUNT
+    }
+}
+
+// Personal best splits by course
+#[derive(Resource, Default)]
+struct SpeedrunBests(HashMap<String, Vec<u32>>);
+
+impl SpeedrunBests {
+    fn load() -> Self {
+        let mut bests = Self::default();
+        let text = std::fs::read_to_string(bests_path()).unwrap_or_default();
+        for line in text.lines() {
+            let Some((course, splits)) = line.split_once('=') else {
+                continue;
+            };
+            let splits: Option<Vec<u32>> = splits
+                .split(',')
+                .map(|tick| tick.trim().parse().ok())
+                .collect();
+            if let Some(splits) = splits.filter(|splits| splits.len() == SPLIT_COUNT) {
+                bests.0.insert(course.trim().to_string(), splits);
+            }
+        }
+        bests
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let path = bests_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut courses: Vec<_> = self.0.keys().collect();
+        courses.sort();
+        let mut text = String::new();
+        for course in courses {
+            let ticks: Vec<String> = self.0[course].iter().map(u32::to_string).collect();
+            let _ = writeln!(text, "{course} = {}", ticks.join(", "));
+        }
+        std::fs::write(path, text)
+    }
+}
+
+fn bests_path() -> PathBuf {
+    crate::data_file("speedrun.txt")
+}
+
+fn course_name(modifiers: Modifiers) -> String {
+    if modifiers.is_empty() {
+        return "normal".to_string();
+    }
+    let names: Vec<&str> = Modifier::ALL
+        .into_iter()
+        .filter(|&m| modifiers.has(m))
+        .map(Modifier::name)
+        .collect();
+    names.join("+")
+}
+
+// Story levels have goals of their own
+fn start_speedrun(
+    settings: Res<Settings>,
+    story: Option<Res<StoryMode>>,
+    run_modifiers: Res<RunModifiers>,
+    bests: Res<SpeedrunBests>,
+    mut speedrun: ResMut<Speedrun>,
+) {
+    let course = course_name(run_modifiers.0);
+    *speedrun = Speedrun {
+        active: settings.speedrun && story.is_none(),
+        best: bests.0.get(&course).cloned(),
+        course,
+        ..default()
+    };
+}
+
+fn stop_speedrun(mut speedrun: ResMut<Speedrun>) {
+    if speedrun.active {
+        *speedrun = Speedrun::default();
+    }
+}
+
+// Several points can come at once (a flock, or growth), so one pipe can pass
+// more than one split
+fn take_splits(
+    score: Res<Score>,
+    run_tick: Res<RunTick>,
+    mut speedrun: ResMut<Speedrun>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !speedrun.active || speedrun.finished() {
+        return;
+    }
+    while !speedrun.finished() && score.0 >= (speedrun.splits.len() as u32 + 1) * SPLIT_POINTS {
+        speedrun.splits.push(run_tick.0);
+    }
+    if speedrun.finished() {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+fn save_best_splits(
+    ranking: RunRanking,
+    preset: Res<ActivePreset>,
+    run_rules: Res<RunRules>,
+    mut speedrun: ResMut<Speedrun>,
+    mut bests: ResMut<SpeedrunBests>,
+) {
+    if !speedrun.active
+        || !speedrun.finished()
+        || !ranking.ranked_under_own_rules()
+        || preset.0.is_some()
+        || run_rules.0.roulette
+    {
+        return;
+    }
+    let finish = speedrun.splits[SPLIT_COUNT - 1];
+    if speedrun
+        .best
+        .as_ref()
+        .is_some_and(|best| best[SPLIT_COUNT - 1] <= finish)
+    {
+        return;
+    }
+    speedrun.new_best = true;
+    bests
+        .0
+        .insert(speedrun.course.clone(), speedrun.splits.clone());
+    if let Err(err) = bests.save() {
+        error!("Cannot save speedrun splits: {err}");
+    }
+}
+
+#[derive(Component)]
+struct SplitsText;
+
+fn spawn_splits_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Below the laser charges
+            top: Val::Px(70.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        SplitsText,
+    ));
+}
+
+fn section(value: String, color: Color) -> TextSection {
+    TextSection::new(
+        value,
+        TextStyle {
+            font_size: 20.0,
+            color,
+            ..default()
+        },
+    )
+}
+
+// The clock, then a line per split with how far ahead or behind the best it
+// was; left up on the game over screen
+fn update_splits_text(
+    speedrun: Res<Speedrun>,
+    run_tick: Res<RunTick>,
+    mut q: Query<&mut Text, With<SplitsText>>,
+) {
+    if !speedrun.is_changed() && !run_tick.is_changed() {
+        return;
+    }
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    if !speedrun.active {
+        if !text.sections.is_empty() {
+            text.sections.clear();
+        }
+        return;
+    }
+
+    let header = if speedrun.new_best {
+        format!(
+            "Finished {} - new best!\n",
+            format_time(run_seconds(speedrun.splits[SPLIT_COUNT - 1]))
+        )
+    } else if speedrun.finished() {
+        format!(
+            "Finished {}\n",
+            format_time(run_seconds(speedrun.splits[SPLIT_COUNT - 1]))
+        )
+    } else {
+        format!(
+            "{} to {TARGET_SCORE}\n",
+            format_time(run_seconds(run_tick.0))
+        )
+    };
+    let mut sections = vec![section(header, Color::WHITE)];
+    for (i, &tick) in speedrun.splits.iter().enumerate() {
+        let points = (i as u32 + 1) * SPLIT_POINTS;
+        sections.push(section(
+            format!("{points:>3}  {}", format_time(run_seconds(tick))),
+            Color::WHITE,
+        ));
+        let delta = speedrun
+            .best
+            .as_ref()
+            .map(|best| run_seconds(tick) - run_seconds(best[i]));
+        match delta {
+            Some(delta) => {
+                let color = if delta <= 0.0 {
+                    AHEAD_COLOR
+                } else {
+                    BEHIND_COLOR
+                };
+                sections.push(section(format!("  {delta:+.2}\n"), color));
+            }
+            None => sections.push(section("\n".to_string(), Color::WHITE)),
+        }
+    }
+    text.sections = sections;
+}
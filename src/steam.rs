@@ -0,0 +1,130 @@
+// Steam achievements and stats (enabled with the `steamworks` cargo feature)
+//
+// If Steam isn't running (or the game isn't launched through it) we log once and
+// play on without it.
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use steamworks::Client;
+
+use crate::achievements::AchievementUnlocked;
+use crate::{update_best_score, BestScore, GameState, Stats};
+
+// 480 is Valve's Spacewar test app; swap in our own ID once the store page exists
+const STEAM_APP_ID: u32 = 480;
+
+// Steam stat API names
+const STAT_RUNS_PLAYED: &str = "runs_played";
+const STAT_FLAPS: &str = "flaps";
+const STAT_PIPES_PASSED: &str = "pipes_passed";
+const STAT_BEST_SCORE: &str = "best_score";
+
+pub struct SteamPlugin;
+
+impl Plugin for SteamPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, init_steam)
+            .add_systems(
+                Update,
+                (run_steam_callbacks, unlock_steam_achievements).run_if(resource_exists::<Steam>),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                store_steam_stats
+                    .after(update_best_score)
+                    .run_if(resource_exists::<Steam>),
+            )
+            .add_systems(Last, shutdown_steam.run_if(resource_exists::<Steam>));
+    }
+}
+
+#[derive(Resource)]
+struct Steam {
+    client: Client,
+    // Session stats already added to Steam's lifetime counters
+    pushed: Stats,
+}
+
+fn init_steam(mut commands: Commands) {
+    match Client::init_app(STEAM_APP_ID) {
+        Ok(client) => commands.insert_resource(Steam {
+            client,
+            pushed: Stats::default(),
+        }),
+        Err(err) => info!("Steam not available, achievements and stats disabled: {err}"),
+    }
+}
+
+fn run_steam_callbacks(steam: Res<Steam>) {
+    steam.client.run_callbacks();
+}
+
+fn unlock_steam_achievements(steam: Res<Steam>, mut events: EventReader<AchievementUnlocked>) {
+    let user_stats = steam.client.user_stats();
+    for AchievementUnlocked(achievement) in events.read() {
+        if user_stats.achievement(achievement.id()).set().is_err() {
+            warn!("Steam rejected achievement {}", achievement.id());
+        }
+    }
+}
+
+// Steam counters are lifetime totals, so add what changed since the last push
+fn add_to_stat(steam: &Steam, name: &str, delta: u32) {
+    if delta == 0 {
+        return;
+    }
+    let user_stats = steam.client.user_stats();
+    let current = user_stats.get_stat_i32(name).unwrap_or(0);
+    if user_stats
+        .set_stat_i32(name, current + delta as i32)
+        .is_err()
+    {
+        warn!("Steam rejected stat {name}");
+    }
+}
+
+fn push_stats(steam: &mut Steam, stats: &Stats, best: &BestScore) {
+    let pushed = steam.pushed;
+    add_to_stat(
+        steam,
+        STAT_RUNS_PLAYED,
+        stats.runs_played - pushed.runs_played,
+    );
+    add_to_stat(steam, STAT_FLAPS, stats.flaps - pushed.flaps);
+    add_to_stat(
+        steam,
+        STAT_PIPES_PASSED,
+        stats.pipes_passed - pushed.pipes_passed,
+    );
+    steam.pushed = *stats;
+
+    let user_stats = steam.client.user_stats();
+    let steam_best = user_stats.get_stat_i32(STAT_BEST_SCORE).unwrap_or(0);
+    if best.0 as i32 > steam_best {
+        let _ = user_stats.set_stat_i32(STAT_BEST_SCORE, best.0 as i32);
+    }
+    if user_stats.store_stats().is_err() {
+        warn!("Failed to store Steam stats");
+    }
+}
+
+// Stats are uploaded once per run; Steam throttles frequent stores
+fn store_steam_stats(mut steam: ResMut<Steam>, stats: Res<Stats>, best: Res<BestScore>) {
+    push_stats(&mut steam, &stats, &best);
+}
+
+// Flush stats and shut the API down while the window (and overlay) are still alive
+fn shutdown_steam(
+    mut commands: Commands,
+    mut exit: EventReader<AppExit>,
+    mut steam: ResMut<Steam>,
+    stats: Res<Stats>,
+    best: Res<BestScore>,
+) {
+    if exit.read().next().is_none() {
+        return;
+    }
+    push_stats(&mut steam, &stats, &best);
+    steam.client.run_callbacks();
+    commands.remove_resource::<Steam>();
+}
@@ -0,0 +1,545 @@
+// Story mode (T on the main menu): a short campaign of levels with dialogue
+// scenes between them. Each level asks for a number of pipes; passing that many
+// ends the run and plays the next scene, crashing retries the level. The
+// campaign is read from story.txt, which a mod can replace:
+//
+//   story.txt   `key = value` lines:
+//                 speaker = name, sprite sheet prefix for the portrait[, hex tint]
+//                 say = name: text      a line of the scene before the next level
+//                 level = pipes to pass, ending the scene before it
+//               Lines after the last level are the ending.
+//
+// Scenes advance with the flap button (or Enter) and Escape leaves. The level
+// reached is kept on disk, so story mode picks up there until the ending has
+// been seen.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::atlas::SpriteAtlas;
+use crate::controls::GameInput;
+use crate::mods::Mods;
+use crate::photo::PhotoMode;
+use crate::screen_reader::Announce;
+use crate::toast::ShowToast;
+use crate::{GameOverUI, GameState, Score, WINDOW_W};
+
+const STORY_PATH: &str = "story.txt";
+// Flaps this soon after a line appears don't skip it, so the flap that passed a
+// level's last pipe doesn't also skip the first line of the next scene
+const READ_DELAY: f32 = 0.4;
+// Portraits are the sprite at this scale
+const PORTRAIT_SCALE: f32 = 3.0;
+
+pub struct StoryPlugin;
+
+impl Plugin for StoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, start_story.run_if(in_state(GameState::Menu)))
+            .add_systems(OnEnter(GameState::Menu), end_story)
+            .add_systems(OnEnter(GameState::Story), show_scene)
+            .add_systems(Update, scene_input.run_if(in_state(GameState::Story)))
+            .add_systems(OnExit(GameState::Story), despawn_scene)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                show_level.run_if(resource_exists::<StoryMode>),
+            )
+            .add_systems(
+                Update,
+                update_level
+                    .run_if(in_state(GameState::Playing).and_then(resource_exists::<StoryMode>)),
+            )
+            .add_systems(OnExit(GameState::Playing), despawn_level)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                show_level_result.run_if(resource_exists::<StoryMode>),
+            )
+            .add_systems(
+                Update,
+                leave_story.run_if(
+                    in_state(GameState::GameOver)
+                        .and_then(resource_exists::<StoryMode>)
+                        .and_then(not(resource_exists::<PhotoMode>)),
+                ),
+            );
+    }
+}
+
+// --------------------------------------------
+// Campaign file
+// --------------------------------------------
+
+#[derive(Default)]
+struct Story {
+    speakers: HashMap<String, Speaker>,
+    chapters: Vec<Chapter>,
+}
+
+struct Speaker {
+    // Sprite sheet prefix, as for the bird's flap frames
+    portrait: String,
+    tint: Color,
+}
+
+// A scene and the level after it
+#[derive(Default)]
+struct Chapter {
+    lines: Vec<Line>,
+    // None for the ending
+    pipes: Option<u32>,
+}
+
+struct Line {
+    speaker: String,
+    text: String,
+}
+
+impl Story {
+    // From the first enabled mod that has one
+    fn load(mods: &Mods) -> Self {
+        let path = mods.resolve_file(STORY_PATH);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => parse_story(&text),
+            Err(err) => {
+                error!("Cannot read story {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+}
+
+fn parse_story(text: &str) -> Story {
+    let mut story = Story::default();
+    let mut chapter = Chapter::default();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = line.split_once('=').and_then(|(key, value)| {
+            let value = value.trim();
+            match key.trim() {
+                "speaker" => {
+                    let mut fields = value.split(',').map(str::trim);
+                    let name = fields.next()?;
+                    let portrait = fields.next()?;
+                    let tint = match fields.next() {
+                        Some(hex) => Srgba::hex(hex).ok()?.into(),
+                        None => Color::WHITE,
+                    };
+                    let speaker = Speaker {
+                        portrait: portrait.to_string(),
+                        tint,
+                    };
+                    story.speakers.insert(name.to_string(), speaker);
+                }
+                "say" => {
+                    let (speaker, text) = value.split_once(':')?;
+                    chapter.lines.push(Line {
+                        speaker: speaker.trim().to_string(),
+                        text: text.trim().to_string(),
+                    });
+                }
+                "level" => {
+                    let pipes = value.parse().ok().filter(|&pipes| pipes > 0)?;
+                    chapter.pipes = Some(pipes);
+                    story.chapters.push(std::mem::take(&mut chapter));
+                }
+                _ => return None,
+            }
+            Some(())
+        });
+        if parsed.is_none() {
+            warn!("{STORY_PATH}: ignoring line \"{line}\"");
+        }
+    }
+    if !chapter.lines.is_empty() {
+        story.chapters.push(chapter);
+    }
+    story
+}
+
+// --------------------------------------------
+// Progress
+// --------------------------------------------
+
+// Present while playing story mode
+#[derive(Resource)]
+pub struct StoryMode {
+    story: Story,
+    // Into story.chapters
+    chapter: usize,
+    // Into the chapter's lines
+    line: usize,
+    // Real time the current line appeared
+    shown_at: f32,
+}
+
+impl StoryMode {
+    fn chapter(&self) -> Option<&Chapter> {
+        self.story.chapters.get(self.chapter)
+    }
+
+    fn line(&self) -> Option<&Line> {
+        self.chapter()?.lines.get(self.line)
+    }
+
+    // Pipes the current level asks for
+    fn pipes(&self) -> Option<u32> {
+        self.chapter()?.pipes
+    }
+
+    // Counting from 1, and how many there are
+    fn level_number(&self) -> (usize, usize) {
+        let levels = |chapters: &[Chapter]| chapters.iter().filter(|c| c.pipes.is_some()).count();
+        let chapters = &self.story.chapters;
+        (
+            levels(&chapters[..=self.chapter.min(chapters.len() - 1)]),
+            levels(chapters),
+        )
+    }
+}
+
+fn progress_path() -> PathBuf {
+    crate::data_file("story_progress.txt")
+}
+
+fn load_progress() -> usize {
+    std::fs::read_to_string(progress_path())
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or_default()
+}
+
+fn save_progress(chapter: usize) {
+    let path = progress_path();
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, format!("{chapter}\n")));
+    if let Err(err) = result {
+        error!("Cannot save story progress: {err}");
+    }
+}
+
+fn start_story(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mods: Res<Mods>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !keys.just_pressed(KeyCode::KeyT) {
+        return;
+    }
+    // Read again each time, in case the mods changed
+    let story = Story::load(&mods);
+    if story.chapters.is_empty() {
+        toasts.send(ShowToast("There's no story to play".to_string()));
+        return;
+    }
+    // Start over once the ending has been reached
+    let chapter = Some(load_progress()).filter(|&c| c < story.chapters.len());
+    commands.insert_resource(StoryMode {
+        story,
+        chapter: chapter.unwrap_or_default(),
+        line: 0,
+        shown_at: 0.0,
+    });
+    next_state.set(GameState::Story);
+}
+
+fn end_story(mut commands: Commands) {
+    commands.remove_resource::<StoryMode>();
+}
+
+// --------------------------------------------
+// Scenes
+// --------------------------------------------
+
+#[derive(Component)]
+struct SceneUI;
+
+// The parts that change from line to line
+#[derive(Component)]
+struct SceneBox;
+
+fn show_scene(
+    mut commands: Commands,
+    mut mode: ResMut<StoryMode>,
+    atlas: Res<SpriteAtlas>,
+    time: Res<Time<Real>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut announce: EventWriter<Announce>,
+) {
+    mode.line = 0;
+    // A level straight after another one, or no ending
+    if mode.line().is_none() {
+        finish_scene(&mode, &mut next_state);
+        return;
+    }
+    let (level, levels) = mode.level_number();
+    let title = match mode.pipes() {
+        Some(pipes) => format!("Level {level} of {levels}: pass {pipes} pipes"),
+        None => "The end".to_string(),
+    };
+    commands.spawn((
+        TextBundle::from_section(
+            title,
+            TextStyle {
+                font_size: 36.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            left: Val::Px(WINDOW_W * 0.5 - 200.0),
+            ..default()
+        }),
+        SceneUI,
+    ));
+    spawn_line(&mut commands, &mut mode, &atlas, &time, &mut announce);
+}
+
+// The text box with the current line: portrait on the left, speaker and text
+// on the right
+fn spawn_line(
+    commands: &mut Commands,
+    mode: &mut StoryMode,
+    atlas: &SpriteAtlas,
+    time: &Time<Real>,
+    announce: &mut EventWriter<Announce>,
+) {
+    mode.shown_at = time.elapsed_seconds();
+    let Some(line) = mode.line() else {
+        return;
+    };
+    announce.send(Announce(format!("{}: {}", line.speaker, line.text)));
+    let speaker = mode.story.speakers.get(&line.speaker);
+    let portrait = speaker.and_then(|s| atlas.frames(&s.portrait).first().copied());
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(40.0),
+                    right: Val::Px(40.0),
+                    bottom: Val::Px(40.0),
+                    height: Val::Px(160.0),
+                    padding: UiRect::all(Val::Px(16.0)),
+                    column_gap: Val::Px(20.0),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::srgba(1.0, 1.0, 1.0, 0.85).into(),
+                ..default()
+            },
+            SceneUI,
+            SceneBox,
+        ))
+        .with_children(|parent| {
+            if let (Some(speaker), Some(index)) = (speaker, portrait) {
+                parent.spawn((
+                    ImageBundle {
+                        style: Style {
+                            width: Val::Px(34.0 * PORTRAIT_SCALE),
+                            height: Val::Px(24.0 * PORTRAIT_SCALE),
+                            flex_shrink: 0.0,
+                            ..default()
+                        },
+                        image: UiImage::new(atlas.texture.clone()).with_color(speaker.tint),
+                        ..default()
+                    },
+                    TextureAtlas {
+                        layout: atlas.layout.clone(),
+                        index,
+                    },
+                ));
+            }
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        flex_grow: 1.0,
+                        row_gap: Val::Px(8.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|column| {
+                    column.spawn(TextBundle::from_section(
+                        line.speaker.clone(),
+                        TextStyle {
+                            font_size: 26.0,
+                            color: Color::srgb(0.5, 0.2, 0.1),
+                            ..default()
+                        },
+                    ));
+                    column.spawn(TextBundle::from_section(
+                        line.text.clone(),
+                        TextStyle {
+                            font_size: 22.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    ));
+                    column.spawn(TextBundle::from_section(
+                        "Flap to continue, Esc to leave",
+                        TextStyle {
+                            font_size: 16.0,
+                            color: Color::srgb(0.4, 0.4, 0.4),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn scene_input(
+    mut commands: Commands,
+    controls: GameInput,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mode: ResMut<StoryMode>,
+    atlas: Res<SpriteAtlas>,
+    time: Res<Time<Real>>,
+    boxes: Query<Entity, With<SceneBox>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut announce: EventWriter<Announce>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+        return;
+    }
+    let advance = controls.confirm() || keys.just_pressed(KeyCode::Enter);
+    if !advance || time.elapsed_seconds() - mode.shown_at < READ_DELAY {
+        return;
+    }
+
+    mode.line += 1;
+    if mode.line().is_some() {
+        for e in &boxes {
+            commands.entity(e).despawn_recursive();
+        }
+        spawn_line(&mut commands, &mut mode, &atlas, &time, &mut announce);
+    } else {
+        finish_scene(&mode, &mut next_state);
+    }
+}
+
+// On to the level, or back to the menu after the ending
+fn finish_scene(mode: &StoryMode, next_state: &mut NextState<GameState>) {
+    if mode.pipes().is_some() {
+        next_state.set(GameState::Playing);
+    } else {
+        // Next time starts from the beginning
+        save_progress(0);
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn despawn_scene(mut commands: Commands, q: Query<Entity, With<SceneUI>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// --------------------------------------------
+// Levels
+// --------------------------------------------
+
+#[derive(Component)]
+struct LevelText;
+
+fn show_level(mut commands: Commands, mode: Res<StoryMode>) {
+    let (level, _) = mode.level_number();
+    commands.spawn((
+        TextBundle::from_section(
+            level_progress(level, 0, mode.pipes().unwrap_or_default()),
+            TextStyle {
+                font_size: 22.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        LevelText,
+    ));
+}
+
+// Keep the count up to date and move on to the next scene once it's reached
+fn update_level(
+    score: Res<Score>,
+    mut mode: ResMut<StoryMode>,
+    mut q: Query<&mut Text, With<LevelText>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !score.is_changed() {
+        return;
+    }
+    let Some(pipes) = mode.pipes() else {
+        return;
+    };
+    let (level, _) = mode.level_number();
+    for mut text in &mut q {
+        text.sections[0].value = level_progress(level, score.0, pipes);
+    }
+    if score.0 >= pipes {
+        mode.chapter += 1;
+        save_progress(mode.chapter);
+        next_state.set(GameState::Story);
+    }
+}
+
+fn despawn_level(mut commands: Commands, q: Query<Entity, With<LevelText>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn show_level_result(mut commands: Commands, mode: Res<StoryMode>, score: Res<Score>) {
+    let (level, _) = mode.level_number();
+    let pipes = mode.pipes().unwrap_or_default();
+    commands.spawn((
+        TextBundle::from_section(
+            format!(
+                "{}\nEsc to leave story mode",
+                level_progress(level, score.0, pipes)
+            ),
+            TextStyle {
+                font_size: 22.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Below the challenge code
+            top: Val::Px(440.0),
+            left: Val::Px(WINDOW_W * 0.5 - 200.0),
+            ..default()
+        }),
+        GameOverUI,
+    ));
+}
+
+fn leave_story(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+// "Level 2: 3/10 pipes"
+fn level_progress(level: usize, passed: u32, pipes: u32) -> String {
+    format!("Level {level}: {passed}/{pipes} pipes")
+}
@@ -0,0 +1,285 @@
+// Streaks: pipes passed since the bird last crashed, carried over between runs
+// that end without a crash (a finished story level, or quitting from pause).
+// Streaks of 10, 25 and 50 earn a reward each: coins, a shield that lets the
+// birds fly through pipes for a few seconds, and golden feathers. A row of pips
+// under the score fills up towards the next one.
+//
+// The shield changes the run, so streaks are counted in the simulation, for
+// every run: replays store the streak their run started on and count on from
+// it. Coins and feathers are only paid for ranked runs (see wallet.rs).
+
+use bevy::prelude::*;
+
+use crate::replay::ReplayPlayback;
+use crate::toast::ShowToast;
+use crate::{
+    check_collisions_and_scoring, simulation_running, start_game, Bird, Crashed, GameState,
+    PipePassed, RestartDemo, FIXED_HZ, WINDOW_W,
+};
+
+// Streak lengths that earn something, and what
+const MILESTONES: [(u32, Reward); 3] = [
+    (10, Reward::Coins(10)),
+    (25, Reward::Shield),
+    (50, Reward::GoldenFeathers),
+];
+const SHIELD_TICKS: u32 = 5 * FIXED_HZ as u32;
+const PIPS: usize = 10;
+const PIP_SIZE: f32 = 10.0;
+const PIP_GAP: f32 = 4.0;
+const PIP_EMPTY: Color = Color::srgba(1.0, 1.0, 1.0, 0.3);
+const PIP_FULL: Color = Color::WHITE;
+const SHIELD_COLOR: Color = Color::srgba(0.4, 0.9, 1.0, 0.35);
+const SHIELD_RADIUS: f32 = 24.0;
+
+pub struct StreakPlugin;
+
+impl Plugin for StreakPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Streak>()
+            .add_event::<StreakMilestone>()
+            .add_systems(Startup, spawn_pips)
+            .add_systems(OnEnter(GameState::Menu), end_shield.after(start_game))
+            .add_systems(OnEnter(GameState::Playing), end_shield.after(start_game))
+            .add_systems(
+                OnEnter(GameState::Replay),
+                (end_shield, start_replay_streak).after(start_game),
+            )
+            .add_systems(
+                Update,
+                end_shield
+                    .after(start_game)
+                    .run_if(on_event::<RestartDemo>()),
+            )
+            // Back to the session's own streak once a replay is over
+            .add_systems(OnExit(GameState::GameOver), restore_streak)
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Replay,
+                    entered: GameState::Menu,
+                },
+                restore_streak,
+            )
+            .add_systems(
+                FixedUpdate,
+                count_streak
+                    .after(check_collisions_and_scoring)
+                    .run_if(simulation_running),
+            )
+            .add_systems(Update, (announce_shield, show_shield, update_pips));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Reward {
+    Coins(u32),
+    // Pipes are harmless for SHIELD_TICKS
+    Shield,
+    // A gold tint for the bird for good
+    GoldenFeathers,
+}
+
+#[derive(Resource, Default)]
+pub struct Streak {
+    pub current: u32,
+    // The session's streak, put aside while a replay counts its own
+    saved: Option<u32>,
+    // Fixed ticks of shield left
+    shield_ticks: u32,
+}
+
+impl Streak {
+    pub fn shielded(&self) -> bool {
+        self.shield_ticks > 0
+    }
+}
+
+// The streak reached one of MILESTONES
+#[derive(Event)]
+pub struct StreakMilestone {
+    pub streak: u32,
+    pub reward: Reward,
+}
+
+#[derive(Component)]
+struct StreakPips;
+
+#[derive(Component)]
+struct Pip(usize);
+
+#[derive(Component)]
+struct ShieldBubble;
+
+fn spawn_pips(mut commands: Commands) {
+    let width = PIPS as f32 * (PIP_SIZE + PIP_GAP) - PIP_GAP;
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    // Under the score
+                    top: Val::Px(56.0),
+                    left: Val::Px((WINDOW_W - width) * 0.5),
+                    column_gap: Val::Px(PIP_GAP),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            StreakPips,
+        ))
+        .with_children(|row| {
+            for i in 0..PIPS {
+                row.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Px(PIP_SIZE),
+                            height: Val::Px(PIP_SIZE),
+                            ..default()
+                        },
+                        background_color: PIP_EMPTY.into(),
+                        ..default()
+                    },
+                    Pip(i),
+                ));
+            }
+        });
+}
+
+fn end_shield(mut streak: ResMut<Streak>) {
+    streak.shield_ticks = 0;
+}
+
+fn start_replay_streak(mut streak: ResMut<Streak>, playback: Option<Res<ReplayPlayback>>) {
+    let Some(playback) = playback else {
+        return;
+    };
+    if streak.saved.is_none() {
+        streak.saved = Some(streak.current);
+    }
+    streak.current = playback.replay.streak_start;
+}
+
+fn restore_streak(mut streak: ResMut<Streak>) {
+    if let Some(saved) = streak.saved.take() {
+        streak.current = saved;
+    }
+}
+
+// Neither event is sent for the menu demo, so it never counts
+fn count_streak(
+    mut streak: ResMut<Streak>,
+    mut passed: EventReader<PipePassed>,
+    mut crashed: EventReader<Crashed>,
+    mut milestones: EventWriter<StreakMilestone>,
+) {
+    if streak.shield_ticks > 0 {
+        streak.shield_ticks -= 1;
+    }
+    for _ in passed.read() {
+        streak.current += 1;
+        let Some(&(_, reward)) = MILESTONES.iter().find(|(at, _)| *at == streak.current) else {
+            continue;
+        };
+        if reward == Reward::Shield {
+            streak.shield_ticks = SHIELD_TICKS;
+        }
+        milestones.send(StreakMilestone {
+            streak: streak.current,
+            reward,
+        });
+    }
+    if crashed.read().count() > 0 {
+        streak.current = 0;
+        streak.shield_ticks = 0;
+    }
+}
+
+// Paid rewards are announced by the wallet
+fn announce_shield(
+    mut milestones: EventReader<StreakMilestone>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for milestone in milestones.read() {
+        if milestone.reward == Reward::Shield {
+            toasts.send(ShowToast(format!(
+                "{} in a row! Shielded for {} seconds",
+                milestone.streak,
+                SHIELD_TICKS / FIXED_HZ as u32
+            )));
+        }
+    }
+}
+
+// A bubble around every bird while the shield lasts
+fn show_shield(
+    mut commands: Commands,
+    streak: Res<Streak>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    birds: Query<Entity, With<Bird>>,
+    bubbles: Query<Entity, With<ShieldBubble>>,
+) {
+    if !streak.is_changed() || streak.shielded() != bubbles.is_empty() {
+        return;
+    }
+    if !streak.shielded() {
+        for e in &bubbles {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+    let mesh = meshes.add(Circle::new(SHIELD_RADIUS));
+    let material = materials.add(SHIELD_COLOR);
+    for bird in &birds {
+        commands.entity(bird).with_children(|parent| {
+            parent.spawn((
+                bevy::sprite::MaterialMesh2dBundle {
+                    mesh: mesh.clone().into(),
+                    material: material.clone(),
+                    // Behind the bird
+                    transform: Transform::from_xyz(0.0, 0.0, -0.05),
+                    ..default()
+                },
+                ShieldBubble,
+            ));
+        });
+    }
+}
+
+// How far the streak is from the last milestone to the next, shown during runs
+fn update_pips(
+    streak: Res<Streak>,
+    state: Res<State<GameState>>,
+    mut rows: Query<&mut Visibility, With<StreakPips>>,
+    mut pips: Query<(&Pip, &mut BackgroundColor)>,
+) {
+    if state.is_changed() {
+        let in_run = matches!(state.get(), GameState::Playing | GameState::Replay);
+        for mut visibility in &mut rows {
+            *visibility = if in_run {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+    if !streak.is_changed() {
+        return;
+    }
+    let last = MILESTONES
+        .iter()
+        .map(|&(at, _)| at)
+        .rfind(|&at| at <= streak.current)
+        .unwrap_or(0);
+    let filled = match MILESTONES.iter().find(|(at, _)| *at > streak.current) {
+        Some(&(next, _)) => (streak.current - last) as usize * PIPS / (next - last) as usize,
+        None => PIPS,
+    };
+    for (pip, mut color) in &mut pips {
+        let wanted = if pip.0 < filled { PIP_FULL } else { PIP_EMPTY };
+        if color.0 != wanted {
+            color.0 = wanted;
+        }
+    }
+}
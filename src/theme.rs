@@ -0,0 +1,398 @@
+// Theme: how the game is drawn, worked out from the accessibility settings.
+// Systems that draw things read `Theme` rather than the settings directly. The
+// Night modifier darkens the city on top of whatever the theme is, Ice frosts
+// it and the pipes, and Mirror and Upside down flip the camera. Colors from
+// palette.txt (see palette.rs) replace the classic ones, and pipe colors bought
+// in the shop replace those.
+
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+
+use crate::modifiers::Modifier;
+use crate::palette;
+use crate::settings::{AccessibilitySettings, Palette, PipeStyle};
+use crate::wallet::{Cosmetic, Slot, Wallet};
+use crate::{BackgroundLayer, Bird, Pipe, PipeAssets, RunModifiers, BIRD_SIZE};
+
+const OUTLINE_WIDTH: f32 = 4.0;
+const BIRD_OUTLINE_WIDTH: f32 = 2.0;
+const HIGH_CONTRAST_BACKGROUND: Color = Color::srgb(0.04, 0.04, 0.06);
+const HIGH_CONTRAST_TEXT_SCALE: f32 = 1.3;
+const STRIPE_HEIGHT: f32 = 10.0;
+const STRIPE_SPACING: f32 = 32.0;
+const NIGHT_TINT: Color = Color::srgb(0.3, 0.35, 0.6);
+const ICE_TINT: Color = Color::srgb(0.8, 0.9, 1.0);
+
+#[derive(Resource, Clone, PartialEq)]
+pub struct Theme {
+    pub pipe_color: Color,
+    // Outline and stripe color, picked to stand out against pipe_color
+    pub pipe_accent: Color,
+    pub pipe_style: PipeStyle,
+    // Pipe and accent colors for an Ice run, where the palette leaves them free
+    pub ice_pipes: Option<(Color, Color)>,
+    // Solid color instead of the city background
+    pub background: Option<Color>,
+    // Clear color behind the city, if not the game's own
+    pub sky: Option<Color>,
+    // Frame around the bird
+    pub bird_outline: Option<Color>,
+    // Multiplier on every UI font size
+    pub text_scale: f32,
+    // Draw dark text in white instead, for dark backgrounds
+    pub light_text: bool,
+    // Colors for the UI's dark and light text, if not as spawned
+    pub text_color: Option<Color>,
+    pub light_text_color: Option<Color>,
+}
+
+impl Theme {
+    fn from_settings(accessibility: &AccessibilitySettings, colors: &palette::Palette) -> Self {
+        let (pipe_color, pipe_accent) = match accessibility.palette {
+            // Dark purple/maroon color to match the floor of the background
+            Palette::Classic => (
+                colors.pipes.unwrap_or(Color::srgb(0.2, 0.024, 0.176)),
+                colors.pipe_accent.unwrap_or(Color::WHITE),
+            ),
+            Palette::Orange => (Color::srgb(0.902, 0.624, 0.0), Color::BLACK),
+            Palette::Blue => (Color::srgb(0.0, 0.447, 0.698), Color::WHITE),
+            Palette::Yellow => (Color::srgb(0.941, 0.894, 0.259), Color::BLACK),
+        };
+        if accessibility.high_contrast {
+            return Self {
+                pipe_color: Color::srgb(1.0, 0.85, 0.0),
+                pipe_accent: Color::WHITE,
+                pipe_style: match accessibility.pipe_style {
+                    PipeStyle::Plain => PipeStyle::Outline,
+                    style => style,
+                },
+                ice_pipes: None,
+                background: Some(HIGH_CONTRAST_BACKGROUND),
+                sky: None,
+                bird_outline: Some(Color::WHITE),
+                text_scale: HIGH_CONTRAST_TEXT_SCALE,
+                light_text: true,
+                text_color: None,
+                light_text_color: None,
+            };
+        }
+        Self {
+            pipe_color,
+            pipe_accent,
+            pipe_style: accessibility.pipe_style,
+            // The colorblind palettes keep their colors on ice
+            ice_pipes: (accessibility.palette == Palette::Classic)
+                .then_some((Color::srgb(0.62, 0.84, 0.96), Color::srgb(0.1, 0.3, 0.55))),
+            background: None,
+            sky: colors.sky,
+            bird_outline: None,
+            text_scale: 1.0,
+            light_text: false,
+            text_color: colors.text,
+            light_text_color: colors.light_text,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::from_settings(
+            &AccessibilitySettings::default(),
+            &palette::Palette::default(),
+        )
+    }
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>().add_systems(
+            Update,
+            (
+                update_theme,
+                (
+                    style_pipes,
+                    style_bird,
+                    style_background,
+                    style_night,
+                    style_flip,
+                    style_text,
+                ),
+            )
+                .chain(),
+        );
+    }
+}
+
+// Outline or stripe parented to a pipe
+#[derive(Component)]
+struct PipeDecoration;
+
+#[derive(Component)]
+struct BirdOutline;
+
+// Font size and color of each section as spawned, before the theme touched them
+#[derive(Component)]
+struct BaseTextStyle(Vec<(f32, Color)>);
+
+fn update_theme(
+    accessibility: Res<AccessibilitySettings>,
+    colors: Res<palette::Palette>,
+    wallet: Res<Wallet>,
+    mut theme: ResMut<Theme>,
+) {
+    if !accessibility.is_changed() && !colors.is_changed() && !wallet.is_changed() {
+        return;
+    }
+    let mut new_theme = Theme::from_settings(&accessibility, &colors);
+    // The colorblind palettes and high contrast keep their colors
+    let pipe_colors = wallet
+        .wearing(Slot::PipeColor)
+        .and_then(Cosmetic::pipe_colors);
+    if let Some((body, accent)) = pipe_colors {
+        if accessibility.palette == Palette::Classic && !accessibility.high_contrast {
+            new_theme.pipe_color = body;
+            new_theme.pipe_accent = accent;
+        }
+    }
+    if *theme != new_theme {
+        *theme = new_theme;
+    }
+}
+
+// Recolor the shared pipe materials, and decorate new pipes (or all of them
+// when the theme changes)
+fn style_pipes(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    run_modifiers: Res<RunModifiers>,
+    assets: Res<PipeAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    pipes: Query<(Entity, Option<&Children>, Ref<Pipe>)>,
+    decorations: Query<(), With<PipeDecoration>>,
+) {
+    if theme.is_changed() || run_modifiers.is_changed() {
+        let (body, accent) = theme
+            .ice_pipes
+            .filter(|_| run_modifiers.0.has(Modifier::Ice))
+            .unwrap_or((theme.pipe_color, theme.pipe_accent));
+        for (handle, color) in [(&assets.body, body), (&assets.accent, accent)] {
+            if let Some(material) = materials.get_mut(handle) {
+                material.color = color;
+            }
+        }
+    }
+
+    for (e, children, pipe) in &pipes {
+        if !theme.is_changed() && !pipe.is_added() {
+            continue;
+        }
+        profile_span!("decorate_pipe");
+        for &child in children.into_iter().flatten() {
+            if decorations.contains(child) {
+                commands.entity(child).despawn_recursive();
+            }
+        }
+
+        let size = pipe.size;
+        commands
+            .entity(e)
+            .with_children(|parent| match theme.pipe_style {
+                PipeStyle::Plain => {}
+                PipeStyle::Outline => {
+                    parent.spawn(pipe_decoration(
+                        &assets,
+                        size + OUTLINE_WIDTH * 2.0,
+                        Vec3::Z * -0.5,
+                    ));
+                }
+                PipeStyle::Stripes => {
+                    let mut y = -size.y * 0.5 + STRIPE_SPACING * 0.5;
+                    while y < size.y * 0.5 - STRIPE_HEIGHT * 0.5 {
+                        let stripe = Vec2::new(size.x, STRIPE_HEIGHT);
+                        parent.spawn(pipe_decoration(&assets, stripe, Vec3::new(0.0, y, 0.5)));
+                        y += STRIPE_SPACING;
+                    }
+                }
+            });
+    }
+}
+
+fn pipe_decoration(
+    assets: &PipeAssets,
+    size: Vec2,
+    offset: Vec3,
+) -> (MaterialMesh2dBundle<ColorMaterial>, PipeDecoration) {
+    (
+        MaterialMesh2dBundle {
+            mesh: assets.quad.clone(),
+            material: assets.accent.clone(),
+            transform: Transform::from_translation(offset).with_scale(size.extend(1.0)),
+            ..default()
+        },
+        PipeDecoration,
+    )
+}
+
+fn outline_bar(color: Color, size: Vec2, offset: Vec3) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(size),
+            ..default()
+        },
+        transform: Transform::from_translation(offset),
+        ..default()
+    }
+}
+
+fn style_bird(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    birds: Query<(Entity, Ref<Bird>)>,
+    outlines: Query<(Entity, &Parent), With<BirdOutline>>,
+) {
+    for (e, bird) in &birds {
+        if !theme.is_changed() && !bird.is_added() {
+            continue;
+        }
+        for (outline, parent) in &outlines {
+            if parent.get() == e {
+                commands.entity(outline).despawn_recursive();
+            }
+        }
+        let Some(color) = theme.bird_outline else {
+            continue;
+        };
+        // Four thin bars framing the bird's hitbox
+        let w = BIRD_OUTLINE_WIDTH;
+        let (half_x, half_y) = (BIRD_SIZE.x * 0.5 + w * 0.5, BIRD_SIZE.y * 0.5 + w * 0.5);
+        let horizontal = Vec2::new(BIRD_SIZE.x + w * 2.0, w);
+        let vertical = Vec2::new(w, BIRD_SIZE.y + w * 2.0);
+        commands.entity(e).with_children(|parent| {
+            for (size, offset) in [
+                (horizontal, Vec3::new(0.0, half_y, 0.5)),
+                (horizontal, Vec3::new(0.0, -half_y, 0.5)),
+                (vertical, Vec3::new(half_x, 0.0, 0.5)),
+                (vertical, Vec3::new(-half_x, 0.0, 0.5)),
+            ] {
+                parent.spawn((outline_bar(color, size, offset), BirdOutline));
+            }
+        });
+    }
+}
+
+fn style_background(
+    theme: Res<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+    // The clear color to go back to when the theme has no background of its own
+    mut original_clear: Local<Option<Color>>,
+    mut layers: Query<(&mut Visibility, Ref<BackgroundLayer>)>,
+) {
+    let original = *original_clear.get_or_insert(clear_color.0);
+    if theme.is_changed() {
+        // Leave a transparent (overlay) window transparent
+        let wanted = match theme.background.or(theme.sky) {
+            Some(color) if original.alpha() > 0.0 => color,
+            _ => original,
+        };
+        if clear_color.0 != wanted {
+            clear_color.0 = wanted;
+        }
+    }
+    for (mut visibility, layer) in &mut layers {
+        if theme.is_changed() || layer.is_added() {
+            *visibility = if theme.background.is_some() {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+        }
+    }
+}
+
+fn style_night(
+    run_modifiers: Res<RunModifiers>,
+    mut layers: Query<(&mut Sprite, Ref<BackgroundLayer>)>,
+) {
+    let tint = if run_modifiers.0.has(Modifier::Night) {
+        NIGHT_TINT
+    } else if run_modifiers.0.has(Modifier::Ice) {
+        ICE_TINT
+    } else {
+        Color::WHITE
+    };
+    for (mut sprite, layer) in &mut layers {
+        if run_modifiers.is_changed() || layer.is_added() {
+            sprite.color = tint;
+        }
+    }
+}
+
+// A camera flipped on x mirrors the whole scene, and one flipped on y turns it
+// upside down, so gravity pulls the bird up the screen; UI isn't drawn through
+// the camera's transform, so text stays readable
+fn style_flip(
+    run_modifiers: Res<RunModifiers>,
+    mut cameras: Query<&mut Transform, With<Camera2d>>,
+) {
+    if !run_modifiers.is_changed() {
+        return;
+    }
+    let flip = |modifier| {
+        if run_modifiers.0.has(modifier) {
+            -1.0
+        } else {
+            1.0
+        }
+    };
+    for mut tf in &mut cameras {
+        tf.scale.x = flip(Modifier::Mirror);
+        tf.scale.y = flip(Modifier::UpsideDown);
+    }
+}
+
+fn style_text(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    mut texts: Query<(Entity, &mut Text, Option<&BaseTextStyle>)>,
+) {
+    let unchanged = theme.text_scale == 1.0
+        && !theme.light_text
+        && theme.text_color.is_none()
+        && theme.light_text_color.is_none();
+    profile_span!("style_text_sections");
+    for (e, mut text, base) in &mut texts {
+        let base = match base {
+            Some(_) if !theme.is_changed() => continue,
+            Some(base) => base.0.clone(),
+            None => {
+                let base: Vec<(f32, Color)> = text
+                    .sections
+                    .iter()
+                    .map(|s| (s.style.font_size, s.style.color))
+                    .collect();
+                commands.entity(e).insert(BaseTextStyle(base.clone()));
+                if unchanged {
+                    continue;
+                }
+                base
+            }
+        };
+        for (section, (size, color)) in text.sections.iter_mut().zip(base) {
+            section.style.font_size = size * theme.text_scale;
+            let luminance = color.luminance();
+            let recolor = if luminance < 0.1 && theme.light_text {
+                Some(Color::WHITE)
+            } else if luminance < 0.1 {
+                theme.text_color
+            } else if luminance > 0.9 {
+                theme.light_text_color
+            } else {
+                None
+            };
+            section.style.color = recolor.map_or(color, |c| c.with_alpha(color.alpha()));
+        }
+    }
+}
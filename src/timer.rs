@@ -0,0 +1,93 @@
+// Run timer (Run timer in the settings menu): elapsed time and pace in points
+// per minute in the corner during a run, and on the game over screen. Time is
+// counted in fixed steps, so it's the same on replay, and at practice speed
+// measures the run rather than the wall clock.
+
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::{GameState, RunTick, Score, FIXED_HZ};
+
+pub struct RunTimerPlugin;
+
+impl Plugin for RunTimerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_timer_text)
+            .add_systems(Update, update_timer_text);
+    }
+}
+
+// Seconds of play after this many fixed steps
+pub fn run_seconds(ticks: u32) -> f32 {
+    ticks as f32 / FIXED_HZ as f32
+}
+
+// "1:05.32"
+pub fn format_time(seconds: f32) -> String {
+    let minutes = (seconds / 60.0).floor();
+    format!("{minutes:.0}:{:05.2}", seconds - minutes * 60.0)
+}
+
+// Points per minute, or 0 before the first second is up
+pub fn pace(score: u32, seconds: f32) -> f32 {
+    if seconds < 1.0 {
+        0.0
+    } else {
+        score as f32 * 60.0 / seconds
+    }
+}
+
+// "Time 1:05.32 - 11.0 points/min"
+pub fn summary(score: u32, ticks: u32) -> String {
+    let seconds = run_seconds(ticks);
+    format!(
+        "Time {} - {:.1} points/min",
+        format_time(seconds),
+        pace(score, seconds)
+    )
+}
+
+#[derive(Component)]
+struct TimerText;
+
+fn spawn_timer_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 22.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        TimerText,
+    ));
+}
+
+// Only during a run, and only written when it reads differently
+fn update_timer_text(
+    state: Res<State<GameState>>,
+    settings: Res<Settings>,
+    score: Res<Score>,
+    run_tick: Res<RunTick>,
+    mut q: Query<&mut Text, With<TimerText>>,
+) {
+    let Ok(mut text) = q.get_single_mut() else {
+        return;
+    };
+    let running = matches!(state.get(), GameState::Playing | GameState::Replay);
+    let value = if settings.run_timer && running {
+        summary(score.0, run_tick.0)
+    } else {
+        String::new()
+    };
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
@@ -0,0 +1,92 @@
+// Short-lived notification text near the bottom of the screen
+
+use bevy::prelude::*;
+
+const TOAST_SECS: f32 = 2.5;
+// Fade out over the last part of the toast's life
+const TOAST_FADE_SECS: f32 = 0.5;
+
+// Send this to show a toast; a new toast replaces the current one
+#[derive(Event)]
+pub struct ShowToast(pub String);
+
+#[derive(Component)]
+struct Toast {
+    timer: Timer,
+}
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShowToast>()
+            .add_systems(Update, (spawn_toasts, fade_toasts).chain());
+    }
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    mut events: EventReader<ShowToast>,
+    existing: Query<Entity, With<Toast>>,
+) {
+    let Some(ShowToast(message)) = events.read().last() else {
+        return;
+    };
+    for e in &existing {
+        commands.entity(e).despawn_recursive();
+    }
+
+    // Full-width row so the text centers itself
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(40.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            Toast {
+                timer: Timer::from_seconds(TOAST_SECS, TimerMode::Once),
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    message.clone(),
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            );
+        });
+}
+
+fn fade_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut Toast, &Children)>,
+    mut texts: Query<(&mut Text, &mut BackgroundColor)>,
+) {
+    for (e, mut toast, children) in &mut toasts {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        let alpha = (toast.timer.remaining_secs() / TOAST_FADE_SECS).min(1.0);
+        for &child in children {
+            if let Ok((mut text, mut bg)) = texts.get_mut(child) {
+                for section in &mut text.sections {
+                    section.style.color.set_alpha(alpha);
+                }
+                bg.0.set_alpha(alpha * 0.6);
+            }
+        }
+    }
+}
@@ -0,0 +1,472 @@
+// Party tournament: E on the main menu. Two to eight players sharing the
+// keyboard type in their names, then play a knockout bracket: in each match both
+// players fly one run in turn on the same course, and the higher score goes
+// through (a tie replays the match on a new course; an odd player out gets a
+// bye). The bracket screen between runs shows the round, who's up next and the
+// standings, until one player is left as the champion.
+//
+// Esc on the bracket screen goes back to the menu with the tournament kept, and
+// E picks it up again; X there abandons it.
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::controls::GameInput;
+use crate::photo::PhotoMode;
+use crate::toast::ShowToast;
+use crate::{GameState, NextRunSeed, Score, WINDOW_W};
+
+const MAX_PLAYERS: usize = 8;
+const MAX_NAME_LEN: usize = 12;
+
+pub struct TournamentPlugin;
+
+impl Plugin for TournamentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tournament>()
+            .add_systems(Update, open_tournament.run_if(in_state(GameState::Menu)))
+            .add_systems(OnEnter(GameState::Tournament), show_tournament)
+            .add_systems(OnExit(GameState::Tournament), despawn_tournament)
+            .add_systems(
+                Update,
+                (
+                    tournament_input,
+                    update_tournament.run_if(in_state(GameState::Tournament)),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                record_run.run_if(resource_exists::<TournamentRun>),
+            )
+            .add_systems(
+                Update,
+                back_to_bracket.run_if(
+                    in_state(GameState::GameOver)
+                        .and_then(resource_exists::<TournamentRun>)
+                        .and_then(not(resource_exists::<PhotoMode>)),
+                ),
+            )
+            // Left for the menu from the game over screen: the run counts all the same
+            .add_systems(
+                OnEnter(GameState::Menu),
+                (record_run, end_run)
+                    .chain()
+                    .run_if(resource_exists::<TournamentRun>),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Tournament {
+    players: Vec<Player>,
+    phase: Phase,
+    round: u32,
+    matches: Vec<Match>,
+    // The name being typed in
+    entry: String,
+}
+
+#[derive(Default, PartialEq, Eq, Clone, Copy)]
+enum Phase {
+    #[default]
+    Entry,
+    Bracket,
+    Champion(usize),
+}
+
+struct Player {
+    name: String,
+    wins: u32,
+    runs: u32,
+    best: u32,
+    // Round the player was knocked out in
+    out_in: Option<u32>,
+}
+
+// Two players by index into Tournament::players, or one with a bye
+struct Match {
+    players: [usize; 2],
+    bye: bool,
+    scores: [Option<u32>; 2],
+    // Both fly the same course
+    seed: u32,
+}
+
+impl Match {
+    fn new(a: usize, b: Option<usize>) -> Self {
+        Self {
+            players: [a, b.unwrap_or(a)],
+            bye: b.is_none(),
+            scores: [None; 2],
+            seed: rand::random(),
+        }
+    }
+
+    // Whose run is next: 0 or 1
+    fn next_side(&self) -> Option<usize> {
+        if self.bye {
+            return None;
+        }
+        self.scores.iter().position(Option::is_none)
+    }
+
+    fn winner(&self) -> Option<usize> {
+        match self.scores {
+            _ if self.bye => Some(self.players[0]),
+            [Some(a), Some(b)] if a > b => Some(self.players[0]),
+            [Some(a), Some(b)] if b > a => Some(self.players[1]),
+            _ => None,
+        }
+    }
+}
+
+impl Tournament {
+    fn start(&mut self) {
+        self.phase = Phase::Bracket;
+        self.round = 1;
+        self.draw((0..self.players.len()).collect());
+    }
+
+    // Pair up who's left in entry order, the odd one out getting a bye
+    fn draw(&mut self, left: Vec<usize>) {
+        self.matches = left
+            .chunks(2)
+            .map(|pair| Match::new(pair[0], pair.get(1).copied()))
+            .collect();
+    }
+
+    // The match and side whose run is next
+    fn next_up(&self) -> Option<(usize, usize)> {
+        self.matches
+            .iter()
+            .enumerate()
+            .find_map(|(i, m)| m.next_side().map(|side| (i, side)))
+    }
+
+    fn rounds(&self) -> u32 {
+        self.players.len().next_power_of_two().trailing_zeros()
+    }
+
+    fn record(&mut self, score: u32) {
+        let Some((i, side)) = self.next_up() else {
+            return;
+        };
+        let round = self.round;
+        let m = &mut self.matches[i];
+        m.scores[side] = Some(score);
+        let player = &mut self.players[m.players[side]];
+        player.runs += 1;
+        player.best = player.best.max(score);
+
+        if m.next_side().is_some() {
+            return;
+        }
+        match m.winner() {
+            Some(winner) => {
+                let loser = m.players[usize::from(m.players[0] == winner)];
+                self.players[winner].wins += 1;
+                self.players[loser].out_in = Some(round);
+            }
+            // A tie: again, on a new course
+            None => *m = Match::new(m.players[0], Some(m.players[1])),
+        }
+
+        if self.next_up().is_none() {
+            let left: Vec<usize> = self.matches.iter().filter_map(Match::winner).collect();
+            if let [champion] = left[..] {
+                self.phase = Phase::Champion(champion);
+            } else {
+                self.round += 1;
+                self.draw(left);
+            }
+        }
+    }
+
+    // Still in first, then by wins and best run
+    fn standings(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.players.len()).collect();
+        order.sort_by_key(|&i| {
+            let p = &self.players[i];
+            (
+                std::cmp::Reverse(p.out_in.unwrap_or(u32::MAX)),
+                std::cmp::Reverse(p.wins),
+                std::cmp::Reverse(p.best),
+            )
+        });
+        order
+    }
+}
+
+// A tournament run is being flown (or just ended), so the game over screen
+// leads back to the bracket instead of retrying
+#[derive(Resource)]
+pub struct TournamentRun {
+    recorded: bool,
+}
+
+#[derive(Component)]
+struct TournamentUI;
+
+#[derive(Component)]
+struct BracketText;
+
+#[derive(Component)]
+struct StandingsText;
+
+fn open_tournament(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if input.just_pressed(KeyCode::KeyE) {
+        next_state.set(GameState::Tournament);
+    }
+}
+
+fn show_tournament(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "Tournament",
+            TextStyle {
+                font_size: 56.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(30.0),
+            left: Val::Px(WINDOW_W * 0.5 - 140.0),
+            ..default()
+        }),
+        TournamentUI,
+    ));
+    // Both filled in by update_tournament
+    for (left, marker) in [(40.0, true), (WINDOW_W * 0.5 + 40.0, false)] {
+        let mut text = commands.spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(110.0),
+                left: Val::Px(left),
+                ..default()
+            }),
+            TournamentUI,
+        ));
+        if marker {
+            text.insert(BracketText);
+        } else {
+            text.insert(StandingsText);
+        }
+    }
+}
+
+fn despawn_tournament(mut commands: Commands, q: Query<Entity, With<TournamentUI>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// Runs in every state so it has always read past the keys pressed before the
+// screen opened, E included
+fn tournament_input(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    mut keys: EventReader<KeyboardInput>,
+    mut tournament: ResMut<Tournament>,
+    mut next_seed: ResMut<NextRunSeed>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if *state.get() != GameState::Tournament {
+        keys.clear();
+        return;
+    }
+    for key in keys.read() {
+        if !key.state.is_pressed() {
+            continue;
+        }
+        match (tournament.phase, &key.logical_key) {
+            (Phase::Entry, Key::Escape) => {
+                *tournament = Tournament::default();
+                next_state.set(GameState::Menu);
+            }
+            (Phase::Entry, Key::Backspace) => {
+                tournament.entry.pop();
+            }
+            (Phase::Entry, Key::Enter) if tournament.entry.trim().is_empty() => {
+                if tournament.players.len() < 2 {
+                    toasts.send(ShowToast("Add at least two players".to_string()));
+                } else {
+                    tournament.start();
+                }
+            }
+            (Phase::Entry, Key::Enter) => {
+                let name = std::mem::take(&mut tournament.entry).trim().to_string();
+                tournament.players.push(Player {
+                    name,
+                    wins: 0,
+                    runs: 0,
+                    best: 0,
+                    out_in: None,
+                });
+                if tournament.players.len() == MAX_PLAYERS {
+                    tournament.start();
+                }
+            }
+            (Phase::Entry, Key::Character(text)) => {
+                for c in text.chars() {
+                    if tournament.entry.chars().count() < MAX_NAME_LEN
+                        && (c.is_alphanumeric() || c == ' ')
+                    {
+                        tournament.entry.push(c);
+                    }
+                }
+            }
+            (Phase::Entry, Key::Space) if tournament.entry.chars().count() < MAX_NAME_LEN => {
+                tournament.entry.push(' ');
+            }
+            (Phase::Bracket, Key::Enter) => {
+                let Some((i, _)) = tournament.next_up() else {
+                    continue;
+                };
+                next_seed.0 = Some(tournament.matches[i].seed.into());
+                commands.insert_resource(TournamentRun { recorded: false });
+                next_state.set(GameState::Playing);
+            }
+            (Phase::Bracket, Key::Character(c)) if c.eq_ignore_ascii_case("x") => {
+                *tournament = Tournament::default();
+                next_state.set(GameState::Menu);
+            }
+            (Phase::Bracket, Key::Escape) => next_state.set(GameState::Menu),
+            (Phase::Champion(_), Key::Enter | Key::Escape) => {
+                *tournament = Tournament::default();
+                next_state.set(GameState::Menu);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn record_run(
+    score: Res<Score>,
+    mut run: ResMut<TournamentRun>,
+    mut tournament: ResMut<Tournament>,
+) {
+    if !run.recorded {
+        run.recorded = true;
+        tournament.record(score.0);
+    }
+}
+
+fn end_run(mut commands: Commands) {
+    commands.remove_resource::<TournamentRun>();
+}
+
+fn back_to_bracket(
+    mut commands: Commands,
+    controls: GameInput,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if controls.retry() {
+        commands.remove_resource::<TournamentRun>();
+        next_state.set(GameState::Tournament);
+    }
+}
+
+fn update_tournament(
+    tournament: Res<Tournament>,
+    mut bracket_q: Query<&mut Text, (With<BracketText>, Without<StandingsText>)>,
+    mut standings_q: Query<&mut Text, With<StandingsText>>,
+) {
+    let (Ok(mut bracket), Ok(mut standings)) =
+        (bracket_q.get_single_mut(), standings_q.get_single_mut())
+    else {
+        return;
+    };
+    let name = |i: usize| tournament.players[i].name.as_str();
+
+    let mut left = String::new();
+    let mut right = String::new();
+    match tournament.phase {
+        Phase::Entry => {
+            left.push_str("Type each player's name and press Enter\n\n");
+            for (i, player) in tournament.players.iter().enumerate() {
+                left.push_str(&format!("{}. {}\n", i + 1, player.name));
+            }
+            if tournament.players.len() < MAX_PLAYERS {
+                left.push_str(&format!(
+                    "{}. {}_\n",
+                    tournament.players.len() + 1,
+                    tournament.entry
+                ));
+            }
+            left.push_str(&format!(
+                "\n2 to {MAX_PLAYERS} players. Enter on an empty name starts\nEsc to go back"
+            ));
+        }
+        Phase::Bracket => {
+            left.push_str(&format!(
+                "Round {} of {}\n\n",
+                tournament.round,
+                tournament.rounds()
+            ));
+            for m in &tournament.matches {
+                let [a, b] = m.players;
+                if m.bye {
+                    left.push_str(&format!("{} (bye)\n", name(a)));
+                    continue;
+                }
+                let score = |s: Option<u32>| s.map_or("-".to_string(), |s| s.to_string());
+                left.push_str(&format!(
+                    "{} {} vs {} {}\n",
+                    name(a),
+                    score(m.scores[0]),
+                    score(m.scores[1]),
+                    name(b)
+                ));
+            }
+            if let Some((i, side)) = tournament.next_up() {
+                left.push_str(&format!(
+                    "\nUp next: {}\nEnter to fly, Esc for the menu, X to abandon",
+                    name(tournament.matches[i].players[side])
+                ));
+            }
+        }
+        Phase::Champion(champion) => {
+            left.push_str(&format!(
+                "{} is the champion!\n\nEnter to finish",
+                name(champion)
+            ));
+        }
+    }
+
+    if tournament.phase != Phase::Entry {
+        right.push_str("Standings\n\n");
+        for (place, i) in tournament.standings().into_iter().enumerate() {
+            let p = &tournament.players[i];
+            let status = match p.out_in {
+                Some(round) => format!("out in round {round}"),
+                None => "still in".to_string(),
+            };
+            right.push_str(&format!(
+                "{}. {} - {} wins, best {} ({status})\n",
+                place + 1,
+                p.name,
+                p.wins,
+                p.best
+            ));
+        }
+    }
+
+    if bracket.sections[0].value != left {
+        bracket.sections[0].value = left;
+    }
+    if standings.sections[0].value != right {
+        standings.sections[0].value = right;
+    }
+}
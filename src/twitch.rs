@@ -0,0 +1,244 @@
+// Twitch "chat plays" mode (launch with `--twitch <channel>`)
+//
+// A background thread joins the channel's IRC chat anonymously and forwards
+// messages to the game. Chat commands become buffered flaps, rate limited so a
+// flood of "flap" messages can't pin the bird to the ceiling.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{FlapInput, GameState};
+
+const TWITCH_IRC_ADDR: &str = "irc.chat.twitch.tv:6667";
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+// Chat messages that count as a flap (compared case-insensitively)
+const FLAP_COMMANDS: [&str; 4] = ["flap", "!flap", "jump", "!jump"];
+// Minimum time between any two chat flaps
+const CHAT_FLAP_COOLDOWN: f32 = 0.25;
+// Minimum time between flaps from the same viewer
+const CHAT_USER_COOLDOWN: f32 = 1.0;
+// Let the game over screen breathe before chat can restart
+const CHAT_RESTART_DELAY: f32 = 2.0;
+const CHAT_FEED_LINES: usize = 5;
+
+pub struct TwitchPlugin {
+    pub channel: String,
+}
+
+impl Plugin for TwitchPlugin {
+    fn build(&self, app: &mut App) {
+        let channel = self.channel.trim_start_matches('#').to_lowercase();
+        let (tx, rx) = mpsc::channel();
+        let thread_channel = channel.clone();
+        thread::spawn(move || run_chat_client(thread_channel, tx));
+
+        app.insert_resource(ChatLink(Mutex::new(rx)))
+            .insert_resource(ChatFeed {
+                channel,
+                lines: VecDeque::new(),
+            })
+            .init_resource::<ChatRateLimit>()
+            .add_systems(Startup, spawn_chat_widget)
+            .add_systems(OnEnter(GameState::GameOver), mark_death_time)
+            .add_systems(Update, (receive_chat, update_chat_widget).chain());
+    }
+}
+
+struct ChatMessage {
+    user: String,
+    text: String,
+}
+
+// Receiving end of the IRC thread (Mutex so the resource is Sync)
+#[derive(Resource)]
+struct ChatLink(Mutex<Receiver<ChatMessage>>);
+
+// Recent accepted chat commands, shown in the on-screen widget
+#[derive(Resource)]
+struct ChatFeed {
+    channel: String,
+    lines: VecDeque<String>,
+}
+
+// Times are seconds since startup
+#[derive(Resource, Default)]
+struct ChatRateLimit {
+    last_flap: Option<f32>,
+    last_flap_by_user: HashMap<String, f32>,
+    died_at: f32,
+}
+
+#[derive(Component)]
+struct ChatWidget;
+
+// --------------------------------------------
+// IRC client thread
+// --------------------------------------------
+
+fn run_chat_client(channel: String, tx: Sender<ChatMessage>) {
+    loop {
+        match read_chat(&channel, &tx) {
+            // Game closed the channel
+            Ok(()) => return,
+            Err(err) => warn!("Twitch chat connection lost ({err}), reconnecting"),
+        }
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+fn read_chat(channel: &str, tx: &Sender<ChatMessage>) -> io::Result<()> {
+    let mut stream = TcpStream::connect(TWITCH_IRC_ADDR)?;
+    // justinfan<digits> is Twitch's anonymous, read-only login
+    let nick = format!("justinfan{}", rand::thread_rng().gen_range(10_000..100_000));
+    write!(stream, "NICK {nick}\r\nJOIN #{channel}\r\n")?;
+    info!("Joined Twitch chat #{channel}");
+
+    for line in BufReader::new(stream.try_clone()?).lines() {
+        let line = line?;
+        if let Some(server) = line.strip_prefix("PING ") {
+            write!(stream, "PONG {server}\r\n")?;
+            continue;
+        }
+        if let Some((user, text)) = parse_privmsg(&line) {
+            let message = ChatMessage {
+                user: user.to_string(),
+                text: text.to_string(),
+            };
+            if tx.send(message).is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Err(io::ErrorKind::UnexpectedEof.into())
+}
+
+// ":nick!nick@nick.tmi.twitch.tv PRIVMSG #channel :message" -> (nick, message)
+fn parse_privmsg(line: &str) -> Option<(&str, &str)> {
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let (_target, message) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+    let nick = prefix.split('!').next()?;
+    Some((nick, message))
+}
+
+// --------------------------------------------
+// Chat input
+// --------------------------------------------
+
+fn mark_death_time(time: Res<Time>, mut limit: ResMut<ChatRateLimit>) {
+    limit.died_at = time.elapsed_seconds();
+}
+
+fn receive_chat(
+    time: Res<Time>,
+    link: Res<ChatLink>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut flap_input: ResMut<FlapInput>,
+    mut limit: ResMut<ChatRateLimit>,
+    mut feed: ResMut<ChatFeed>,
+) {
+    let Ok(rx) = link.0.lock() else {
+        return;
+    };
+    let now = time.elapsed_seconds();
+
+    for message in rx.try_iter() {
+        let text = message.text.trim();
+        if !FLAP_COMMANDS.iter().any(|c| c.eq_ignore_ascii_case(text)) {
+            continue;
+        }
+
+        let global_ready = limit
+            .last_flap
+            .is_none_or(|t| now - t >= CHAT_FLAP_COOLDOWN);
+        let user_ready = limit
+            .last_flap_by_user
+            .get(&message.user)
+            .is_none_or(|t| now - t >= CHAT_USER_COOLDOWN);
+        if !global_ready || !user_ready {
+            continue;
+        }
+
+        match state.get() {
+            GameState::Playing => flap_input.requested = true,
+            GameState::Menu => next_state.set(GameState::Playing),
+            // Let the streamer fiddle with mods and settings, or watch a replay, in peace
+            GameState::Replay
+            | GameState::Mods
+            | GameState::Settings
+            | GameState::ChallengeEntry
+            | GameState::Lobby
+            | GameState::Tournament
+            | GameState::HotSeat
+            | GameState::Presets
+            | GameState::Shop
+            | GameState::Story => continue,
+            GameState::GameOver => {
+                if now - limit.died_at < CHAT_RESTART_DELAY {
+                    continue;
+                }
+                next_state.set(GameState::Playing);
+            }
+        }
+
+        limit.last_flap = Some(now);
+        limit.last_flap_by_user.insert(message.user.clone(), now);
+        feed.lines.push_back(format!("{}: {}", message.user, text));
+        if feed.lines.len() > CHAT_FEED_LINES {
+            feed.lines.pop_front();
+        }
+    }
+}
+
+// --------------------------------------------
+// Chat activity widget
+// --------------------------------------------
+
+fn spawn_chat_widget(mut commands: Commands, feed: Res<ChatFeed>) {
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                format!("Twitch #{} - type flap or !jump\n", feed.channel),
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::srgb(0.75, 0.6, 1.0),
+                    ..default()
+                },
+            ),
+            TextSection::new(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        ChatWidget,
+    ));
+}
+
+fn update_chat_widget(feed: Res<ChatFeed>, mut q: Query<&mut Text, With<ChatWidget>>) {
+    if !feed.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = q.get_single_mut() {
+        if let Some(section) = text.sections.get_mut(1) {
+            section.value = feed.lines.iter().cloned().collect::<Vec<_>>().join("\n");
+        }
+    }
+}
@@ -0,0 +1,322 @@
+// The player's coins and the cosmetics they own and wear, saved between
+// launches in wallet.txt next to the settings (the same `key = value` lines).
+// Streak rewards pay into it for ranked runs, and coins eaten in growth runs
+// for any run the player flew. Coins are spent in the shop (see shop.rs), one
+// cosmetic worn in each slot.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::growth::CoinEaten;
+use crate::streak::{Reward, StreakMilestone};
+use crate::toast::ShowToast;
+use crate::{Bird, GameState, RunRanking};
+
+const GOLDEN_TINT: Color = Color::srgb(1.0, 0.85, 0.35);
+const CRIMSON_TINT: Color = Color::srgb(1.0, 0.45, 0.45);
+const MIDNIGHT_TINT: Color = Color::srgb(0.45, 0.5, 0.9);
+
+// Where a cosmetic is worn; one of each at a time
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Slot {
+    Skin,
+    Trail,
+    PipeColor,
+    DeathEffect,
+}
+
+impl Slot {
+    pub fn label(self) -> &'static str {
+        match self {
+            Slot::Skin => "skin",
+            Slot::Trail => "trail",
+            Slot::PipeColor => "pipe color",
+            Slot::DeathEffect => "death effect",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cosmetic {
+    GoldenFeathers,
+    CrimsonFeathers,
+    MidnightFeathers,
+    SparkTrail,
+    RainbowTrail,
+    MintPipes,
+    RosePipes,
+    FeatherBurst,
+    Confetti,
+}
+
+impl Cosmetic {
+    pub const ALL: [Cosmetic; 9] = [
+        Cosmetic::GoldenFeathers,
+        Cosmetic::CrimsonFeathers,
+        Cosmetic::MidnightFeathers,
+        Cosmetic::SparkTrail,
+        Cosmetic::RainbowTrail,
+        Cosmetic::MintPipes,
+        Cosmetic::RosePipes,
+        Cosmetic::FeatherBurst,
+        Cosmetic::Confetti,
+    ];
+
+    // For the wallet file
+    pub fn name(self) -> &'static str {
+        match self {
+            Cosmetic::GoldenFeathers => "golden_feathers",
+            Cosmetic::CrimsonFeathers => "crimson_feathers",
+            Cosmetic::MidnightFeathers => "midnight_feathers",
+            Cosmetic::SparkTrail => "spark_trail",
+            Cosmetic::RainbowTrail => "rainbow_trail",
+            Cosmetic::MintPipes => "mint_pipes",
+            Cosmetic::RosePipes => "rose_pipes",
+            Cosmetic::FeatherBurst => "feather_burst",
+            Cosmetic::Confetti => "confetti",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Cosmetic::GoldenFeathers => "Golden feathers",
+            Cosmetic::CrimsonFeathers => "Crimson feathers",
+            Cosmetic::MidnightFeathers => "Midnight feathers",
+            Cosmetic::SparkTrail => "Spark trail",
+            Cosmetic::RainbowTrail => "Rainbow trail",
+            Cosmetic::MintPipes => "Mint pipes",
+            Cosmetic::RosePipes => "Rose pipes",
+            Cosmetic::FeatherBurst => "Feather burst",
+            Cosmetic::Confetti => "Confetti",
+        }
+    }
+
+    pub fn slot(self) -> Slot {
+        match self {
+            Cosmetic::GoldenFeathers | Cosmetic::CrimsonFeathers | Cosmetic::MidnightFeathers => {
+                Slot::Skin
+            }
+            Cosmetic::SparkTrail | Cosmetic::RainbowTrail => Slot::Trail,
+            Cosmetic::MintPipes | Cosmetic::RosePipes => Slot::PipeColor,
+            Cosmetic::FeatherBurst | Cosmetic::Confetti => Slot::DeathEffect,
+        }
+    }
+
+    // Coins in the shop; golden feathers can only be earned with a streak
+    pub fn price(self) -> Option<u32> {
+        match self {
+            Cosmetic::GoldenFeathers => None,
+            Cosmetic::CrimsonFeathers => Some(30),
+            Cosmetic::MidnightFeathers => Some(60),
+            Cosmetic::SparkTrail => Some(40),
+            Cosmetic::RainbowTrail => Some(120),
+            Cosmetic::MintPipes | Cosmetic::RosePipes => Some(50),
+            Cosmetic::FeatherBurst => Some(40),
+            Cosmetic::Confetti => Some(80),
+        }
+    }
+
+    // Tint on the bird sprite, for skins
+    fn tint(self) -> Option<Color> {
+        match self {
+            Cosmetic::GoldenFeathers => Some(GOLDEN_TINT),
+            Cosmetic::CrimsonFeathers => Some(CRIMSON_TINT),
+            Cosmetic::MidnightFeathers => Some(MIDNIGHT_TINT),
+            _ => None,
+        }
+    }
+
+    // Pipe body and accent colors, for pipe colors
+    pub fn pipe_colors(self) -> Option<(Color, Color)> {
+        match self {
+            Cosmetic::MintPipes => {
+                Some((Color::srgb(0.35, 0.75, 0.6), Color::srgb(0.05, 0.3, 0.2)))
+            }
+            Cosmetic::RosePipes => Some((Color::srgb(0.85, 0.45, 0.6), Color::WHITE)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Wallet {
+    pub coins: u32,
+    pub cosmetics: Vec<Cosmetic>,
+    // Worn, at most one per slot
+    pub equipped: Vec<Cosmetic>,
+}
+
+impl Wallet {
+    pub fn owns(&self, cosmetic: Cosmetic) -> bool {
+        self.cosmetics.contains(&cosmetic)
+    }
+
+    pub fn wearing(&self, slot: Slot) -> Option<Cosmetic> {
+        self.equipped.iter().copied().find(|c| c.slot() == slot)
+    }
+
+    // Put on an owned cosmetic in place of whatever is in its slot, or take it
+    // off if it's already on
+    pub fn toggle_equipped(&mut self, cosmetic: Cosmetic) {
+        if self.equipped.contains(&cosmetic) {
+            self.equipped.retain(|&c| c != cosmetic);
+        } else if self.owns(cosmetic) {
+            self.equipped.retain(|c| c.slot() != cosmetic.slot());
+            self.equipped.push(cosmetic);
+        }
+    }
+
+    // Spend the coins and put it on; false if it's not for sale, already owned
+    // or too dear
+    pub fn buy(&mut self, cosmetic: Cosmetic) -> bool {
+        let Some(price) = cosmetic.price() else {
+            return false;
+        };
+        if self.owns(cosmetic) || self.coins < price {
+            return false;
+        }
+        self.coins -= price;
+        self.cosmetics.push(cosmetic);
+        self.toggle_equipped(cosmetic);
+        true
+    }
+
+    fn to_text(&self) -> String {
+        let names = |cosmetics: &[Cosmetic]| {
+            cosmetics
+                .iter()
+                .map(|c| c.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        format!(
+            "coins = {}\ncosmetics = {}\nequipped = {}\n",
+            self.coins,
+            names(&self.cosmetics),
+            names(&self.equipped)
+        )
+    }
+
+    fn apply(&mut self, key: &str, value: &str) {
+        let parse = |value: &str| -> Vec<Cosmetic> {
+            value
+                .split(',')
+                .filter_map(|name| Cosmetic::ALL.into_iter().find(|c| c.name() == name.trim()))
+                .collect()
+        };
+        match key {
+            "coins" => self.coins = value.parse().unwrap_or(self.coins),
+            "cosmetics" => self.cosmetics = parse(value),
+            "equipped" => {
+                self.equipped.clear();
+                for cosmetic in parse(value) {
+                    self.toggle_equipped(cosmetic);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub struct WalletPlugin;
+
+impl Plugin for WalletPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load())
+            .add_systems(
+                Update,
+                (pay_streak_rewards, pay_eaten_coins, wear_cosmetics).chain(),
+            )
+            .add_systems(OnExit(GameState::Playing), save_wallet);
+    }
+}
+
+fn wallet_path() -> PathBuf {
+    crate::data_file("wallet.txt")
+}
+
+fn load() -> Wallet {
+    let mut wallet = Wallet::default();
+    let text = std::fs::read_to_string(wallet_path()).unwrap_or_default();
+    let mut has_equipped = false;
+    for line in text.lines() {
+        if let Some((key, value)) = line.split_once('=') {
+            has_equipped |= key.trim() == "equipped";
+            wallet.apply(key.trim(), value.trim());
+        }
+    }
+    // Golden feathers were always worn before there was a shop
+    if !has_equipped && wallet.owns(Cosmetic::GoldenFeathers) {
+        wallet.toggle_equipped(Cosmetic::GoldenFeathers);
+    }
+    wallet
+}
+
+pub fn save(wallet: &Wallet) {
+    let path = wallet_path();
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, wallet.to_text()));
+    if let Err(err) = result {
+        error!("Cannot save wallet: {err}");
+    }
+}
+
+fn pay_streak_rewards(
+    ranking: RunRanking,
+    mut milestones: EventReader<StreakMilestone>,
+    mut wallet: ResMut<Wallet>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for milestone in milestones.read() {
+        if !ranking.ranked() {
+            continue;
+        }
+        let message = match milestone.reward {
+            Reward::Coins(coins) => {
+                wallet.coins += coins;
+                format!("{} in a row! +{coins} coins", milestone.streak)
+            }
+            Reward::GoldenFeathers if !wallet.owns(Cosmetic::GoldenFeathers) => {
+                wallet.cosmetics.push(Cosmetic::GoldenFeathers);
+                wallet.toggle_equipped(Cosmetic::GoldenFeathers);
+                format!("{} in a row! Golden feathers unlocked", milestone.streak)
+            }
+            // Announced by the streak itself, or already owned
+            Reward::Shield | Reward::GoldenFeathers => continue,
+        };
+        toasts.send(ShowToast(message));
+        save(&wallet);
+    }
+}
+
+// Saved when the run is over rather than on every coin
+fn pay_eaten_coins(
+    ranking: RunRanking,
+    mut eaten: EventReader<CoinEaten>,
+    mut wallet: ResMut<Wallet>,
+) {
+    let coins = eaten.read().count() as u32;
+    if coins > 0 && ranking.flown_by_player() {
+        wallet.coins += coins;
+    }
+}
+
+fn save_wallet(wallet: Res<Wallet>) {
+    save(&wallet);
+}
+
+// Skins are applied as each bird spawns, or when the skin changes
+fn wear_cosmetics(wallet: Res<Wallet>, mut birds: Query<(&mut Sprite, Ref<Bird>)>) {
+    let tint = wallet
+        .wearing(Slot::Skin)
+        .and_then(Cosmetic::tint)
+        .unwrap_or(Color::WHITE);
+    for (mut sprite, bird) in &mut birds {
+        if (wallet.is_changed() || bird.is_added()) && sprite.color != tint {
+            sprite.color = tint;
+        }
+    }
+}
@@ -0,0 +1,29 @@
+// The headless environment runs the game with GamePlugin alone, so every
+// resource its systems read has to come from there too
+
+use floopybirb::agent::{Action, Env};
+
+#[test]
+fn env_steps_a_run() {
+    let mut env = Env::new();
+    let mut obs = env.reset(Some(1));
+    assert!(obs.alive);
+    assert_eq!(obs.score, 0);
+    for tick in 0..120 {
+        let action = if tick % 20 == 0 {
+            Action::Flap
+        } else {
+            Action::Idle
+        };
+        obs = env.step(action).observation;
+        if !obs.alive {
+            break;
+        }
+    }
+    assert!(obs.tick > 0);
+
+    // A second run on the same course starts over
+    let obs = env.reset(Some(1));
+    assert!(obs.alive);
+    assert_eq!(obs.score, 0);
+}